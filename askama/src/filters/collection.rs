@@ -0,0 +1,518 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::iter::{Rev, Sum};
+use std::vec;
+
+use crate::error::{Error, Result};
+
+/// Sorts the items in ascending order
+///
+/// Items that can't be compared, like `NaN`, are placed after all other items, see
+/// [`sort_order()`]. The sort is stable, i.e. equal items keep their order.
+///
+/// ```ignore
+/// {% for name in names|sort %}{{ name }}{% endfor %}
+/// ```
+pub fn sort<I>(iter: I) -> Result<Vec<I::Item>>
+where
+    I: Iterator,
+    I::Item: PartialOrd,
+{
+    sort_by(iter, sort_order)
+}
+
+/// Compares two items for sorting
+///
+/// Items that can't be compared with themselves, like `NaN`, are greater than all
+/// other items and equal to each other, so that they are sorted last. This makes the
+/// order total for types like `f64`, which `slice::sort_by()` requires.
+pub fn sort_order<T: PartialOrd + ?Sized>(a: &T, b: &T) -> Ordering {
+    match (a.partial_cmp(a).is_some(), b.partial_cmp(b).is_some()) {
+        (true, true) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => Ordering::Equal,
+    }
+}
+
+/// Sorts the items with a comparison function
+///
+/// In templates, the argument is the name of the attribute to sort by, which may be
+/// a path like `"author.name"`. The code generator turns it into a comparison of the
+/// attribute of the items.
+///
+/// ```ignore
+/// {% for user in users|sort_by("name") %}{{ user.name }}{% endfor %}
+/// ```
+pub fn sort_by<I, F>(iter: I, compare: F) -> Result<Vec<I::Item>>
+where
+    I: Iterator,
+    F: FnMut(&I::Item, &I::Item) -> Ordering,
+{
+    let mut items: Vec<_> = iter.collect();
+    items.sort_by(compare);
+    Ok(items)
+}
+
+/// Reverses the order of the items
+pub fn reverse<I: DoubleEndedIterator>(iter: I) -> Result<Rev<I>> {
+    Ok(iter.rev())
+}
+
+/// Removes the items that are equal to an earlier item
+pub fn unique<I>(iter: I) -> Result<Vec<I::Item>>
+where
+    I: Iterator,
+    I::Item: Hash + Eq,
+{
+    let items: Vec<_> = iter.collect();
+    let keep: Vec<bool> = {
+        let mut seen = HashSet::with_capacity(items.len());
+        items.iter().map(|item| seen.insert(item)).collect()
+    };
+    Ok(items
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(item, keep)| keep.then(|| item))
+        .collect())
+}
+
+/// Returns the first item, or `None` if there are no items
+///
+/// ```ignore
+/// {% if let Some(user) = users|first %}{{ user.name }}{% endif %}
+/// ```
+pub fn first<I: Iterator>(mut iter: I) -> Result<Option<I::Item>> {
+    Ok(iter.next())
+}
+
+/// Returns the last item, or `None` if there are no items
+pub fn last<I: Iterator>(iter: I) -> Result<Option<I::Item>> {
+    Ok(iter.last())
+}
+
+/// Returns the number of items
+pub fn length<I: Iterator>(iter: I) -> Result<usize> {
+    Ok(iter.count())
+}
+
+/// An item of a sequence of numbers that can be summed up
+///
+/// This is implemented for the primitive number types and for references to them.
+pub trait SumItem: Sized {
+    /// The type of the sum
+    type Output: Sum<Self>;
+}
+
+impl<'a, T: Sum<&'a T>> SumItem for &'a T {
+    type Output = T;
+}
+
+macro_rules! impl_sum_item {
+    ($($ty:ty)*) => {$(
+        impl SumItem for $ty {
+            type Output = $ty;
+        }
+    )*};
+}
+
+impl_sum_item!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize f32 f64);
+
+/// Returns the sum of the items
+pub fn sum<I>(iter: I) -> Result<<I::Item as SumItem>::Output>
+where
+    I: Iterator,
+    I::Item: SumItem,
+{
+    Ok(iter.sum())
+}
+
+/// Returns the smallest item, or `None` if there are no items
+///
+/// Items are compared with [`sort_order()`], so the result is the same as `sort|first`:
+/// items that can't be compared, like `NaN`, are only returned if all items are like that.
+/// If several items are the smallest, the first one is returned.
+pub fn min<I>(iter: I) -> Result<Option<I::Item>>
+where
+    I: Iterator,
+    I::Item: PartialOrd,
+{
+    Ok(iter.fold(None, |min, item| match min {
+        Some(min) if sort_order(&item, &min) != Ordering::Less => Some(min),
+        _ => Some(item),
+    }))
+}
+
+/// Returns the largest item, or `None` if there are no items
+///
+/// Items are compared with [`sort_order()`], so the result is the same as `sort|last`:
+/// an item that can't be compared, like `NaN`, is returned if there is one.
+/// If several items are the largest, the last one is returned.
+pub fn max<I>(iter: I) -> Result<Option<I::Item>>
+where
+    I: Iterator,
+    I::Item: PartialOrd,
+{
+    Ok(iter.fold(None, |max, item| match max {
+        Some(max) if sort_order(&item, &max) == Ordering::Less => Some(max),
+        _ => Some(item),
+    }))
+}
+
+/// Maps the items to one of their attributes
+///
+/// In templates, the argument is the name of the attribute, which may be a path like
+/// `"author.name"`. The code generator turns it into a closure returning a reference to
+/// the attribute of an item.
+///
+/// ```ignore
+/// {{ users|map("email")|join(", ") }}
+/// ```
+pub fn map<I, B, F>(iter: I, f: F) -> Result<std::iter::Map<I, F>>
+where
+    I: Iterator,
+    F: FnMut(I::Item) -> B,
+{
+    Ok(iter.map(f))
+}
+
+/// Keeps the items for which the test returns `true`
+///
+/// In templates, the argument is either the name of a `bool` attribute of the items,
+/// the name of an attribute and a value to compare it with, or the path of a function
+/// taking a reference to an item and returning a `bool`.
+///
+/// ```ignore
+/// {% for user in users|select("active") %}{{ user.name }}{% endfor %}
+/// {% for user in users|select("role", "admin") %}{{ user.name }}{% endfor %}
+/// {% for n in numbers|select(self::is_even) %}{{ n }}{% endfor %}
+/// ```
+pub fn select<I, F>(iter: I, test: F) -> Result<std::iter::Filter<I, F>>
+where
+    I: Iterator,
+    F: FnMut(&I::Item) -> bool,
+{
+    Ok(iter.filter(test))
+}
+
+/// Removes the items for which the test returns `true`
+///
+/// This is the opposite of [`select`], and takes the same arguments.
+pub fn reject<I, F>(iter: I, mut test: F) -> Result<impl Iterator<Item = I::Item>>
+where
+    I: Iterator,
+    F: FnMut(&I::Item) -> bool,
+{
+    Ok(iter.filter(move |item| !test(item)))
+}
+
+/// Groups the items by the value of one of their attributes
+///
+/// Returns pairs of a key and the items that have this key, in the order in which the
+/// keys first appear. Sort the items by the same attribute to get the groups in the order
+/// of their keys. In templates, the argument is the name of the attribute, which the code
+/// generator turns into a closure returning a clone of the attribute of an item.
+///
+/// ```ignore
+/// {% for (team, members) in users|sort_by("team")|groupby("team") %}
+///   {{ team }}: {{ members|map("name")|join(", ") }}
+/// {% endfor %}
+/// ```
+pub fn groupby<I, K, F>(iter: I, mut key: F) -> Result<Vec<(K, Vec<I::Item>)>>
+where
+    I: Iterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+{
+    let mut groups: Vec<(K, Vec<I::Item>)> = Vec::new();
+    for item in iter {
+        let k = key(&item);
+        match groups.iter_mut().find(|(group, _)| *group == k) {
+            Some((_, items)) => items.push(item),
+            None => groups.push((k, vec![item])),
+        }
+    }
+    Ok(groups)
+}
+
+/// Splits the items into rows of `size` items
+///
+/// The last row has fewer items, unless a `fill` item is given to pad it with.
+/// The rows are collected while iterating, so the input isn't collected as a whole.
+/// In templates, `fill` is borrowed like the input, so it needs to have the type of the
+/// elements of the input.
+///
+/// ```ignore
+/// {% for row in products|batch(3) %}
+///   <tr>{% for product in row %}<td>{{ product.name }}</td>{% endfor %}</tr>
+/// {% endfor %}
+/// ```
+pub fn batch<I: Iterator>(iter: I, size: usize, fill: Option<I::Item>) -> Result<Batch<I>> {
+    if size == 0 {
+        return Err(Error::Custom("the batch size must not be 0".into()));
+    }
+    Ok(Batch { iter, size, fill })
+}
+
+/// The iterator returned by [`batch`]
+pub struct Batch<I: Iterator> {
+    iter: I,
+    size: usize,
+    fill: Option<I::Item>,
+}
+
+impl<I> Iterator for Batch<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut row: Vec<_> = self.iter.by_ref().take(self.size).collect();
+        if row.is_empty() {
+            return None;
+        }
+        if let Some(fill) = &self.fill {
+            row.resize(self.size, fill.clone());
+        }
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        let rows = |len: usize| len / self.size + usize::from(len % self.size != 0);
+        (rows(lower), upper.map(rows))
+    }
+}
+
+/// Splits the items into `count` columns
+///
+/// The first columns get one more item than the last ones if the items can't be split
+/// evenly, and columns are empty if there are fewer items than columns. If the length of
+/// the input is known in advance, like for a `Vec` or a slice, the columns are collected
+/// while iterating. Otherwise the input is collected first.
+///
+/// ```ignore
+/// {% for column in products|slice(3) %}
+///   <ul>{% for product in column %}<li>{{ product.name }}</li>{% endfor %}</ul>
+/// {% endfor %}
+/// ```
+pub fn slice<I: Iterator>(iter: I, count: usize) -> Result<Slice<I>> {
+    if count == 0 {
+        return Err(Error::Custom("the number of slices must not be 0".into()));
+    }
+    let source = match iter.size_hint() {
+        (lower, Some(upper)) if lower == upper => Source::Iter(iter),
+        _ => Source::Collected(iter.collect::<Vec<_>>().into_iter()),
+    };
+    let len = source.len();
+    Ok(Slice {
+        source,
+        len,
+        count,
+        index: 0,
+    })
+}
+
+/// The iterator returned by [`slice`]
+pub struct Slice<I: Iterator> {
+    source: Source<I>,
+    len: usize,
+    count: usize,
+    index: usize,
+}
+
+enum Source<I: Iterator> {
+    Iter(I),
+    Collected(vec::IntoIter<I::Item>),
+}
+
+impl<I: Iterator> Source<I> {
+    fn len(&self) -> usize {
+        match self {
+            Source::Iter(iter) => iter.size_hint().0,
+            Source::Collected(iter) => iter.len(),
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for Slice<I> {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.count {
+            return None;
+        }
+        let size = self.len / self.count + usize::from(self.index < self.len % self.count);
+        self.index += 1;
+        Some(match &mut self.source {
+            Source::Iter(iter) => iter.take(size).collect(),
+            Source::Collected(iter) => iter.take(size).collect(),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort() {
+        let items = [3, 1, 2];
+        assert_eq!(sort(items.iter()).unwrap(), vec![&1, &2, &3]);
+        let items = [2.5, f64::NAN, 1.0];
+        let sorted = sort(items.iter()).unwrap();
+        assert_eq!(sorted[..2], [&1.0, &2.5]);
+        assert!(sorted[2].is_nan());
+
+        // Longer slices are sorted with a different algorithm, which checks the order.
+        let items: Vec<f64> = (0..40)
+            .map(|i| {
+                if i % 7 == 3 {
+                    f64::NAN
+                } else {
+                    f64::from((i * 13) % 40)
+                }
+            })
+            .collect();
+        let sorted = sort(items.iter()).unwrap();
+        let numbers = sorted.iter().take_while(|x| !x.is_nan()).count();
+        assert_eq!(numbers, 34);
+        assert!(sorted[..numbers].windows(2).all(|w| w[0] < w[1]));
+        assert!(sorted[numbers..].iter().all(|x| x.is_nan()));
+        let items = [("b", 1), ("a", 2), ("b", 0)];
+        assert_eq!(
+            sort_by(items.iter(), |a, b| a.0.cmp(b.0)).unwrap(),
+            vec![&("a", 2), &("b", 1), &("b", 0)]
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        let items = ["a", "b", "c"];
+        assert_eq!(
+            reverse(items.iter()).unwrap().collect::<Vec<_>>(),
+            vec![&"c", &"b", &"a"]
+        );
+    }
+
+    #[test]
+    fn test_unique() {
+        let items = ["a", "b", "a", "c", "b"];
+        assert_eq!(unique(items.iter()).unwrap(), vec![&"a", &"b", &"c"]);
+        assert!(unique(Vec::<i32>::new().into_iter()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_first_last_length() {
+        let items = [1, 2, 3];
+        assert_eq!(first(items.iter()).unwrap(), Some(&1));
+        assert_eq!(last(items.iter()).unwrap(), Some(&3));
+        assert_eq!(length(items.iter()).unwrap(), 3);
+        assert_eq!(first([0; 0].iter()).unwrap(), None);
+        assert_eq!(last([0; 0].iter()).unwrap(), None);
+        assert_eq!(length([0; 0].iter()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_sum_min_max() {
+        let items = [3, 1, 2];
+        assert_eq!(sum(items.iter()).unwrap(), 6);
+        assert_eq!(sum(items.into_iter()).unwrap(), 6);
+        assert_eq!(sum([0.5, 0.25].iter()).unwrap(), 0.75);
+        assert_eq!(min(items.iter()).unwrap(), Some(&1));
+        assert_eq!(max(items.iter()).unwrap(), Some(&3));
+        assert_eq!(max([1.5, 0.5].iter()).unwrap(), Some(&1.5));
+        assert_eq!(min([0; 0].iter()).unwrap(), None);
+        assert_eq!(max(["b", "c", "a"].iter()).unwrap(), Some(&"c"));
+    }
+
+    #[test]
+    fn test_min_max_nan() {
+        for items in [
+            [f64::NAN, 2.0, 1.0],
+            [2.0, f64::NAN, 1.0],
+            [2.0, 1.0, f64::NAN],
+        ] {
+            let sorted = sort(items.iter()).unwrap();
+            assert_eq!(min(items.iter()).unwrap(), Some(&1.0));
+            assert_eq!(
+                min(items.iter()).unwrap(),
+                first(sorted.iter().copied()).unwrap()
+            );
+            assert!(max(items.iter()).unwrap().unwrap().is_nan());
+            assert!(last(sorted.iter().copied()).unwrap().unwrap().is_nan());
+        }
+        assert!(min([f64::NAN, f64::NAN].iter()).unwrap().unwrap().is_nan());
+        assert_eq!(max([1.0, 2.0].iter()).unwrap(), Some(&2.0));
+    }
+
+    #[test]
+    fn test_map_select_reject() {
+        let items = [(1, "a"), (2, "b"), (3, "c")];
+        assert_eq!(
+            map(items.iter(), |item| &item.1)
+                .unwrap()
+                .collect::<Vec<_>>(),
+            vec![&"a", &"b", &"c"]
+        );
+        assert_eq!(
+            select(items.iter(), |item| item.0 % 2 == 1)
+                .unwrap()
+                .collect::<Vec<_>>(),
+            vec![&(1, "a"), &(3, "c")]
+        );
+        assert_eq!(
+            reject(items.iter(), |item| item.0 % 2 == 1)
+                .unwrap()
+                .collect::<Vec<_>>(),
+            vec![&(2, "b")]
+        );
+    }
+
+    #[test]
+    fn test_groupby() {
+        let items = [("x", 1), ("y", 2), ("x", 3)];
+        assert_eq!(
+            groupby(items.iter(), |item| item.0).unwrap(),
+            vec![("x", vec![&("x", 1), &("x", 3)]), ("y", vec![&("y", 2)])]
+        );
+        assert!(groupby([0; 0].iter(), |n| **n).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_batch() {
+        let items = [1, 2, 3, 4, 5];
+        let rows: Vec<_> = batch(items.iter(), 2, None).unwrap().collect();
+        assert_eq!(rows, vec![vec![&1, &2], vec![&3, &4], vec![&5]]);
+        let rows: Vec<_> = batch(items.iter(), 3, Some(&0)).unwrap().collect();
+        assert_eq!(rows, vec![vec![&1, &2, &3], vec![&4, &5, &0]]);
+        assert_eq!(
+            batch(items.iter(), 2, None).unwrap().size_hint(),
+            (3, Some(3))
+        );
+        assert_eq!(batch([0; 0].iter(), 2, None).unwrap().count(), 0);
+        assert!(batch(items.iter(), 0, None).is_err());
+    }
+
+    #[test]
+    fn test_slice() {
+        let items = [1, 2, 3, 4, 5];
+        let columns: Vec<_> = slice(items.iter(), 2).unwrap().collect();
+        assert_eq!(columns, vec![vec![&1, &2, &3], vec![&4, &5]]);
+        let columns: Vec<_> = slice(items.iter().filter(|n| **n != 3), 3)
+            .unwrap()
+            .collect();
+        assert_eq!(columns, vec![vec![&1, &2], vec![&4], vec![&5]]);
+        let columns: Vec<_> = slice(items[..1].iter(), 3).unwrap().collect();
+        assert_eq!(columns, vec![vec![&1], vec![], vec![]]);
+        assert!(slice(items.iter(), 0).is_err());
+    }
+}
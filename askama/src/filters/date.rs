@@ -0,0 +1,341 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+
+/// A date, time or date and time that the date filters can format
+///
+/// This is implemented for the date and time types of the `chrono` crate
+/// (requires the `chrono` feature) and of the `time` crate (requires the `time` feature).
+pub trait FormatDateTime {
+    /// Formats the value according to a `strftime`-like format string
+    fn strftime(&self, format: &str) -> Result<String>;
+
+    /// Formats the value according to ISO 8601
+    fn isoformat(&self) -> Result<String>;
+}
+
+/// A date and time with a timezone, i.e. a point in time
+pub trait Timestamp {
+    /// The number of seconds since the Unix epoch
+    fn unix_timestamp(&self) -> i64;
+}
+
+impl<T: FormatDateTime + ?Sized> FormatDateTime for &T {
+    fn strftime(&self, format: &str) -> Result<String> {
+        T::strftime(self, format)
+    }
+
+    fn isoformat(&self) -> Result<String> {
+        T::isoformat(self)
+    }
+}
+
+impl<T: Timestamp + ?Sized> Timestamp for &T {
+    fn unix_timestamp(&self) -> i64 {
+        T::unix_timestamp(self)
+    }
+}
+
+/// Formats a date or time according to a `strftime`-like format string
+///
+/// ```ignore
+/// {{ post.published|date("%Y-%m-%d") }}
+/// ```
+///
+/// The format string is that of [`chrono`](https://docs.rs/chrono/0.4/chrono/format/strftime/index.html)
+/// for its types, and that of [`time`](https://docs.rs/time/0.3/time/format_description/parse/fn.parse_strftime_borrowed.html)
+/// for its types. A format that's invalid, or that uses fields the value doesn't have,
+/// e.g. `%H` for a date, is an error.
+pub fn date<T: FormatDateTime>(value: T, format: &str) -> Result<String> {
+    value.strftime(format)
+}
+
+/// Formats a date and time as `2023-01-02 03:04:05`
+pub fn datetime<T: FormatDateTime>(value: T) -> Result<String> {
+    value.strftime("%Y-%m-%d %H:%M:%S")
+}
+
+/// Formats a date or time according to ISO 8601, e.g. `2023-01-02T03:04:05+01:00`
+///
+/// Dates and times without a timezone are formatted without an offset. Fractions of
+/// seconds are only written if they aren't zero.
+pub fn isoformat<T: FormatDateTime>(value: T) -> Result<String> {
+    value.isoformat()
+}
+
+/// Formats the time passed since a point in time, e.g. `3 days, 2 hours`
+///
+/// The time is given in the largest unit, from years to minutes, and in the next
+/// smaller unit unless that is zero. Points in time in the future give `0 minutes`.
+pub fn timesince<T: Timestamp>(value: T) -> Result<String> {
+    Ok(relative(now() - value.unix_timestamp()))
+}
+
+/// Formats the time left until a point in time, e.g. `3 days, 2 hours`
+///
+/// This is the reverse of [`timesince`]. Points in time in the past give `0 minutes`.
+pub fn timeuntil<T: Timestamp>(value: T) -> Result<String> {
+    Ok(relative(value.unix_timestamp() - now()))
+}
+
+fn now() -> i64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+fn relative(seconds: i64) -> String {
+    const UNITS: [(i64, &str, &str); 6] = [
+        (365 * 24 * 60 * 60, "year", "years"),
+        (30 * 24 * 60 * 60, "month", "months"),
+        (7 * 24 * 60 * 60, "week", "weeks"),
+        (24 * 60 * 60, "day", "days"),
+        (60 * 60, "hour", "hours"),
+        (60, "minute", "minutes"),
+    ];
+    let unit = |count: i64, i: usize| {
+        let (_, one, many) = UNITS[i];
+        format!("{} {}", count, if count == 1 { one } else { many })
+    };
+
+    let i = match UNITS.iter().position(|(secs, _, _)| seconds >= *secs) {
+        Some(i) => i,
+        None => return unit(0, UNITS.len() - 1),
+    };
+    let count = seconds / UNITS[i].0;
+    let mut s = unit(count, i);
+    if let Some((secs, _, _)) = UNITS.get(i + 1) {
+        let rest = (seconds - count * UNITS[i].0) / secs;
+        if rest > 0 {
+            s.push_str(", ");
+            s.push_str(&unit(rest, i + 1));
+        }
+    }
+    s
+}
+
+fn invalid_format(format: &str) -> Error {
+    Error::Custom(format!("invalid date format {format:?}").into())
+}
+
+#[cfg(feature = "chrono")]
+mod chrono {
+    use std::fmt::Write;
+
+    use dep_chrono::format::{Item, StrftimeItems};
+    use dep_chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+
+    use super::{invalid_format, FormatDateTime, Timestamp};
+    use crate::error::{Error, Result};
+
+    fn items(format: &str) -> Result<StrftimeItems<'_>> {
+        let items = StrftimeItems::new(format);
+        match items.clone().any(|item| matches!(item, Item::Error)) {
+            true => Err(invalid_format(format)),
+            false => Ok(items),
+        }
+    }
+
+    macro_rules! impl_naive {
+        ($($ty:ty => $iso:literal,)*) => {$(
+            impl FormatDateTime for $ty {
+                fn strftime(&self, format: &str) -> Result<String> {
+                    let mut s = String::new();
+                    write!(s, "{}", self.format_with_items(items(format)?)).map_err(Error::Fmt)?;
+                    Ok(s)
+                }
+
+                fn isoformat(&self) -> Result<String> {
+                    Ok(self.format($iso).to_string())
+                }
+            }
+        )*};
+    }
+
+    impl_naive! {
+        NaiveDate => "%Y-%m-%d",
+        NaiveTime => "%H:%M:%S%.f",
+        NaiveDateTime => "%Y-%m-%dT%H:%M:%S%.f",
+    }
+
+    impl<Tz: TimeZone> FormatDateTime for DateTime<Tz>
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        fn strftime(&self, format: &str) -> Result<String> {
+            let mut s = String::new();
+            write!(s, "{}", self.format_with_items(items(format)?)).map_err(Error::Fmt)?;
+            Ok(s)
+        }
+
+        fn isoformat(&self) -> Result<String> {
+            Ok(self.to_rfc3339())
+        }
+    }
+
+    impl<Tz: TimeZone> Timestamp for DateTime<Tz> {
+        fn unix_timestamp(&self) -> i64 {
+            self.timestamp()
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time {
+    use dep_time::format_description::parse_strftime_borrowed;
+    use dep_time::{Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+    use super::{invalid_format, FormatDateTime, Timestamp};
+    use crate::error::{Error, Result};
+
+    // Formats like chrono's `%H:%M:%S%.f`
+    fn iso_time(time: Time) -> String {
+        let (h, m, s, nanos) = time.as_hms_nano();
+        let mut iso = format!("{h:02}:{m:02}:{s:02}");
+        if nanos == 0 {
+        } else if nanos % 1_000_000 == 0 {
+            iso.push_str(&format!(".{:03}", nanos / 1_000_000));
+        } else if nanos % 1_000 == 0 {
+            iso.push_str(&format!(".{:06}", nanos / 1_000));
+        } else {
+            iso.push_str(&format!(".{nanos:09}"));
+        }
+        iso
+    }
+
+    // Formats like chrono's `DateTime::to_rfc3339()`, which writes UTC as `+00:00`
+    fn iso_offset(offset: UtcOffset) -> String {
+        let (h, m, _) = offset.as_hms();
+        let sign = if offset.is_negative() { '-' } else { '+' };
+        format!("{sign}{:02}:{:02}", h.unsigned_abs(), m.unsigned_abs())
+    }
+
+    macro_rules! impl_format {
+        ($($ty:ty => $iso:expr,)*) => {$(
+            impl FormatDateTime for $ty {
+                fn strftime(&self, format: &str) -> Result<String> {
+                    let items = parse_strftime_borrowed(format).map_err(|_| invalid_format(format))?;
+                    self.format(&*items).map_err(|e| Error::Custom(e.into()))
+                }
+
+                fn isoformat(&self) -> Result<String> {
+                    let iso: fn(&$ty) -> Result<String> = $iso;
+                    iso(self)
+                }
+            }
+        )*};
+    }
+
+    impl_format! {
+        Date => |d| d.strftime("%Y-%m-%d"),
+        Time => |t| Ok(iso_time(*t)),
+        PrimitiveDateTime => |dt| Ok(format!("{}T{}", dt.date().isoformat()?, iso_time(dt.time()))),
+        OffsetDateTime => |dt| {
+            let date = dt.date().isoformat()?;
+            Ok(format!("{date}T{}{}", iso_time(dt.time()), iso_offset(dt.offset())))
+        },
+    }
+
+    impl Timestamp for OffsetDateTime {
+        fn unix_timestamp(&self) -> i64 {
+            OffsetDateTime::unix_timestamp(*self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative() {
+        assert_eq!(relative(-5), "0 minutes");
+        assert_eq!(relative(59), "0 minutes");
+        assert_eq!(relative(60), "1 minute");
+        assert_eq!(relative(2 * 60 * 60 + 59), "2 hours");
+        assert_eq!(relative(2 * 60 * 60 + 60), "2 hours, 1 minute");
+        assert_eq!(relative(3 * 24 * 60 * 60 + 2 * 60 * 60), "3 days, 2 hours");
+        assert_eq!(relative(8 * 24 * 60 * 60), "1 week, 1 day");
+        assert_eq!(relative(400 * 24 * 60 * 60), "1 year, 1 month");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono() {
+        use dep_chrono::{Duration, FixedOffset, NaiveDate, TimeZone, Utc};
+
+        let day = NaiveDate::from_ymd_opt(2023, 1, 2).unwrap();
+        let naive = day.and_hms_milli_opt(3, 4, 5, 600).unwrap();
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        let aware = offset.from_local_datetime(&naive).unwrap();
+
+        assert_eq!(date(day, "%d.%m.%Y").unwrap(), "02.01.2023");
+        assert_eq!(
+            date(aware, "%Y-%m-%d %H:%M %:z").unwrap(),
+            "2023-01-02 03:04 +01:00"
+        );
+        assert_eq!(
+            date(aware.with_timezone(&Utc), "%H:%M %Z").unwrap(),
+            "02:04 UTC"
+        );
+        assert!(date(day, "%Y-%m-%d %H:%M").is_err());
+        assert!(date(day, "%Y-%Q").is_err());
+        assert_eq!(datetime(naive).unwrap(), "2023-01-02 03:04:05");
+        assert_eq!(isoformat(day).unwrap(), "2023-01-02");
+        assert_eq!(isoformat(naive.time()).unwrap(), "03:04:05.600");
+        assert_eq!(isoformat(naive).unwrap(), "2023-01-02T03:04:05.600");
+        assert_eq!(isoformat(aware).unwrap(), "2023-01-02T03:04:05.600+01:00");
+        assert_eq!(
+            isoformat(Utc.from_utc_datetime(&naive)).unwrap(),
+            "2023-01-02T03:04:05.600+00:00"
+        );
+
+        let now = Utc.timestamp_opt(now(), 0).unwrap();
+        let past = now - Duration::days(3) - Duration::hours(2) - Duration::seconds(30);
+        assert_eq!(
+            timesince(past.with_timezone(&offset)).unwrap(),
+            "3 days, 2 hours"
+        );
+        assert_eq!(timeuntil(past).unwrap(), "0 minutes");
+        let future = now + Duration::weeks(2) + Duration::seconds(30);
+        assert_eq!(timeuntil(future).unwrap(), "2 weeks");
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time() {
+        use dep_time::{Date, Duration, Month, OffsetDateTime, UtcOffset};
+
+        let day = Date::from_calendar_date(2023, Month::January, 2).unwrap();
+        let primitive = day.with_hms_milli(3, 4, 5, 600).unwrap();
+        let offset = UtcOffset::from_hms(1, 0, 0).unwrap();
+        let aware = primitive.assume_offset(offset);
+
+        assert_eq!(date(day, "%d.%m.%Y").unwrap(), "02.01.2023");
+        assert_eq!(
+            date(aware, "%Y-%m-%d %H:%M %z").unwrap(),
+            "2023-01-02 03:04 +0100"
+        );
+        assert!(date(day, "%Y-%m-%d %H:%M").is_err());
+        assert!(date(day, "%Y-%Q").is_err());
+        assert_eq!(datetime(primitive).unwrap(), "2023-01-02 03:04:05");
+        assert_eq!(isoformat(day).unwrap(), "2023-01-02");
+        assert_eq!(isoformat(primitive.time()).unwrap(), "03:04:05.600");
+        assert_eq!(isoformat(primitive).unwrap(), "2023-01-02T03:04:05.600");
+        assert_eq!(isoformat(aware).unwrap(), "2023-01-02T03:04:05.600+01:00");
+        assert_eq!(
+            isoformat(primitive.assume_utc()).unwrap(),
+            "2023-01-02T03:04:05.600+00:00"
+        );
+
+        let past = OffsetDateTime::now_utc() - Duration::days(3) - Duration::hours(2);
+        assert_eq!(
+            timesince(past.to_offset(offset)).unwrap(),
+            "3 days, 2 hours"
+        );
+        assert_eq!(timeuntil(past).unwrap(), "0 minutes");
+        let future = OffsetDateTime::now_utc() + Duration::weeks(2) + Duration::seconds(30);
+        assert_eq!(timeuntil(future).unwrap(), "2 weeks");
+    }
+}
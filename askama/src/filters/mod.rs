@@ -7,6 +7,17 @@
 
 use std::fmt::{self, Write};
 
+mod collection;
+pub use self::collection::{
+    batch, first, groupby, last, length, map, max, min, reject, reverse, select, slice, sort,
+    sort_by, sort_order, sum, unique, Batch, Slice, SumItem,
+};
+
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod date;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub use self::date::{date, datetime, isoformat, timesince, timeuntil, FormatDateTime, Timestamp};
+
 #[cfg(feature = "serde-json")]
 mod json;
 #[cfg(feature = "serde-json")]
@@ -17,8 +28,21 @@ mod yaml;
 #[cfg(feature = "serde-yaml")]
 pub use self::yaml::yaml;
 
+mod number;
+pub use self::number::{currency, numberformat, percent, FormatNumber};
+
+// The `ammonia` feature needs a newer Rust than the rest of askama, see the README.
+#[cfg(feature = "ammonia")]
+#[clippy::msrv = "1.70"]
+mod sanitize;
+#[cfg(feature = "ammonia")]
+pub use self::sanitize::{sanitize, Allowlist};
+
+mod option;
+pub use self::option::{default, unwrap_or_empty, DefaultValue, OrDefault};
+
 #[allow(unused_imports)]
-use crate::error::Error::Fmt;
+use crate::error::Error::{self, Fmt};
 use askama_escape::{Escaper, MarkupDisplay};
 #[cfg(feature = "humansize")]
 use dep_humansize::{format_size_i, ToF64, DECIMAL};
@@ -27,6 +51,9 @@ use dep_num_traits::{cast::NumCast, Signed};
 #[cfg(feature = "percent-encoding")]
 use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 
+#[cfg(feature = "unicode-width")]
+use dep_unicode_width::UnicodeWidthStr;
+
 use super::Result;
 
 #[cfg(feature = "percent-encoding")]
@@ -226,6 +253,131 @@ pub fn truncate<T: fmt::Display>(s: T, len: usize) -> Result<String> {
     Ok(s)
 }
 
+/// Limit the number of words, appends '...' if truncated
+///
+/// The words are separated by single spaces in the result.
+pub fn truncatewords<T: fmt::Display>(s: T, count: usize) -> Result<String> {
+    let s = s.to_string();
+    let mut words = s.split_whitespace();
+    let mut rv = String::with_capacity(s.len());
+    for word in words.by_ref().take(count) {
+        if !rv.is_empty() {
+            rv.push(' ');
+        }
+        rv.push_str(word);
+    }
+    if words.next().is_some() {
+        rv.push_str("...");
+    }
+    Ok(rv)
+}
+
+/// Limit the number of characters of HTML text, appends '...' if truncated
+///
+/// Tags don't count as characters, and an entity like `&amp;` counts as a single
+/// character. Tags that are open at the end of the truncated text are closed, so the
+/// result can be marked as `safe` if the input is.
+pub fn truncatechars_html<T: fmt::Display>(s: T, len: usize) -> Result<String> {
+    let s = s.to_string();
+    let mut rv = String::with_capacity(s.len());
+    let mut open: Vec<&str> = Vec::new();
+    let mut count = 0;
+    // The state when the limit was reached, to go back to if there is more text.
+    let mut limit = None;
+    let mut rest = s.as_str();
+    while let Some(c) = rest.chars().next() {
+        let token = match c {
+            '<' => html_tag(rest),
+            '&' => match rest.find(';') {
+                Some(end)
+                    if rest[1..end]
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'#') =>
+                {
+                    &rest[..=end]
+                }
+                _ => &rest[..1],
+            },
+            _ => &rest[..c.len_utf8()],
+        };
+        rest = &rest[token.len()..];
+
+        if c == '<' && token.len() > 1 {
+            if let Some(name) = token.strip_prefix("</") {
+                let name = tag_name(name);
+                if let Some(pos) = open.iter().rposition(|tag| tag.eq_ignore_ascii_case(name)) {
+                    open.truncate(pos);
+                }
+            } else if !token.starts_with("<!") && !token.ends_with("/>") {
+                let name = tag_name(&token[1..]);
+                if !VOID_ELEMENTS
+                    .iter()
+                    .any(|void| void.eq_ignore_ascii_case(name))
+                {
+                    open.push(name);
+                }
+            }
+            rv.push_str(token);
+            continue;
+        }
+
+        if count == len {
+            if let Some((rv_len, limit_open)) = limit {
+                rv.truncate(rv_len);
+                open = limit_open;
+            }
+            rv.push_str("...");
+            for tag in open.iter().rev() {
+                write!(rv, "</{tag}>")?;
+            }
+            return Ok(rv);
+        }
+        rv.push_str(token);
+        count += 1;
+        if count == len {
+            limit = Some((rv.len(), open.clone()));
+        }
+    }
+    Ok(rv)
+}
+
+// Elements that have no closing tag.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+// Elements that don't separate words.
+const INLINE_ELEMENTS: [&str; 23] = [
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "dfn", "em", "i", "kbd", "mark", "q",
+    "s", "samp", "small", "span", "strong", "sub", "sup", "u", "var",
+];
+
+// Returns the tag or comment at the start of `s`, or just the `<` if it isn't one.
+fn html_tag(s: &str) -> &str {
+    if s.starts_with("<!--") {
+        return match s.find("-->") {
+            Some(end) => &s[..end + 3],
+            None => s,
+        };
+    }
+    match s[1..].chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '/' || c == '!' => match s.find('>') {
+            Some(end) => &s[..=end],
+            None => &s[..1],
+        },
+        _ => &s[..1],
+    }
+}
+
+// Returns the name at the start of a tag, without the `<` or `</`.
+fn tag_name(s: &str) -> &str {
+    let end = s
+        .find(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+        .unwrap_or(s.len());
+    &s[..end]
+}
+
 /// Indent lines with `width` spaces
 pub fn indent<T: fmt::Display>(s: T, width: usize) -> Result<String> {
     let s = s.to_string();
@@ -245,6 +397,61 @@ pub fn indent<T: fmt::Display>(s: T, width: usize) -> Result<String> {
     Ok(indented)
 }
 
+/// Wrap lines that are longer than `width` columns
+///
+/// Lines are wrapped at whitespace, so words that are longer than `width` get their own
+/// line. Existing line breaks, the indentation of the lines and the whitespace between
+/// words that stay on the same line are kept. The whitespace at a new line break is removed.
+///
+/// With the `unicode-width` feature enabled, the width of the text is measured in columns,
+/// so wide characters like CJK ideographs count as two. Otherwise every character counts
+/// as one column, which is exact for ASCII text.
+pub fn wordwrap<T: fmt::Display>(s: T, width: usize) -> Result<String> {
+    let s = s.to_string();
+    let mut wrapped = String::with_capacity(s.len());
+    for (i, line) in s.split('\n').enumerate() {
+        if i > 0 {
+            wrapped.push('\n');
+        }
+        let mut rest = line.trim_start();
+        let indent = &line[..line.len() - rest.len()];
+        wrapped.push_str(indent);
+        let mut line_width = text_width(indent);
+        while !rest.is_empty() {
+            // The whitespace before the next word, which is empty for the first word.
+            let (space, tail) = rest.split_at(rest.len() - rest.trim_start().len());
+            if tail.is_empty() {
+                wrapped.push_str(space);
+                break;
+            }
+            let (word, tail) = tail.split_at(tail.find(char::is_whitespace).unwrap_or(tail.len()));
+            let word_width = text_width(word);
+            let space_width = text_width(space);
+            if !space.is_empty() && line_width + space_width + word_width > width {
+                wrapped.push('\n');
+                line_width = 0;
+            } else {
+                wrapped.push_str(space);
+                line_width += space_width;
+            }
+            wrapped.push_str(word);
+            line_width += word_width;
+            rest = tail;
+        }
+    }
+    Ok(wrapped)
+}
+
+#[cfg(feature = "unicode-width")]
+fn text_width(s: &str) -> usize {
+    s.width()
+}
+
+#[cfg(not(feature = "unicode-width"))]
+fn text_width(s: &str) -> usize {
+    s.chars().count()
+}
+
 #[cfg(feature = "num-traits")]
 /// Casts number to f64
 pub fn into_f64<T>(number: T) -> Result<f64>
@@ -307,6 +514,21 @@ pub fn capitalize<T: fmt::Display>(s: T) -> Result<String> {
     }
 }
 
+/// Title case a value. The first character of every word will be uppercase, all others lowercase.
+pub fn title<T: fmt::Display>(s: T) -> Result<String> {
+    let s = s.to_string();
+    let mut rv = String::with_capacity(s.len());
+    let mut word_start = true;
+    for c in s.chars() {
+        match word_start {
+            true => rv.extend(c.to_uppercase()),
+            false => rv.extend(c.to_lowercase()),
+        }
+        word_start = c.is_whitespace() || matches!(c, '-' | '(' | '[' | '{' | '<');
+    }
+    Ok(rv)
+}
+
 /// Centers the value in a field of a given width
 pub fn center(src: &dyn fmt::Display, dst_len: usize) -> Result<String> {
     let src = src.to_string();
@@ -334,6 +556,220 @@ pub fn center(src: &dyn fmt::Display, dst_len: usize) -> Result<String> {
     }
 }
 
+/// Remove HTML tags and comments, and decode HTML entities
+///
+/// Like in Jinja, consecutive whitespace is collapsed into a single space.
+/// As the result is plain text, it is escaped again in HTML templates.
+pub fn striptags<T: fmt::Display>(s: T) -> Result<String> {
+    let s = s.to_string();
+    let mut text = String::with_capacity(s.len());
+    let mut rest = s.as_str();
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let tag = html_tag(&rest[start..]);
+        if tag.len() == 1 {
+            text.push('<');
+        } else if !tag.starts_with("<!") {
+            // Tags separate words, as in `<p>one</p><p>two</p>`, unless they are inline.
+            let name = tag_name(tag.trim_start_matches(['<', '/']));
+            if !INLINE_ELEMENTS
+                .iter()
+                .any(|inline| inline.eq_ignore_ascii_case(name))
+            {
+                text.push(' ');
+            }
+        }
+        rest = &rest[start + tag.len()..];
+    }
+    text.push_str(rest);
+
+    let text = decode_entities(&text);
+    let mut rv = String::with_capacity(text.len());
+    for word in text.split_whitespace() {
+        if !rv.is_empty() {
+            rv.push(' ');
+        }
+        rv.push_str(word);
+    }
+    Ok(rv)
+}
+
+// Decodes numeric entities and the named ones that are common in HTML text.
+fn decode_entities(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        rv.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                entity => {
+                    let code = match entity.strip_prefix('#')? {
+                        hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16),
+                        dec => dec.parse(),
+                    };
+                    char::from_u32(code.ok()?)?
+                }
+            };
+            Some((c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                rv.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                rv.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rv.push_str(rest);
+    rv
+}
+
+/// Replace all occurrences of `from` with `to`
+pub fn replace<T, F, R>(s: T, from: F, to: R) -> Result<String>
+where
+    T: fmt::Display,
+    F: AsRef<str>,
+    R: AsRef<str>,
+{
+    let s = s.to_string();
+    Ok(s.replace(from.as_ref(), to.as_ref()))
+}
+
+/// Split a string at every occurrence of `separator`
+///
+/// Returns an iterator over the parts, to be used in a `for` loop or with another filter.
+pub fn split<T, S>(s: T, separator: S) -> Result<std::vec::IntoIter<String>>
+where
+    T: fmt::Display,
+    S: AsRef<str>,
+{
+    let separator = separator.as_ref();
+    if separator.is_empty() {
+        return Err(Error::Custom(
+            "the separator of split must not be empty".into(),
+        ));
+    }
+    let s = s.to_string();
+    let parts: Vec<String> = s.split(separator).map(str::to_owned).collect();
+    Ok(parts.into_iter())
+}
+
+#[cfg(feature = "slugify")]
+/// Turn a string into a slug for URLs
+///
+/// Unicode characters are transliterated to ASCII, e.g. `"Crème Brûlée"` becomes
+/// `"creme-brulee"`. Everything but letters and digits is replaced by single dashes.
+pub fn slugify<T: fmt::Display>(s: T) -> Result<String> {
+    let s = deunicode::deunicode(&s.to_string());
+    let mut slug = String::with_capacity(s.len());
+    for word in s.split(|c: char| !c.is_ascii_alphanumeric()) {
+        if word.is_empty() {
+            continue;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.extend(word.chars().map(|c| c.to_ascii_lowercase()));
+    }
+    Ok(slug)
+}
+
+/// Turn URLs and email addresses into links
+///
+/// Words that start with `http://`, `https://` or `www.`, and email addresses are wrapped
+/// in `<a>` tags. Punctuation around them, like in `(see https://example.com).`, is not
+/// part of the link. Askama will automatically insert the first (`Escaper`) argument, which
+/// escapes the text and the links, so the result is marked as safe.
+pub fn urlize<E, T>(e: E, s: T) -> Result<MarkupDisplay<E, String>>
+where
+    E: Escaper,
+    T: fmt::Display,
+{
+    let s = s.to_string();
+    let mut rv = String::with_capacity(s.len());
+    let mut rest = s.as_str();
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        urlize_word(&e, &mut rv, &rest[..word_end])?;
+        rest = &rest[word_end..];
+        let space_end = rest
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(rest.len());
+        rv.push_str(&rest[..space_end]);
+        rest = &rest[space_end..];
+    }
+    Ok(MarkupDisplay::new_safe(rv, e))
+}
+
+fn urlize_word<E: Escaper>(e: &E, rv: &mut String, word: &str) -> fmt::Result {
+    let start = word
+        .find(|c: char| !matches!(c, '(' | '<' | '[' | '"' | '\''))
+        .unwrap_or(word.len());
+    let mut end = word.len();
+    while end > start {
+        let link = &word[start..end];
+        let trailing = match link.chars().next_back() {
+            Some('.' | ',' | ':' | ';' | '!' | '?' | '>' | ']' | '"' | '\'') => true,
+            // Keep the parenthesis of URLs like `https://en.wikipedia.org/wiki/Rust_(film)`.
+            Some(')') => link.matches('(').count() < link.matches(')').count(),
+            _ => false,
+        };
+        if !trailing {
+            break;
+        }
+        end -= 1;
+    }
+
+    let link = &word[start..end];
+    let href = if ["http://", "https://"]
+        .iter()
+        .any(|scheme| link.len() > scheme.len() && link.starts_with(scheme))
+    {
+        link.to_owned()
+    } else if link.len() > 4 && link.starts_with("www.") {
+        format!("https://{link}")
+    } else if is_email(link) {
+        format!("mailto:{link}")
+    } else {
+        return e.write_escaped(rv, word);
+    };
+
+    e.write_escaped(&mut *rv, &word[..start])?;
+    rv.push_str("<a href=\"");
+    e.write_escaped(&mut *rv, &href)?;
+    rv.push_str("\" rel=\"noopener\">");
+    e.write_escaped(&mut *rv, link)?;
+    rv.push_str("</a>");
+    e.write_escaped(rv, &word[end..])
+}
+
+fn is_email(s: &str) -> bool {
+    let (local, domain) = match s.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-'))
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '.' | '-'))
+}
+
 /// Count the words in that string
 pub fn wordcount<T: fmt::Display>(s: T) -> Result<usize> {
     let s = s.to_string();
@@ -341,6 +777,40 @@ pub fn wordcount<T: fmt::Display>(s: T) -> Result<usize> {
     Ok(s.split_whitespace().count())
 }
 
+#[cfg(feature = "markdown")]
+const DEFAULT_MARKDOWN_OPTIONS: comrak::ComrakOptions = comrak::ComrakOptions {
+    extension: comrak::ComrakExtensionOptions {
+        strikethrough: true,
+        tagfilter: true,
+        table: true,
+        autolink: true,
+        // default:
+        tasklist: false,
+        superscript: false,
+        header_ids: None,
+        footnotes: false,
+        description_lists: false,
+        front_matter_delimiter: None,
+    },
+    parse: comrak::ComrakParseOptions {
+        // default:
+        smart: false,
+        default_info_string: None,
+        relaxed_tasklist_matching: false,
+    },
+    render: comrak::ComrakRenderOptions {
+        escape: true,
+        // default:
+        hardbreaks: false,
+        github_pre_lang: false,
+        full_info_string: false,
+        width: 0,
+        unsafe_: false,
+        list_style: comrak::ListStyleType::Dash,
+        sourcepos: false,
+    },
+};
+
 #[cfg(feature = "markdown")]
 pub fn markdown<E, S>(
     e: E,
@@ -351,48 +821,66 @@ where
     E: Escaper,
     S: AsRef<str>,
 {
-    use comrak::{
-        markdown_to_html, ComrakExtensionOptions, ComrakOptions, ComrakParseOptions,
-        ComrakRenderOptions, ListStyleType,
-    };
-
-    const DEFAULT_OPTIONS: ComrakOptions = ComrakOptions {
-        extension: ComrakExtensionOptions {
-            strikethrough: true,
-            tagfilter: true,
-            table: true,
-            autolink: true,
-            // default:
-            tasklist: false,
-            superscript: false,
-            header_ids: None,
-            footnotes: false,
-            description_lists: false,
-            front_matter_delimiter: None,
-        },
-        parse: ComrakParseOptions {
-            // default:
-            smart: false,
-            default_info_string: None,
-            relaxed_tasklist_matching: false,
-        },
-        render: ComrakRenderOptions {
-            escape: true,
-            // default:
-            hardbreaks: false,
-            github_pre_lang: false,
-            full_info_string: false,
-            width: 0,
-            unsafe_: false,
-            list_style: ListStyleType::Dash,
-            sourcepos: false,
-        },
-    };
+    let s = comrak::markdown_to_html(s.as_ref(), options.unwrap_or(&DEFAULT_MARKDOWN_OPTIONS));
+    Ok(MarkupDisplay::new_safe(s, e))
+}
 
-    let s = markdown_to_html(s.as_ref(), options.unwrap_or(&DEFAULT_OPTIONS));
+/// Renders markdown like `markdown`, passing fenced code blocks to `highlighter`
+///
+/// Askama uses this if the `markdown` filter gets a highlighter argument, or if a highlighter
+/// is configured in `askama.toml`.
+#[cfg(feature = "markdown")]
+pub fn markdown_with_highlighter<E, S>(
+    e: E,
+    s: S,
+    options: Option<&comrak::ComrakOptions>,
+    highlighter: &dyn comrak::adapters::SyntaxHighlighterAdapter,
+) -> Result<MarkupDisplay<E, String>>
+where
+    E: Escaper,
+    S: AsRef<str>,
+{
+    let mut plugins = comrak::ComrakPlugins::default();
+    plugins.render.codefence_syntax_highlighter = Some(highlighter);
+    let s = comrak::markdown_to_html_with_plugins(
+        s.as_ref(),
+        options.unwrap_or(&DEFAULT_MARKDOWN_OPTIONS),
+        &plugins,
+    );
     Ok(MarkupDisplay::new_safe(s, e))
 }
 
+/// Returns the syntect highlighter for one of syntect's bundled themes
+///
+/// Loading the syntax definitions and themes is slow, so the highlighters are kept for the
+/// rest of the thread. This is only meant to be called by the generated code, after the code
+/// generator has checked that `theme` is bundled: the highlighter panics for unknown themes.
+/// Pass the theme as the argument of the `markdown` filter to use it in a template.
+#[cfg(feature = "syntect")]
+#[doc(hidden)]
+pub fn syntect_adapter(
+    theme: &'static str,
+) -> std::rc::Rc<comrak::plugins::syntect::SyntectAdapter<'static>> {
+    use comrak::plugins::syntect::SyntectAdapter;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    thread_local! {
+        static ADAPTERS: RefCell<Vec<(&'static str, Rc<SyntectAdapter<'static>>)>> =
+            RefCell::new(Vec::new());
+    }
+
+    ADAPTERS.with(|adapters| {
+        let mut adapters = adapters.borrow_mut();
+        if let Some((_, adapter)) = adapters.iter().find(|(name, _)| *name == theme) {
+            return Rc::clone(adapter);
+        }
+        let adapter = Rc::new(SyntectAdapter::new(theme));
+        adapters.push((theme, Rc::clone(&adapter)));
+        adapter
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -639,4 +1127,147 @@ mod tests {
         assert_eq!(wordcount("foo").unwrap(), 1);
         assert_eq!(wordcount("foo bar").unwrap(), 2);
     }
+
+    #[test]
+    fn test_truncatewords() {
+        assert_eq!(truncatewords("foo bar baz", 2).unwrap(), "foo bar...");
+        assert_eq!(truncatewords("foo  bar\nbaz", 3).unwrap(), "foo bar baz");
+        assert_eq!(truncatewords("foo bar", 0).unwrap(), "...");
+        assert_eq!(truncatewords("", 2).unwrap(), "");
+    }
+
+    #[test]
+    fn test_truncatechars_html() {
+        assert_eq!(truncatechars_html("hello", 5).unwrap(), "hello");
+        assert_eq!(truncatechars_html("hello", 2).unwrap(), "he...");
+        assert_eq!(
+            truncatechars_html("<p>Hello <b>world</b></p>", 8).unwrap(),
+            "<p>Hello <b>wo...</b></p>"
+        );
+        assert_eq!(
+            truncatechars_html("<p><b>abc</b> def</p>", 3).unwrap(),
+            "<p><b>abc...</b></p>"
+        );
+        assert_eq!(
+            truncatechars_html("<p><b>abc</b></p>", 3).unwrap(),
+            "<p><b>abc</b></p>"
+        );
+        assert_eq!(
+            truncatechars_html("a<br>b &amp; c<!-- <i> -->d", 4).unwrap(),
+            "a<br>b &amp;..."
+        );
+        assert_eq!(
+            truncatechars_html("1 < 2 <img src=x>", 3).unwrap(),
+            "1 <..."
+        );
+    }
+
+    #[test]
+    fn test_wordwrap() {
+        assert_eq!(wordwrap("foo bar baz", 7).unwrap(), "foo bar\nbaz");
+        assert_eq!(wordwrap("foo bar baz", 6).unwrap(), "foo\nbar\nbaz");
+        assert_eq!(
+            wordwrap("a verylongword b", 4).unwrap(),
+            "a\nverylongword\nb"
+        );
+        assert_eq!(wordwrap("foo\n\nbar baz", 20).unwrap(), "foo\n\nbar baz");
+        assert_eq!(wordwrap("", 10).unwrap(), "");
+        assert_eq!(wordwrap("foo  bar", 10).unwrap(), "foo  bar");
+        assert_eq!(wordwrap("foo  bar", 7).unwrap(), "foo\nbar");
+    }
+
+    #[test]
+    fn test_wordwrap_indented() {
+        assert_eq!(
+            wordwrap("    foo bar baz\n  qux", 11).unwrap(),
+            "    foo bar\nbaz\n  qux"
+        );
+        assert_eq!(wordwrap("\tfoo\t bar ", 20).unwrap(), "\tfoo\t bar ");
+        assert_eq!(wordwrap("  verylongword", 4).unwrap(), "  verylongword");
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn test_wordwrap_wide() {
+        assert_eq!(wordwrap("日本語 日本語", 10).unwrap(), "日本語\n日本語");
+    }
+
+    #[test]
+    fn test_title() {
+        assert_eq!(title("hello world").unwrap(), "Hello World");
+        assert_eq!(title("hELLO wORLD").unwrap(), "Hello World");
+        assert_eq!(title("foo-bar (baz)").unwrap(), "Foo-Bar (Baz)");
+        assert_eq!(title("don't stop").unwrap(), "Don't Stop");
+        assert_eq!(title("äbc ßx").unwrap(), "Äbc SSx");
+        assert_eq!(title("").unwrap(), "");
+    }
+
+    #[test]
+    fn test_striptags() {
+        assert_eq!(
+            striptags("<p>Hello <b>world</b>!</p><p>Bye</p>").unwrap(),
+            "Hello world! Bye"
+        );
+        assert_eq!(
+            striptags("a &lt; b &amp;&amp; c &#62; d &#x41;&nbsp;").unwrap(),
+            "a < b && c > d A"
+        );
+        assert_eq!(striptags("1 < 2 <!-- note --> & 3").unwrap(), "1 < 2 & 3");
+        assert_eq!(striptags("&unknown; &#xZZ;").unwrap(), "&unknown; &#xZZ;");
+        assert_eq!(striptags("  \n ").unwrap(), "");
+    }
+
+    #[test]
+    fn test_replace() {
+        assert_eq!(replace("foo bar foo", "foo", "baz").unwrap(), "baz bar baz");
+        assert_eq!(replace("foo", "x", "y").unwrap(), "foo");
+        assert_eq!(replace(42, "4", String::new()).unwrap(), "2");
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            split("a, b, c", ", ").unwrap().collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(split("", ",").unwrap().collect::<Vec<_>>(), vec![""]);
+        assert!(split("abc", "").is_err());
+    }
+
+    #[cfg(feature = "slugify")]
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!").unwrap(), "hello-world");
+        assert_eq!(slugify("Crème Brûlée").unwrap(), "creme-brulee");
+        assert_eq!(slugify("  --Rust  2021-- ").unwrap(), "rust-2021");
+        assert_eq!(slugify("北京").unwrap(), "bei-jing");
+        assert_eq!(slugify("").unwrap(), "");
+    }
+
+    #[test]
+    fn test_urlize() {
+        use askama_escape::Html;
+
+        let urlize = |s| urlize(Html, s).unwrap().to_string();
+        assert_eq!(
+            urlize("see https://example.com/?a=1&b=2."),
+            "see <a href=\"https://example.com/?a=1&amp;b=2\" rel=\"noopener\">\
+             https://example.com/?a=1&amp;b=2</a>."
+        );
+        assert_eq!(
+            urlize("(www.example.com) <b>"),
+            "(<a href=\"https://www.example.com\" rel=\"noopener\">www.example.com</a>) \
+             &lt;b&gt;"
+        );
+        assert_eq!(
+            urlize("mail me@example.com,\nplease"),
+            "mail <a href=\"mailto:me@example.com\" rel=\"noopener\">me@example.com</a>,\nplease"
+        );
+        assert_eq!(
+            urlize("https://en.wikipedia.org/wiki/Rust_(film)"),
+            "<a href=\"https://en.wikipedia.org/wiki/Rust_(film)\" rel=\"noopener\">\
+             https://en.wikipedia.org/wiki/Rust_(film)</a>"
+        );
+        assert_eq!(urlize("http:// @ a@b www."), "http:// @ a@b www.");
+    }
 }
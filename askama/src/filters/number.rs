@@ -0,0 +1,373 @@
+use crate::error::{Error, Result};
+
+/// A number that the number filters can format
+///
+/// This is implemented for the primitive integer and floating point types.
+pub trait FormatNumber {
+    /// Writes the number multiplied by 10<sup>`shift`</sup>, rounded to `decimals`
+    /// decimal places, without grouping, e.g. `-1234.50`
+    fn to_decimal(&self, shift: u32, decimals: usize) -> String;
+}
+
+impl<T: FormatNumber + ?Sized> FormatNumber for &T {
+    fn to_decimal(&self, shift: u32, decimals: usize) -> String {
+        T::to_decimal(self, shift, decimals)
+    }
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty)*) => {$(
+        impl FormatNumber for $ty {
+            fn to_decimal(&self, shift: u32, decimals: usize) -> String {
+                let mut s = self.to_string();
+                if *self != 0 {
+                    s.extend((0..shift).map(|_| '0'));
+                }
+                if decimals > 0 {
+                    s.push('.');
+                    s.extend((0..decimals).map(|_| '0'));
+                }
+                s
+            }
+        }
+    )*};
+}
+
+impl_integer!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+macro_rules! impl_float {
+    ($($ty:ty)*) => {$(
+        impl FormatNumber for $ty {
+            fn to_decimal(&self, shift: u32, decimals: usize) -> String {
+                let s = format!("{:.*}", decimals, self * (10.0 as $ty).powi(shift as i32));
+                // Don't keep the sign of values rounded to zero
+                match s.strip_prefix('-') {
+                    Some(abs) if abs.bytes().all(|b| matches!(b, b'0' | b'.')) => abs.to_owned(),
+                    _ => s,
+                }
+            }
+        }
+    )*};
+}
+
+impl_float!(f32 f64);
+
+/// Formats a number with thousands separators and a fixed number of decimals
+///
+/// ```ignore
+/// {{ 1234567.891|numberformat(2) }}
+/// ```
+///
+/// Output: `1,234,567.89`. Without the number of decimals, the number is rounded to an
+/// integer. With the `icu` feature, a locale can be passed as an additional argument,
+/// e.g. `numberformat(2, "de")` gives `1.234.567,89`.
+pub fn numberformat<T: FormatNumber>(
+    value: T,
+    decimals: usize,
+    locale: Option<&str>,
+) -> Result<String> {
+    let plain = value.to_decimal(0, decimals);
+    match locale {
+        Some(locale) => locale::format(&plain, locale),
+        None => Ok(group(&plain)),
+    }
+}
+
+/// Formats a ratio as a percentage, e.g. `0.256` as `26%`
+///
+/// The number of decimals of the percentage is an optional argument, e.g.
+/// `percent(1)` gives `25.6%`. With the `icu` feature, a locale can be passed as an
+/// additional argument, e.g. `percent(1, "de")` gives `25,6 %`.
+pub fn percent<T: FormatNumber>(value: T, decimals: usize, locale: Option<&str>) -> Result<String> {
+    let plain = value.to_decimal(2, decimals);
+    match locale {
+        Some(locale) => locale::percent(&plain, locale),
+        None => Ok(format!("{}%", group(&plain))),
+    }
+}
+
+/// Formats an amount of money in a currency given by its ISO 4217 code
+///
+/// ```ignore
+/// {{ 1234.5|currency("EUR") }}
+/// ```
+///
+/// Output: `€1,234.50`. Amounts are rounded to two decimals, or to none for currencies
+/// without minor units like `JPY`. Currencies without a well-known symbol are written
+/// with their code, e.g. `CHF 1,234.50`. With the `icu` feature, a locale can be passed
+/// as an additional argument, e.g. `currency("EUR", "de")` gives `1.234,50 €`.
+pub fn currency<T: FormatNumber>(value: T, code: &str, locale: Option<&str>) -> Result<String> {
+    if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_uppercase()) {
+        return Err(Error::Custom(
+            format!("invalid currency code {code:?}").into(),
+        ));
+    }
+
+    let decimals = match ZERO_DECIMAL_CURRENCIES.contains(&code) {
+        true => 0,
+        false => 2,
+    };
+    let plain = value.to_decimal(0, decimals);
+    if let Some(locale) = locale {
+        return locale::currency(&plain, code, locale);
+    }
+
+    let (sign, abs) = split_sign(&plain);
+    Ok(match CURRENCY_SYMBOLS.iter().find(|(c, _)| *c == code) {
+        Some((_, symbol)) => format!("{sign}{symbol}{}", group(abs)),
+        None => format!("{sign}{code}\u{a0}{}", group(abs)),
+    })
+}
+
+fn split_sign(plain: &str) -> (&str, &str) {
+    match plain.strip_prefix('-') {
+        Some(abs) => ("-", abs),
+        None => ("", plain),
+    }
+}
+
+// Inserts a comma between each group of three digits of the integer part
+fn group(plain: &str) -> String {
+    let (sign, abs) = split_sign(plain);
+    let (int, frac) = match abs.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (abs, None),
+    };
+    if int.is_empty() || !int.bytes().all(|b| b.is_ascii_digit()) {
+        // `NaN` or infinite
+        return plain.to_owned();
+    }
+
+    let mut s = String::with_capacity(plain.len() + int.len() / 3);
+    s.push_str(sign);
+    for (i, c) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            s.push(',');
+        }
+        s.push(c);
+    }
+    if let Some(frac) = frac {
+        s.push('.');
+        s.push_str(frac);
+    }
+    s
+}
+
+// The currencies without minor units in ISO 4217
+const ZERO_DECIMAL_CURRENCIES: &[&str] = &[
+    "BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI", "VND",
+    "VUV", "XAF", "XOF", "XPF",
+];
+
+// The symbols that are used for currencies in English
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("AUD", "A$"),
+    ("BRL", "R$"),
+    ("CAD", "CA$"),
+    ("CNY", "CN¥"),
+    ("EUR", "€"),
+    ("GBP", "£"),
+    ("HKD", "HK$"),
+    ("ILS", "₪"),
+    ("INR", "₹"),
+    ("JPY", "¥"),
+    ("KRW", "₩"),
+    ("MXN", "MX$"),
+    ("NZD", "NZ$"),
+    ("TWD", "NT$"),
+    ("USD", "$"),
+    ("VND", "₫"),
+];
+
+#[cfg(not(feature = "icu"))]
+mod locale {
+    use crate::error::{Error, Result};
+
+    fn unsupported() -> Error {
+        Error::Custom("formatting numbers for a locale requires the `icu` feature".into())
+    }
+
+    pub(super) fn format(_: &str, _: &str) -> Result<String> {
+        Err(unsupported())
+    }
+
+    pub(super) fn percent(_: &str, _: &str) -> Result<String> {
+        Err(unsupported())
+    }
+
+    pub(super) fn currency(_: &str, _: &str, _: &str) -> Result<String> {
+        Err(unsupported())
+    }
+}
+
+#[cfg(feature = "icu")]
+mod locale {
+    use std::str::FromStr;
+
+    use fixed_decimal::FixedDecimal;
+    use icu_decimal::FixedDecimalFormatter;
+    use icu_experimental::dimension::currency::formatter::{CurrencyCode, CurrencyFormatter};
+    use icu_experimental::dimension::provider::percent::PercentEssentialsV1Marker;
+    use icu_experimental::provider::Baked;
+    use icu_locid::Locale;
+    use icu_provider::{DataLocale, DataPayload, DataProvider, DataRequest};
+    use tinystr::TinyAsciiStr;
+    use writeable::Writeable;
+
+    use super::split_sign;
+    use crate::error::{Error, Result};
+
+    fn error(e: impl std::fmt::Display) -> Error {
+        Error::Custom(e.to_string().into())
+    }
+
+    fn data_locale(locale: &str) -> Result<DataLocale> {
+        let locale = Locale::from_str(locale)
+            .map_err(|_| Error::Custom(format!("invalid locale {locale:?}").into()))?;
+        Ok(DataLocale::from(&locale))
+    }
+
+    // Formats the number with the symbols and grouping of the locale. `NaN` and infinite
+    // numbers are returned as they are.
+    fn decimal(plain: &str, locale: &DataLocale) -> Result<String> {
+        let value = match FixedDecimal::from_str(plain) {
+            Ok(value) => value,
+            Err(_) => return Ok(plain.to_owned()),
+        };
+        let formatter =
+            FixedDecimalFormatter::try_new(locale, Default::default()).map_err(error)?;
+        Ok(formatter.format_to_string(&value))
+    }
+
+    // Formats the absolute value of the number, and returns the minus sign of the locale
+    // for negative numbers, to be written in front of a percent or currency sign.
+    fn abs_and_sign(plain: &str, locale: &DataLocale) -> Result<(String, String)> {
+        let (sign, abs) = split_sign(plain);
+        let sign = match sign {
+            "" => String::new(),
+            _ => decimal("-1", locale)?.trim_end_matches('1').to_owned(),
+        };
+        Ok((decimal(abs, locale)?, sign))
+    }
+
+    pub(super) fn format(plain: &str, locale: &str) -> Result<String> {
+        decimal(plain, &data_locale(locale)?)
+    }
+
+    pub(super) fn percent(plain: &str, locale: &str) -> Result<String> {
+        let locale = data_locale(locale)?;
+        let payload: DataPayload<PercentEssentialsV1Marker> = Baked
+            .load(DataRequest {
+                locale: &locale,
+                metadata: Default::default(),
+            })
+            .and_then(|response| response.take_payload())
+            .map_err(error)?;
+        let data = payload.get();
+
+        let (abs, sign) = abs_and_sign(plain, &locale)?;
+        let affixes = &data.percent_sign_affixes;
+        Ok(match data.number_index < data.percent_symbol_index {
+            true => format!("{sign}{abs}{}{}", affixes.prefix, data.percent_sign_symbol),
+            false => format!("{sign}{}{}{abs}", data.percent_sign_symbol, affixes.suffix),
+        })
+    }
+
+    pub(super) fn currency(plain: &str, code: &str, locale: &str) -> Result<String> {
+        let locale = data_locale(locale)?;
+        let code = TinyAsciiStr::from_str(code).map_err(error)?;
+        let formatter = CurrencyFormatter::try_new(&locale, Default::default()).map_err(error)?;
+
+        // The formatter doesn't format the number itself yet, so only its placement
+        // relative to the currency sign is taken from formatting `1`.
+        let one = FixedDecimal::from(1);
+        let mut pattern = String::new();
+        formatter
+            .format_fixed_decimal(&one, CurrencyCode(code))
+            .write_to(&mut pattern)
+            .map_err(Error::Fmt)?;
+
+        let (abs, sign) = abs_and_sign(plain, &locale)?;
+        Ok(format!("{sign}{}", pattern.replacen('1', &abs, 1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_decimal() {
+        assert_eq!(0.to_decimal(2, 0), "0");
+        assert_eq!(12u8.to_decimal(0, 2), "12.00");
+        assert_eq!((-5i64).to_decimal(2, 1), "-500.0");
+        assert_eq!(1.005f64.to_decimal(0, 1), "1.0");
+        assert_eq!(0.256f64.to_decimal(2, 1), "25.6");
+        assert_eq!((-0.001f32).to_decimal(0, 2), "0.00");
+        assert_eq!(f64::NAN.to_decimal(0, 2), "NaN");
+    }
+
+    #[test]
+    fn test_numberformat() {
+        assert_eq!(numberformat(0, 0, None).unwrap(), "0");
+        assert_eq!(numberformat(999, 0, None).unwrap(), "999");
+        assert_eq!(numberformat(1000, 0, None).unwrap(), "1,000");
+        assert_eq!(numberformat(-1234567, 0, None).unwrap(), "-1,234,567");
+        assert_eq!(numberformat(1234567.891, 2, None).unwrap(), "1,234,567.89");
+        assert_eq!(numberformat(123456.5f32, 0, None).unwrap(), "123,456");
+        assert_eq!(numberformat(u128::MAX, 0, None).unwrap().len(), 51);
+        assert_eq!(numberformat(f64::INFINITY, 2, None).unwrap(), "inf");
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(0.256, 0, None).unwrap(), "26%");
+        assert_eq!(percent(0.256, 1, None).unwrap(), "25.6%");
+        assert_eq!(percent(12, 0, None).unwrap(), "1,200%");
+        assert_eq!(percent(-0.5, 0, None).unwrap(), "-50%");
+    }
+
+    #[test]
+    fn test_currency() {
+        assert_eq!(currency(1234.5, "EUR", None).unwrap(), "€1,234.50");
+        assert_eq!(currency(-3, "USD", None).unwrap(), "-$3.00");
+        assert_eq!(currency(1234.5, "JPY", None).unwrap(), "¥1,234");
+        assert_eq!(currency(10, "CHF", None).unwrap(), "CHF\u{a0}10.00");
+        assert!(currency(10, "eur", None).is_err());
+    }
+
+    #[cfg(not(feature = "icu"))]
+    #[test]
+    fn test_locale_unsupported() {
+        assert!(numberformat(1, 0, Some("de")).is_err());
+    }
+
+    #[cfg(feature = "icu")]
+    #[test]
+    fn test_locale() {
+        assert_eq!(
+            numberformat(1234567.891, 2, Some("de")).unwrap(),
+            "1.234.567,89"
+        );
+        assert_eq!(
+            numberformat(-1234567, 0, Some("en-IN")).unwrap(),
+            "-12,34,567"
+        );
+        assert_eq!(numberformat(1234.5, 1, Some("en")).unwrap(), "1,234.5");
+        assert!(numberformat(1, 0, Some("not a locale")).is_err());
+
+        assert_eq!(percent(0.256, 1, Some("de")).unwrap(), "25,6\u{a0}%");
+        assert_eq!(percent(-0.5, 0, Some("tr")).unwrap(), "-%50");
+        assert_eq!(percent(0.5, 0, Some("en")).unwrap(), "50%");
+
+        assert_eq!(
+            currency(1234.5, "EUR", Some("de")).unwrap(),
+            "1.234,50\u{a0}€"
+        );
+        assert_eq!(currency(-1234.5, "EUR", Some("en")).unwrap(), "-€1,234.50");
+        assert_eq!(
+            currency(1234.5, "EUR", Some("nl")).unwrap(),
+            "€\u{a0}1.234,50"
+        );
+    }
+}
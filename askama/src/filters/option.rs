@@ -0,0 +1,178 @@
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+
+use askama_escape::{Escaper, MarkupDisplay};
+
+use crate::error::Result;
+
+/// A value that may be missing, like an `Option`
+///
+/// This is implemented for `Option<T>` and for types that are always present, like
+/// strings, so that the `default` filter can also replace empty strings.
+pub trait DefaultValue {
+    /// The type of the value if it is present
+    type Value: fmt::Display + ?Sized;
+
+    /// Returns the value, or `None` if it is missing
+    fn value(&self) -> Option<&Self::Value>;
+}
+
+impl<T: fmt::Display> DefaultValue for Option<T> {
+    type Value = T;
+
+    fn value(&self) -> Option<&T> {
+        self.as_ref()
+    }
+}
+
+impl<T: DefaultValue + ?Sized> DefaultValue for &T {
+    type Value = T::Value;
+
+    fn value(&self) -> Option<&T::Value> {
+        (**self).value()
+    }
+}
+
+impl DefaultValue for str {
+    type Value = str;
+
+    fn value(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+impl DefaultValue for String {
+    type Value = String;
+
+    fn value(&self) -> Option<&String> {
+        Some(self)
+    }
+}
+
+impl DefaultValue for Cow<'_, str> {
+    type Value = Self;
+
+    fn value(&self) -> Option<&Self> {
+        Some(self)
+    }
+}
+
+impl<E: Escaper, T: fmt::Display> DefaultValue for MarkupDisplay<E, T> {
+    type Value = Self;
+
+    fn value(&self) -> Option<&Self> {
+        Some(self)
+    }
+}
+
+/// Either a value or its default, as returned by the `default` filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrDefault<T, D> {
+    Value(T),
+    Default(D),
+}
+
+impl<T: fmt::Display, D> OrDefault<T, D> {
+    /// Escapes the value, but not the default
+    ///
+    /// The code generator uses this if the default is marked as safe.
+    pub fn escape_value<E: Escaper>(self, escaper: E) -> OrDefault<MarkupDisplay<E, T>, D> {
+        match self {
+            OrDefault::Value(value) => OrDefault::Value(MarkupDisplay::new_unsafe(value, escaper)),
+            OrDefault::Default(default) => OrDefault::Default(default),
+        }
+    }
+}
+
+impl<T: fmt::Display, D: fmt::Display> fmt::Display for OrDefault<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrDefault::Value(value) => value.fmt(f),
+            OrDefault::Default(default) => default.fmt(f),
+        }
+    }
+}
+
+/// Renders the value of an `Option`, or `default` if it is `None`
+///
+/// If `empty` is `true`, values that render as an empty string are replaced as well.
+/// This also works for strings that aren't wrapped in an `Option`. The value and the
+/// default are escaped like any other expression, unless they are marked as safe.
+///
+/// ```ignore
+/// {{ user.nickname|default("—") }}
+/// {{ user.bio|default("No bio yet", true) }}
+/// ```
+pub fn default<V, D>(value: &V, default: D, empty: bool) -> Result<OrDefault<&V::Value, D>>
+where
+    V: DefaultValue + ?Sized,
+    D: fmt::Display,
+{
+    Ok(match value.value() {
+        Some(value) if !(empty && is_empty(value)) => OrDefault::Value(value),
+        _ => OrDefault::Default(default),
+    })
+}
+
+/// Renders the value of an `Option`, or nothing if it is `None`
+pub fn unwrap_or_empty<V>(value: &V) -> Result<OrDefault<&V::Value, &'static str>>
+where
+    V: DefaultValue + ?Sized,
+{
+    default(value, "", false)
+}
+
+// Checks if the value renders as an empty string, stopping at the first character.
+fn is_empty<T: fmt::Display + ?Sized>(value: &T) -> bool {
+    struct EmptyWriter;
+
+    impl Write for EmptyWriter {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            match s.is_empty() {
+                true => Ok(()),
+                false => Err(fmt::Error),
+            }
+        }
+    }
+
+    write!(EmptyWriter, "{value}").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use askama_escape::Html;
+
+    #[test]
+    fn test_default() {
+        assert_eq!(
+            default(&Some("foo"), "-", false).unwrap().to_string(),
+            "foo"
+        );
+        assert_eq!(default(&None::<&str>, "-", false).unwrap().to_string(), "-");
+        assert_eq!(default(&Some(""), "-", false).unwrap().to_string(), "");
+        assert_eq!(default(&Some(""), "-", true).unwrap().to_string(), "-");
+        assert_eq!(default(&Some(0), "-", true).unwrap().to_string(), "0");
+        assert_eq!(default("", "-", true).unwrap().to_string(), "-");
+        assert_eq!(
+            default(&String::from("a"), "-", true).unwrap().to_string(),
+            "a"
+        );
+        assert_eq!(default(&&Some(1.5), 0, false).unwrap().to_string(), "1.5");
+    }
+
+    #[test]
+    fn test_unwrap_or_empty() {
+        assert_eq!(unwrap_or_empty(&Some(42)).unwrap().to_string(), "42");
+        assert_eq!(unwrap_or_empty(&None::<i32>).unwrap().to_string(), "");
+    }
+
+    #[test]
+    fn test_escape_value() {
+        let safe = MarkupDisplay::new_safe("<i>none</i>", Html);
+        let value = default(&Some("<b>"), &safe, false).unwrap();
+        assert_eq!(value.escape_value(Html).to_string(), "&lt;b&gt;");
+        let value = default(&None::<&str>, &safe, false).unwrap();
+        assert_eq!(value.escape_value(Html).to_string(), "<i>none</i>");
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::OnceLock;
+
+use askama_escape::{Escaper, MarkupDisplay};
+
+use crate::error::Result;
+
+/// The allowlist of the `sanitize` filter
+///
+/// The code generator builds it from the `[sanitize]` section of `askama.toml` and stores it
+/// in a `static`, so the ammonia [`Builder`](dep_ammonia::Builder) is only set up once for
+/// every call site. The lists that aren't set keep the defaults of
+/// [ammonia](https://docs.rs/ammonia).
+#[derive(Debug)]
+pub struct Allowlist<'a> {
+    tags: Option<&'a [&'a str]>,
+    tag_attributes: Option<&'a [(&'a str, &'a [&'a str])]>,
+    generic_attributes: Option<&'a [&'a str]>,
+    url_schemes: Option<&'a [&'a str]>,
+    link_rel: Option<&'a str>,
+    builder: OnceLock<dep_ammonia::Builder<'a>>,
+}
+
+impl<'a> Allowlist<'a> {
+    /// Creates an allowlist that keeps all the defaults of ammonia
+    pub const fn new() -> Self {
+        Self {
+            tags: None,
+            tag_attributes: None,
+            generic_attributes: None,
+            url_schemes: None,
+            link_rel: None,
+            builder: OnceLock::new(),
+        }
+    }
+
+    /// Sets the tags that are kept
+    pub const fn tags(mut self, tags: &'a [&'a str]) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    /// Sets the attributes that are kept, per tag
+    ///
+    /// The `rel` attribute of `a` tags is only kept if [`link_rel()`](Self::link_rel) is
+    /// empty, because ammonia replaces it otherwise.
+    pub const fn tag_attributes(mut self, tag_attributes: &'a [(&'a str, &'a [&'a str])]) -> Self {
+        self.tag_attributes = Some(tag_attributes);
+        self
+    }
+
+    /// Sets the attributes that are kept on all tags
+    ///
+    /// The `rel` attribute is only kept if [`link_rel()`](Self::link_rel) is empty, because
+    /// ammonia replaces it on links otherwise.
+    pub const fn generic_attributes(mut self, generic_attributes: &'a [&'a str]) -> Self {
+        self.generic_attributes = Some(generic_attributes);
+        self
+    }
+
+    /// Sets the URL schemes that are allowed in links
+    pub const fn url_schemes(mut self, url_schemes: &'a [&'a str]) -> Self {
+        self.url_schemes = Some(url_schemes);
+        self
+    }
+
+    /// Sets the `rel` attribute added to links, or no attribute if it's empty
+    pub const fn link_rel(mut self, link_rel: &'a str) -> Self {
+        self.link_rel = Some(link_rel);
+        self
+    }
+
+    fn builder(&self) -> &dep_ammonia::Builder<'a> {
+        self.builder.get_or_init(|| {
+            // Ammonia panics if it adds a `rel` attribute to links that may also have their own.
+            let rel_allowed = self.link_rel == Some("");
+            let mut builder = dep_ammonia::Builder::default();
+            if let Some(tags) = self.tags {
+                builder.tags(tags.iter().copied().collect());
+            }
+            if let Some(tag_attributes) = self.tag_attributes {
+                builder.tag_attributes(
+                    tag_attributes
+                        .iter()
+                        .map(|(tag, attributes)| {
+                            let attributes = attributes
+                                .iter()
+                                .copied()
+                                .filter(|attr| rel_allowed || *tag != "a" || *attr != "rel");
+                            (*tag, attributes.collect())
+                        })
+                        .collect::<HashMap<_, HashSet<_>>>(),
+                );
+            }
+            if let Some(generic_attributes) = self.generic_attributes {
+                builder.generic_attributes(
+                    generic_attributes
+                        .iter()
+                        .copied()
+                        .filter(|attr| rel_allowed || *attr != "rel")
+                        .collect(),
+                );
+            }
+            if let Some(url_schemes) = self.url_schemes {
+                builder.url_schemes(url_schemes.iter().copied().collect());
+            }
+            if let Some(link_rel) = self.link_rel {
+                builder.link_rel(Some(link_rel).filter(|rel| !rel.is_empty()));
+            }
+            builder
+        })
+    }
+}
+
+impl Default for Allowlist<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Removes the HTML tags and attributes that aren't allowed
+///
+/// Use this to render user-supplied HTML. Unlike with `safe`, the markup that is left can be
+/// trusted. Askama will automatically insert the first (`Escaper`) argument and the allowlist
+/// configured in `askama.toml`, so this filter takes a single argument of any type that
+/// implements `Display`.
+pub fn sanitize<E, T>(
+    e: E,
+    s: T,
+    allowlist: Option<&Allowlist<'_>>,
+) -> Result<MarkupDisplay<E, String>>
+where
+    E: Escaper,
+    T: fmt::Display,
+{
+    let s = s.to_string();
+    let clean = match allowlist {
+        Some(allowlist) => allowlist.builder().clean(&s).to_string(),
+        None => dep_ammonia::clean(&s),
+    };
+    Ok(MarkupDisplay::new_safe(clean, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use askama_escape::Html;
+
+    #[test]
+    fn test_sanitize() {
+        let html = "<p onclick=\"evil()\">Hi <script>evil()</script>\
+                    <a href=\"https://example.com\">link</a> <a href=\"javascript:evil()\">js</a></p>";
+        assert_eq!(
+            sanitize(Html, html, None).unwrap().to_string(),
+            "<p>Hi <a href=\"https://example.com\" rel=\"noopener noreferrer\">link</a> \
+             <a rel=\"noopener noreferrer\">js</a></p>"
+        );
+
+        let allowlist = Allowlist::new()
+            .tags(&["a"])
+            .tag_attributes(&[("a", &["href", "rel"])])
+            .link_rel("");
+        assert_eq!(
+            sanitize(
+                Html,
+                "<p><b>Hi</b> <a href=\"/\" rel=\"me\">me</a></p>",
+                Some(&allowlist)
+            )
+            .unwrap()
+            .to_string(),
+            "Hi <a href=\"/\" rel=\"me\">me</a>"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_rel_with_link_rel() {
+        let html = "<a href=\"/\" rel=\"me\">me</a>";
+        let allowlist = Allowlist::new()
+            .tag_attributes(&[("a", &["href", "rel"])])
+            .link_rel("nofollow");
+        assert_eq!(
+            sanitize(Html, html, Some(&allowlist)).unwrap().to_string(),
+            "<a href=\"/\" rel=\"nofollow\">me</a>"
+        );
+
+        let allowlist = Allowlist::new().generic_attributes(&["rel"]);
+        assert_eq!(
+            sanitize(Html, html, Some(&allowlist)).unwrap().to_string(),
+            "<a href=\"/\" rel=\"noopener noreferrer\">me</a>"
+        );
+    }
+}
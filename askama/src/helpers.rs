@@ -40,9 +40,88 @@ where
     }
 }
 
+/// A template with a `context` only implements `Template` with `default_context`
+///
+/// This trait isn't implemented for any type. The code generator uses it as a bound of the
+/// `Template` methods of templates without `default_context`, so calling them fails with an
+/// error that names it. Use `render_with()`, or add `default_context` to the `template`
+/// attribute to render with the `Default` value of the context.
+pub trait DefaultContextRequired {}
+
+/// Lets `filters` resolve to an empty module if there is no local `filters` module
+///
+/// The code generator imports this module with a glob, which any local `filters` module
+/// shadows, to look for local filters with the same name as a built-in filter.
+pub mod fallback {
+    pub mod filters {}
+}
+
+/// Stand-ins for the built-in filters that were added after local filters of the same name
+///
+/// A local filter can be called with one of these names without the `filters::` prefix in
+/// older versions of Askama, where it isn't a built-in filter. The code generator assigns the
+/// local filter to its stand-in, so that the compiler reports the collision as a mismatch
+/// between both filters. Write `filters::name` to call the local filter.
+#[allow(non_camel_case_types)]
+pub mod built_in_filters {
+    macro_rules! stand_ins {
+        ($($name:ident)*) => { $(
+            pub struct $name;
+        )* };
+    }
+
+    stand_ins!(
+        batch currency date datetime default first groupby isoformat last length map max min
+        numberformat percent reject replace reverse sanitize select slice slugify sort sort_by
+        split striptags sum timesince timeuntil title truncatechars_html truncatewords unique
+        unwrap_or_empty urlize wordwrap
+    );
+}
+
 #[derive(Copy, Clone)]
 pub struct LoopItem {
     pub index: usize,
     pub first: bool,
     pub last: bool,
 }
+
+/// The key of a computed `include`, which selects one of its candidate templates
+///
+/// An index selects the candidate at that position, a string the candidate with that path.
+pub trait IncludeKey {
+    fn position(&self, paths: &[&str]) -> Option<usize>;
+}
+
+macro_rules! impl_include_key {
+    ($($ty:ty)*) => { $(
+        impl IncludeKey for $ty {
+            #[inline]
+            fn position(&self, paths: &[&str]) -> Option<usize> {
+                usize::try_from(*self).ok().filter(|index| *index < paths.len())
+            }
+        }
+    )* };
+}
+
+impl_include_key!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+impl IncludeKey for str {
+    #[inline]
+    fn position(&self, paths: &[&str]) -> Option<usize> {
+        paths.iter().position(|path| *path == self)
+    }
+}
+
+impl IncludeKey for String {
+    #[inline]
+    fn position(&self, paths: &[&str]) -> Option<usize> {
+        self.as_str().position(paths)
+    }
+}
+
+impl<T: IncludeKey + ?Sized> IncludeKey for &T {
+    #[inline]
+    fn position(&self, paths: &[&str]) -> Option<usize> {
+        (**self).position(paths)
+    }
+}
@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{Localizer, Message};
+use crate::error::{Error, Result};
+
+/// A [`Localizer`] that looks up messages by their text in gettext (`.po`) catalogs
+///
+/// The plural form of a message is selected with the `Plural-Forms` rule in the header
+/// of the catalog, or with the English rule if there is none. Fuzzy and obsolete entries,
+/// and entries with a `msgctxt`, are ignored. Messages without a translation are rendered
+/// with [`Message::fallback()`].
+///
+/// ```
+/// use askama::i18n::GettextCatalog;
+///
+/// let catalog = GettextCatalog::new(&["msgid \"Hello {name}!\"\nmsgstr \"Hallo {name}!\""]).unwrap();
+/// ```
+pub struct GettextCatalog {
+    messages: HashMap<String, Vec<String>>,
+    plural: Plural,
+}
+
+impl GettextCatalog {
+    /// Creates a catalog from the sources of gettext catalogs
+    ///
+    /// The plural rule is taken from the header of the first catalog that has one.
+    pub fn new(sources: &[&str]) -> Result<Self> {
+        let mut catalog = Self {
+            messages: HashMap::new(),
+            plural: Plural::english(),
+        };
+        let mut plural = None;
+        for source in sources {
+            for entry in parse_po(source)? {
+                if entry.fuzzy || entry.context.is_some() {
+                    continue;
+                }
+                if entry.id.is_empty() {
+                    if plural.is_none() {
+                        plural = plural_rule(entry.strs.first().map_or("", String::as_str))?;
+                    }
+                } else {
+                    catalog.messages.insert(entry.id, entry.strs);
+                }
+            }
+        }
+        if let Some(plural) = plural {
+            catalog.plural = plural;
+        }
+        Ok(catalog)
+    }
+}
+
+impl fmt::Debug for GettextCatalog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GettextCatalog")
+            .field("messages", &self.messages.len())
+            .finish()
+    }
+}
+
+impl Localizer for GettextCatalog {
+    fn translate(&self, message: &Message<'_>) -> String {
+        let index = match (message.plural, message.count) {
+            (Some(_), Some(count)) => self.plural.eval(count),
+            _ => 0,
+        };
+        let translation = self.messages.get(message.text).and_then(|strs| {
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| strs.get(index))
+        });
+        match translation {
+            Some(text) if !text.is_empty() => message.format(text),
+            _ => message.fallback(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Entry {
+    context: Option<String>,
+    id: String,
+    strs: Vec<String>,
+    fuzzy: bool,
+}
+
+// The field of the entry that continuation lines are appended to.
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    None,
+    Context,
+    Id,
+    Plural,
+    Str(usize),
+}
+
+fn parse_po(source: &str) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let mut entry = Entry::default();
+    let mut field = Field::None;
+    for line in source.lines().map(str::trim) {
+        // Anything but a string continues the entry only until its `msgstr`.
+        let starts_entry = line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("msgctxt ")
+            || line.starts_with("msgid ");
+        if starts_entry && matches!(field, Field::Str(_)) {
+            entries.push(std::mem::take(&mut entry));
+            field = Field::None;
+        }
+
+        if line.is_empty() || line.starts_with("#~") {
+            continue;
+        } else if let Some(flags) = line.strip_prefix("#,") {
+            entry.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+        } else if line.starts_with('#') {
+            continue;
+        } else if let Some(rest) = line.strip_prefix("msgctxt ") {
+            entry.context = Some(po_string(rest)?);
+            field = Field::Context;
+        } else if let Some(rest) = line.strip_prefix("msgid_plural ") {
+            po_string(rest)?;
+            field = Field::Plural;
+        } else if let Some(rest) = line.strip_prefix("msgid ") {
+            entry.id = po_string(rest)?;
+            field = Field::Id;
+        } else if let Some(rest) = line.strip_prefix("msgstr[") {
+            let (index, rest) = rest
+                .split_once(']')
+                .and_then(|(index, rest)| Some((index.parse::<usize>().ok()?, rest)))
+                .ok_or_else(|| invalid_po(line))?;
+            if entry.strs.len() <= index {
+                entry.strs.resize(index + 1, String::new());
+            }
+            entry.strs[index] = po_string(rest.trim_start())?;
+            field = Field::Str(index);
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            entry.strs = vec![po_string(rest)?];
+            field = Field::Str(0);
+        } else if line.starts_with('"') {
+            let s = po_string(line)?;
+            match field {
+                Field::Context => entry.context.get_or_insert_with(String::new).push_str(&s),
+                Field::Id => entry.id.push_str(&s),
+                Field::Plural => {}
+                Field::Str(index) => entry.strs[index].push_str(&s),
+                Field::None => return Err(invalid_po(line)),
+            }
+        } else {
+            return Err(invalid_po(line));
+        }
+    }
+    if matches!(field, Field::Str(_)) {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn po_string(s: &str) -> Result<String> {
+    let inner = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| invalid_po(s))?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(c) => out.push(c),
+            None => return Err(invalid_po(s)),
+        }
+    }
+    Ok(out)
+}
+
+fn invalid_po(line: &str) -> Error {
+    Error::Custom(format!("invalid gettext catalog near: {line:?}").into())
+}
+
+// Reads the `plural=` expression of the `Plural-Forms` field of the catalog header.
+fn plural_rule(header: &str) -> Result<Option<Plural>> {
+    let forms = match header
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Plural-Forms:"))
+    {
+        Some(forms) => forms,
+        None => return Ok(None),
+    };
+    let expr = forms
+        .split(';')
+        .find_map(|part| part.trim().strip_prefix("plural="))
+        .ok_or_else(|| invalid_plural(forms))?;
+    let mut parser = PluralParser {
+        rest: expr.trim(),
+        source: forms,
+    };
+    let plural = parser.ternary()?;
+    match parser.rest.trim_start() {
+        "" => Ok(Some(plural)),
+        _ => Err(invalid_plural(forms)),
+    }
+}
+
+fn invalid_plural(forms: &str) -> Error {
+    Error::Custom(format!("invalid Plural-Forms in gettext catalog: {forms:?}").into())
+}
+
+// A `Plural-Forms` expression, which computes the index of the plural form from `n`.
+enum Plural {
+    N,
+    Number(i64),
+    Not(Box<Plural>),
+    Binary(Box<Plural>, &'static str, Box<Plural>),
+    Ternary(Box<Plural>, Box<Plural>, Box<Plural>),
+}
+
+impl Plural {
+    fn english() -> Self {
+        Plural::Binary(Box::new(Plural::N), "!=", Box::new(Plural::Number(1)))
+    }
+
+    fn eval(&self, n: i64) -> i64 {
+        match self {
+            Plural::N => n,
+            Plural::Number(value) => *value,
+            Plural::Not(expr) => (expr.eval(n) == 0) as i64,
+            Plural::Ternary(cond, then, otherwise) => match cond.eval(n) {
+                0 => otherwise.eval(n),
+                _ => then.eval(n),
+            },
+            Plural::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.eval(n), rhs.eval(n));
+                match *op {
+                    "||" => (lhs != 0 || rhs != 0) as i64,
+                    "&&" => (lhs != 0 && rhs != 0) as i64,
+                    "==" => (lhs == rhs) as i64,
+                    "!=" => (lhs != rhs) as i64,
+                    "<=" => (lhs <= rhs) as i64,
+                    ">=" => (lhs >= rhs) as i64,
+                    "<" => (lhs < rhs) as i64,
+                    ">" => (lhs > rhs) as i64,
+                    "+" => lhs.wrapping_add(rhs),
+                    "-" => lhs.wrapping_sub(rhs),
+                    "*" => lhs.wrapping_mul(rhs),
+                    "/" => lhs.checked_div(rhs).unwrap_or(0),
+                    _ => lhs.checked_rem(rhs).unwrap_or(0),
+                }
+            }
+        }
+    }
+}
+
+// Parses the C expressions of `Plural-Forms`, with C's operator precedence.
+struct PluralParser<'a> {
+    rest: &'a str,
+    source: &'a str,
+}
+
+impl PluralParser<'_> {
+    const LEVELS: &'static [&'static [&'static str]] = &[
+        &["||"],
+        &["&&"],
+        &["==", "!="],
+        &["<=", ">=", "<", ">"],
+        &["+", "-"],
+        &["*", "/", "%"],
+    ];
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn ternary(&mut self) -> Result<Plural> {
+        let cond = self.binary(0)?;
+        if !self.eat("?") {
+            return Ok(cond);
+        }
+        let then = self.ternary()?;
+        if !self.eat(":") {
+            return Err(invalid_plural(self.source));
+        }
+        let otherwise = self.ternary()?;
+        Ok(Plural::Ternary(
+            Box::new(cond),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Plural> {
+        let ops = match Self::LEVELS.get(level) {
+            Some(ops) => *ops,
+            None => return self.unary(),
+        };
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(op) = ops.iter().copied().find(|op| self.eat(op)) {
+            let rhs = self.binary(level + 1)?;
+            lhs = Plural::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Plural> {
+        if self.eat("!") {
+            return Ok(Plural::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let expr = self.ternary()?;
+            return match self.eat(")") {
+                true => Ok(expr),
+                false => Err(invalid_plural(self.source)),
+            };
+        }
+        if self.eat("n") {
+            return Ok(Plural::N);
+        }
+        let end = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest.len());
+        let (number, rest) = self.rest.split_at(end);
+        let number = number.parse().map_err(|_| invalid_plural(self.source))?;
+        self.rest = rest;
+        Ok(Plural::Number(number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DE_PO: &str = r#"msgid ""
+msgstr ""
+"Language: de\n"
+"Plural-Forms: nplurals=2; plural=(n != 1);\n"
+
+#: templates/index.html
+msgid "Hello {name}!"
+msgstr "Hallo {name}!"
+
+msgid "One item"
+msgid_plural "{count} items"
+msgstr[0] "Ein Element"
+msgstr[1] "{count} Elemente"
+
+#, fuzzy
+msgid "Goodbye"
+msgstr "Tschüss"
+
+msgctxt "menu"
+msgid "Open"
+msgstr "Öffnen"
+
+msgid ""
+"Multi "
+"line"
+msgstr "Mehrere "
+"Zeilen"
+
+#~ msgid "Old"
+#~ msgstr "Alt"
+"#;
+
+    #[test]
+    fn test_gettext_catalog() {
+        let catalog = GettextCatalog::new(&[DE_PO]).unwrap();
+        let message = Message {
+            id: "hello-name",
+            text: "Hello {name}!",
+            plural: None,
+            count: None,
+            args: &[("name", &"Welt")],
+        };
+        assert_eq!(catalog.translate(&message), "Hallo Welt!");
+
+        let mut message = Message {
+            id: "one-item-count-items",
+            text: "One item",
+            plural: Some("{count} items"),
+            count: Some(1),
+            args: &[("count", &1)],
+        };
+        assert_eq!(catalog.translate(&message), "Ein Element");
+        message.count = Some(3);
+        message.args = &[("count", &3)];
+        assert_eq!(catalog.translate(&message), "3 Elemente");
+
+        for text in ["Goodbye", "Open", "Old", "Missing"] {
+            let message = Message {
+                id: "",
+                text,
+                plural: None,
+                count: None,
+                args: &[],
+            };
+            assert_eq!(catalog.translate(&message), text);
+        }
+        let message = Message {
+            id: "multi-line",
+            text: "Multi line",
+            plural: None,
+            count: None,
+            args: &[],
+        };
+        assert_eq!(catalog.translate(&message), "Mehrere Zeilen");
+
+        assert!(GettextCatalog::new(&["msgid \"unterminated"]).is_err());
+        assert!(
+            GettextCatalog::new(&["msgid \"\"\nmsgstr \"Plural-Forms: plural=n +;\""]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_plural_forms() {
+        // The rule of Polish, which has three plural forms.
+        let plural = plural_rule(
+            "Plural-Forms: nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 && \
+             (n%100<10 || n%100>=20) ? 1 : 2);",
+        )
+        .unwrap()
+        .unwrap();
+        let forms: Vec<_> = [1, 2, 4, 5, 12, 22, 25, 0].map(|n| plural.eval(n)).into();
+        assert_eq!(forms, [0, 1, 1, 2, 2, 1, 2, 2]);
+
+        let plural = plural_rule("Plural-Forms: nplurals=2; plural=!(n > 1);")
+            .unwrap()
+            .unwrap();
+        assert_eq!((plural.eval(0), plural.eval(2)), (1, 0));
+        assert_eq!(
+            plural_rule("Plural-Forms: nplurals=1; plural=0;")
+                .unwrap()
+                .unwrap()
+                .eval(5),
+            0
+        );
+        assert!(plural_rule("Language: de").unwrap().is_none());
+        assert!(plural_rule("Plural-Forms: nplurals=2; plural=n % 0;")
+            .unwrap()
+            .is_some());
+    }
+}
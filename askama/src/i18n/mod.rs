@@ -0,0 +1,277 @@
+//! Module for the localization of templates
+//!
+//! Translatable messages are written with `{% trans %}` blocks or with the
+//! `_("key")` expression. When rendering, they are passed as a [`Message`] to a
+//! [`Localizer`], which is implemented by the template (or by its render
+//! context, if one is configured) and looks up the translation with the backend
+//! of your choice, like the [`FluentCatalog`] and [`GettextCatalog`] that come
+//! with Askama.
+
+use std::fmt;
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+use crate::error::{Error, Result};
+
+mod gettext;
+pub use self::gettext::GettextCatalog;
+
+/// Translates the messages of a template
+pub trait Localizer {
+    /// Returns the translation of `message`, with its arguments substituted
+    ///
+    /// Implementations usually fall back to [`Message::fallback()`] for
+    /// messages without a translation.
+    fn translate(&self, message: &Message<'_>) -> String;
+}
+
+/// A translatable message, as written in the template
+pub struct Message<'a> {
+    /// The id of the message, to look up its translation in Fluent catalogs
+    ///
+    /// For `_()`, this is the message key. For `{% trans %}` blocks, it is derived
+    /// from the text, so `Hello {{ name }}!` has the id `hello-name`.
+    pub id: &'a str,
+    /// The text of the message, to look up its translation in gettext catalogs
+    ///
+    /// For `{% trans %}` blocks, this is the content of the block, where
+    /// variables are written as `{name}`. For `_()`, this is the message key.
+    pub text: &'a str,
+    /// The plural form of a `{% trans %}` block containing `{% pluralize %}`
+    pub plural: Option<&'a str>,
+    /// The number that selects the plural form, if given
+    pub count: Option<i64>,
+    /// The arguments of the message, by name
+    pub args: &'a [(&'a str, &'a dyn fmt::Display)],
+}
+
+impl Message<'_> {
+    /// Substitutes the `{name}` placeholders of `text` with the message arguments
+    ///
+    /// Placeholders without a matching argument are left as is.
+    pub fn format(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let arg = rest.find('}').and_then(|end| {
+                let name = &rest[1..end];
+                let (_, value) = self.args.iter().find(|(arg, _)| *arg == name)?;
+                Some((end, value))
+            });
+            match arg {
+                Some((end, value)) => {
+                    out.push_str(&value.to_string());
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// Formats the message as written in the template
+    ///
+    /// The plural form is used if `count` is not 1.
+    pub fn fallback(&self) -> String {
+        let text = match (self.plural, self.count) {
+            (Some(plural), Some(count)) if count != 1 => plural,
+            _ => self.text,
+        };
+        self.format(text)
+    }
+}
+
+/// Converts the `count` of a message
+pub trait Count {
+    /// Returns the value as a message count
+    fn as_count(&self) -> i64;
+}
+
+// Counts that don't fit in an `i64` saturate, so they still select the plural form for
+// large numbers.
+macro_rules! impl_count {
+    (unsigned: $($ty:ty),*) => {
+        $(
+            impl Count for $ty {
+                fn as_count(&self) -> i64 {
+                    i64::try_from(*self).unwrap_or(i64::MAX)
+                }
+            }
+        )*
+    };
+    (signed: $($ty:ty),*) => {
+        $(
+            impl Count for $ty {
+                fn as_count(&self) -> i64 {
+                    i64::try_from(*self).unwrap_or(if *self < 0 { i64::MIN } else { i64::MAX })
+                }
+            }
+        )*
+    };
+}
+
+impl_count!(unsigned: u8, u16, u32, u64, u128, usize);
+impl_count!(signed: i8, i16, i32, i64, i128, isize);
+
+impl<T: Count + ?Sized> Count for &T {
+    fn as_count(&self) -> i64 {
+        T::as_count(self)
+    }
+}
+
+/// A [`Localizer`] that looks up messages by their id in Fluent (`.ftl`) resources
+///
+/// Messages without a translation are rendered with [`Message::fallback()`]. The
+/// `count` of a message is passed to Fluent as a number, so it can select the
+/// plural form; other arguments are passed as strings.
+///
+/// ```
+/// use askama::i18n::FluentCatalog;
+///
+/// let catalog = FluentCatalog::new("de", &["hello-name = Hallo, { $name }!"]).unwrap();
+/// ```
+pub struct FluentCatalog {
+    bundle: FluentBundle<FluentResource>,
+}
+
+impl FluentCatalog {
+    /// Creates a catalog for the language `lang`, e.g. `de-AT`, from the sources of
+    /// Fluent resources
+    pub fn new(lang: &str, sources: &[&str]) -> Result<Self> {
+        let lang = lang
+            .parse::<LanguageIdentifier>()
+            .map_err(|err| Error::Custom(Box::new(err)))?;
+        let mut bundle = FluentBundle::new_concurrent(vec![lang]);
+        // The output is escaped like the rest of the template, without bidi isolation marks.
+        bundle.set_use_isolating(false);
+        for source in sources {
+            let resource = FluentResource::try_new(source.to_string()).map_err(|(_, errors)| {
+                Error::Custom(format!("invalid Fluent resource: {errors:?}").into())
+            })?;
+            bundle.add_resource(resource).map_err(|errors| {
+                Error::Custom(format!("invalid Fluent resource: {errors:?}").into())
+            })?;
+        }
+        Ok(Self { bundle })
+    }
+}
+
+impl fmt::Debug for FluentCatalog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FluentCatalog")
+            .field("locales", &self.bundle.locales)
+            .finish()
+    }
+}
+
+impl Localizer for FluentCatalog {
+    fn translate(&self, message: &Message<'_>) -> String {
+        let pattern = match self
+            .bundle
+            .get_message(message.id)
+            .and_then(|msg| msg.value())
+        {
+            Some(pattern) => pattern,
+            None => return message.fallback(),
+        };
+
+        let mut args = FluentArgs::new();
+        for (name, value) in message.args {
+            if *name != "count" || message.count.is_none() {
+                args.set(*name, value.to_string());
+            }
+        }
+        if let Some(count) = message.count {
+            args.set("count", count);
+        }
+
+        let mut errors = Vec::new();
+        self.bundle
+            .format_pattern(pattern, Some(&args), &mut errors)
+            .into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let message = Message {
+            id: "hello-name-missing",
+            text: "Hello {name}, {missing} {",
+            plural: None,
+            count: None,
+            args: &[("name", &"world")],
+        };
+        assert_eq!(message.fallback(), "Hello world, {missing} {");
+    }
+
+    #[test]
+    fn test_fallback_plural() {
+        let mut message = Message {
+            id: "count-item",
+            text: "{count} item",
+            plural: Some("{count} items"),
+            count: Some(1),
+            args: &[("count", &1)],
+        };
+        assert_eq!(message.fallback(), "1 item");
+        message.count = Some(2);
+        message.args = &[("count", &2)];
+        assert_eq!(message.fallback(), "2 items");
+    }
+
+    #[test]
+    fn test_count_saturates() {
+        assert_eq!(3u8.as_count(), 3);
+        assert_eq!((-3i32).as_count(), -3);
+        assert_eq!(u64::MAX.as_count(), i64::MAX);
+        assert_eq!(usize::MAX.as_count(), i64::MAX);
+        assert_eq!(u128::MAX.as_count(), i64::MAX);
+        assert_eq!(i128::MIN.as_count(), i64::MIN);
+    }
+
+    #[test]
+    fn test_fluent_catalog() {
+        let catalog = FluentCatalog::new(
+            "de",
+            &["hello-name = Hallo, { $name }!\n\
+               items =\n    { $count ->\n        [one] Ein Element\n       *[other] { $count } Elemente\n    }\n"],
+        )
+        .unwrap();
+        let message = Message {
+            id: "hello-name",
+            text: "Hello, {name}!",
+            plural: None,
+            count: None,
+            args: &[("name", &"Welt")],
+        };
+        assert_eq!(catalog.translate(&message), "Hallo, Welt!");
+
+        let mut message = Message {
+            id: "items",
+            text: "One item",
+            plural: Some("{count} items"),
+            count: Some(1),
+            args: &[("count", &1)],
+        };
+        assert_eq!(catalog.translate(&message), "Ein Element");
+        message.count = Some(3);
+        message.args = &[("count", &3)];
+        assert_eq!(catalog.translate(&message), "3 Elemente");
+
+        message.id = "missing";
+        assert_eq!(catalog.translate(&message), "3 items");
+        assert!(FluentCatalog::new("de", &["hello = { $"]).is_err());
+    }
+}
@@ -0,0 +1,349 @@
+//! Analysis of templates for editors
+//!
+//! These functions implement the features of the `askama-lsp` language server.
+//! Positions are zero-based lines and UTF-16 columns, as in the Language
+//! Server Protocol. Templates other than the one being edited are read from
+//! disk.
+
+use std::path::{Path, PathBuf};
+
+use askama_config::heritage::Context;
+use askama_parser::{Node, Syntax, WithSpan};
+
+use crate::check::{include_paths, missing_message, used_templates};
+use crate::{offset_of, read_template, walk_nodes, Project};
+
+/// A position in a template
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Position {
+    /// The zero-based line number
+    pub line: u32,
+    /// The zero-based column, in UTF-16 code units
+    pub character: u32,
+}
+
+/// A range in a template
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A problem found in a template
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub message: String,
+}
+
+/// A location in a template
+#[derive(Debug, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub range: Range,
+}
+
+/// Returns the problems of the template at `path`, with the contents `source`
+///
+/// Reports syntax errors, templates that are used but don't exist, and misplaced
+/// `extends`, `macro` and `import` tags.
+pub fn diagnostics(project: &Project, path: &Path, source: &str) -> Vec<Diagnostic> {
+    let nodes = match project.parse(path, source) {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            let start = e
+                .offset()
+                .map_or_else(Position::default, |o| position_at(source, o));
+            let range = Range { start, end: start };
+            let message = e.to_string();
+            return vec![Diagnostic { range, message }];
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    for (names, optional) in used_templates(&nodes) {
+        let found = names
+            .iter()
+            .any(|name| project.config.find_template(name, Some(path)).is_ok());
+        if !optional && !found {
+            diagnostics.push(Diagnostic {
+                range: range_of(source, names[0]),
+                message: missing_message(&names),
+            });
+        }
+    }
+    if diagnostics.is_empty() {
+        if let Err(e) = Context::new(&project.config, path, &nodes) {
+            let range = e.span().map_or_else(Range::default, |span| Range {
+                start: position_at(source, span.start),
+                end: position_at(source, span.end),
+            });
+            diagnostics.push(Diagnostic {
+                range,
+                message: e.to_string(),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Returns the definition of what is at `position` in the template at `path`
+///
+/// Template paths of `extends`, `include`, `import` and `embed` tags lead to
+/// the template, and macro names of `call` and `from` tags to the definition
+/// of the macro.
+pub fn definition(
+    project: &Project,
+    path: &Path,
+    source: &str,
+    position: Position,
+) -> Option<Location> {
+    let offset = offset_at(source, position);
+    let contains = |s: &str| {
+        let start = offset_of(source, s);
+        start <= offset && offset <= start + s.len()
+    };
+
+    let nodes = project.parse(path, source).ok()?;
+    let mut target = None;
+    walk_nodes(&nodes, &mut |node| {
+        if target.is_some() {
+            return;
+        }
+        target = match node {
+            Node::Extends(name) | Node::Import(_, name, _) | Node::Embed(_, name, _, _)
+                if contains(name) =>
+            {
+                Some(Target::Template(name))
+            }
+            Node::Include(_, path, _) => include_paths(path)
+                .into_iter()
+                .find(|name| contains(name))
+                .map(Target::Template),
+            Node::FromImport(_, name, _) if contains(name) => Some(Target::Template(name)),
+            Node::FromImport(_, name, names) => names
+                .iter()
+                .find(|(mname, alias)| contains(mname) || contains(alias))
+                .map(|(mname, _)| Target::ImportedMacro(name, mname)),
+            Node::Call(_, scope, name, _)
+                if *name != "super" && (contains(name) || scope.map_or(false, contains)) =>
+            {
+                Some(Target::Macro(*scope, name))
+            }
+            _ => None,
+        };
+    });
+
+    match target? {
+        Target::Template(name) => Some(Location {
+            path: project.config.find_template(name, Some(path)).ok()?,
+            range: Range::default(),
+        }),
+        Target::ImportedMacro(name, mname) => {
+            let file = project.config.find_template(name, Some(path)).ok()?;
+            find_macro(project, file, mname)
+        }
+        Target::Macro(scope, name) => resolve_macro(project, path, source, scope, name),
+    }
+}
+
+/// Returns the names of the blocks defined by the ancestors of the template at
+/// `path`, if `position` is at the name of a `block` tag
+pub fn complete_blocks(
+    project: &Project,
+    path: &Path,
+    source: &str,
+    position: Position,
+) -> Vec<String> {
+    let before = &source[..offset_at(source, position)];
+    let syntax = project.syntax(path);
+    let prefix = match block_name_prefix(before, syntax) {
+        Some(prefix) => prefix,
+        None => return Vec::new(),
+    };
+    // The template being edited usually doesn't parse while a tag is being typed.
+    let parent = find_extends(source, syntax)
+        .and_then(|name| project.config.find_template(name, Some(path)).ok());
+
+    let mut names = Vec::new();
+    for (path, source) in ancestors(project, path, parent) {
+        let nodes = match project.parse(&path, &source) {
+            Ok(nodes) => nodes,
+            Err(_) => continue,
+        };
+        if let Ok(ctx) = Context::new(&project.config, &path, &nodes) {
+            for name in ctx.blocks.keys() {
+                if name.starts_with(prefix) && !names.iter().any(|n| n == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+    }
+    names.sort();
+    names
+}
+
+enum Target<'a> {
+    Template(&'a str),
+    Macro(Option<&'a str>, &'a str),
+    ImportedMacro(&'a str, &'a str),
+}
+
+// Looks up a macro the same way as the generator: in the calling template, then in its
+// ancestors, and last in the templates of the prelude that were imported without a scope.
+fn resolve_macro(
+    project: &Project,
+    path: &Path,
+    source: &str,
+    scope: Option<&str>,
+    name: &str,
+) -> Option<Location> {
+    let nodes = project.parse(path, source).ok()?;
+    let mut candidates = vec![(path.to_path_buf(), source.to_string())];
+    candidates.extend(ancestors(project, path, parent(project, path, &nodes)));
+
+    for (path, source) in &candidates {
+        let nodes = match project.parse(path, source) {
+            Ok(nodes) => nodes,
+            Err(_) => continue,
+        };
+        let ctx = match Context::new(&project.config, path, &nodes) {
+            Ok(ctx) => ctx,
+            Err(_) => continue,
+        };
+        match scope {
+            Some(scope) => {
+                if let Some(file) = ctx.imports.get(scope) {
+                    return find_macro(project, file.clone(), name);
+                }
+            }
+            None => {
+                if let Some((mname, _)) = ctx.macros.get_key_value(name) {
+                    return Some(Location {
+                        path: path.clone(),
+                        range: range_of(source, mname),
+                    });
+                }
+                if let Some((file, mname)) = ctx.from_imports.get(name) {
+                    return find_macro(project, file.clone(), mname);
+                }
+            }
+        }
+    }
+
+    if scope.is_some() {
+        return None;
+    }
+    project
+        .config
+        .prelude
+        .iter()
+        .filter(|(_, scope)| scope.is_none())
+        .find_map(|(file, _)| find_macro(project, file.clone(), name))
+}
+
+// Finds the definition of the macro `name` in the template at `path`.
+fn find_macro(project: &Project, path: PathBuf, name: &str) -> Option<Location> {
+    let source = read_template(&path).ok()?;
+    let nodes = project.parse(&path, &source).ok()?;
+    let range = nodes.iter().find_map(|node| match &**node {
+        Node::Macro(mname, _) if *mname == name => Some(range_of(&source, mname)),
+        _ => None,
+    })?;
+    Some(Location { path, range })
+}
+
+fn parent(project: &Project, path: &Path, nodes: &[WithSpan<Node<'_>>]) -> Option<PathBuf> {
+    nodes.iter().find_map(|node| match &**node {
+        Node::Extends(name) => project.config.find_template(name, Some(path)).ok(),
+        _ => None,
+    })
+}
+
+// Reads the ancestors of the template at `path`, starting with its parent `first`.
+fn ancestors(project: &Project, path: &Path, first: Option<PathBuf>) -> Vec<(PathBuf, String)> {
+    let mut ancestors: Vec<(PathBuf, String)> = Vec::new();
+    let mut next = first;
+    while let Some(current) = next.take() {
+        // Stop at cycles, which are reported when compiling.
+        if current == path || ancestors.iter().any(|(p, _)| *p == current) {
+            break;
+        }
+        let source = match read_template(&current) {
+            Ok(source) => source,
+            Err(_) => break,
+        };
+        if let Ok(nodes) = project.parse(&current, &source) {
+            next = parent(project, &current, &nodes);
+        }
+        ancestors.push((current, source));
+    }
+    ancestors
+}
+
+// Returns the part of the block name that was typed, if `before` ends in a `block` tag.
+fn block_name_prefix<'a>(before: &'a str, syntax: &Syntax<'_>) -> Option<&'a str> {
+    let start = before.rfind(syntax.block_start)?;
+    let tag = before[start + syntax.block_start.len()..]
+        .trim_start_matches(['-', '+', '~'])
+        .trim_start();
+    let rest = tag.strip_prefix("block")?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let name = rest.trim_start();
+    if name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+fn find_extends<'a>(source: &'a str, syntax: &Syntax<'_>) -> Option<&'a str> {
+    let mut rest = source;
+    while let Some(start) = rest.find(syntax.block_start) {
+        rest = &rest[start + syntax.block_start.len()..];
+        let tag = rest.trim_start_matches(['-', '+', '~']).trim_start();
+        if let Some(tag) = tag.strip_prefix("extends") {
+            let name = tag.trim_start().strip_prefix('"')?;
+            return name.find('"').map(|end| &name[..end]);
+        }
+    }
+    None
+}
+
+fn offset_at(source: &str, position: Position) -> usize {
+    let mut offset = 0;
+    for (i, line) in source.split_inclusive('\n').enumerate() {
+        if i == position.line as usize {
+            let mut units = 0;
+            for (j, c) in line.char_indices() {
+                if units >= position.character as usize || c == '\n' {
+                    return offset + j;
+                }
+                units += c.len_utf16();
+            }
+            return offset + line.len();
+        }
+        offset += line.len();
+    }
+    source.len()
+}
+
+fn position_at(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: character as u32,
+    }
+}
+
+fn range_of(source: &str, s: &str) -> Range {
+    let start = offset_of(source, s);
+    Range {
+        start: position_at(source, start),
+        end: position_at(source, start + s.len()),
+    }
+}
@@ -0,0 +1,70 @@
+//! Prints the syntax trees of templates as JSON.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::{env, fs};
+
+use askama_cli::{Error, Project};
+
+const USAGE: &str = "\
+Usage: askama-ast [OPTIONS] [FILES]...
+
+Prints the syntax trees of the given templates, or of all templates of the
+crate, as JSON, one line per template.
+
+Options:
+    --root <DIR>     The crate root, containing `askama.toml` [default: .]
+    --config <FILE>  The configuration file, relative to the crate root
+";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("askama-ast: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Error> {
+    let mut root = PathBuf::from(".");
+    let mut config = None;
+    let mut files = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| Error::from(format!("missing value for {arg}\n\n{USAGE}")))
+        };
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(value()?),
+            "--config" => config = Some(value()?),
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return Ok(());
+            }
+            _ if arg.starts_with('-') => {
+                return Err(format!("unexpected argument {arg:?}\n\n{USAGE}").into())
+            }
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+
+    let project = Project::open(&root, config.as_deref())?;
+    if files.is_empty() {
+        files = project.templates()?;
+    }
+
+    for path in &files {
+        let display = project.display_path(path).display();
+        let source =
+            fs::read_to_string(path).map_err(|e| format!("unable to read {display}: {e}"))?;
+        let json = project
+            .ast_json(path, &source)
+            .map_err(|e| format!("{display}: {e}"))?;
+        println!("{json}");
+    }
+    Ok(())
+}
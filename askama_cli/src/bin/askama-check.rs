@@ -0,0 +1,69 @@
+//! Checks the templates of a crate for problems.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use askama_cli::check;
+use askama_cli::{Error, Project};
+
+const USAGE: &str = "\
+Usage: askama-check [OPTIONS]
+
+Options:
+    --root <DIR>     The crate root, containing `askama.toml` [default: .]
+    --config <FILE>  The configuration file, relative to the crate root
+    --json           Print the diagnostics as JSON
+    --deny-warnings  Fail if there are warnings, such as unreferenced templates
+";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("askama-check: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run() -> Result<bool, Error> {
+    let mut root = PathBuf::from(".");
+    let mut config = None;
+    let mut json = false;
+    let mut deny_warnings = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| Error::from(format!("missing value for {arg}\n\n{USAGE}")))
+        };
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(value()?),
+            "--config" => config = Some(value()?),
+            "--json" => json = true,
+            "--deny-warnings" => deny_warnings = true,
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return Ok(true);
+            }
+            _ => return Err(format!("unexpected argument {arg:?}\n\n{USAGE}").into()),
+        }
+    }
+
+    let project = Project::open(&root, config.as_deref())?;
+    let diagnostics = check::check(&project)?;
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+    if json {
+        println!("{}", check::to_json(&diagnostics));
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{diagnostic}");
+        }
+        println!("{errors} error(s), {warnings} warning(s)");
+    }
+    Ok(errors == 0 && (!deny_warnings || warnings == 0))
+}
@@ -0,0 +1,63 @@
+//! Extracts the translatable messages of the templates of a crate.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::{env, fs};
+
+use askama_cli::extract::{self, Format};
+use askama_cli::{Error, Project};
+
+const USAGE: &str = "\
+Usage: askama-extract [OPTIONS]
+
+Options:
+    --root <DIR>       The crate root, containing `askama.toml` [default: .]
+    --config <FILE>    The configuration file, relative to the crate root
+    --format <FORMAT>  The output format, `pot` or `ftl` [default: pot]
+    --output <FILE>    The output file [default: stdout]
+";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("askama-extract: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Error> {
+    let mut root = PathBuf::from(".");
+    let mut config = None;
+    let mut format = Format::Pot;
+    let mut output = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| Error::from(format!("missing value for {arg}\n\n{USAGE}")))
+        };
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(value()?),
+            "--config" => config = Some(value()?),
+            "--format" => format = value()?.parse()?,
+            "--output" => output = Some(value()?),
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return Ok(());
+            }
+            _ => return Err(format!("unexpected argument {arg:?}\n\n{USAGE}").into()),
+        }
+    }
+
+    let project = Project::open(&root, config.as_deref())?;
+    let messages = extract::extract(&project)?;
+    let out = extract::write(&messages, format);
+    match output {
+        Some(path) => fs::write(&path, out).map_err(|e| format!("unable to write {path}: {e}"))?,
+        None => print!("{out}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Formats the templates of a crate.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::{env, fs};
+
+use askama_cli::{format, Error, Project};
+
+const USAGE: &str = "\
+Usage: askama-fmt [OPTIONS] [FILES]...
+
+Formats the given templates, or all templates of the crate.
+
+Options:
+    --root <DIR>     The crate root, containing `askama.toml` [default: .]
+    --config <FILE>  The configuration file, relative to the crate root
+    --check          Only list the templates that are not formatted, and fail
+                     if there are any
+    --indent <N>     Re-indent the lines that hold only a tag that trims the
+                     whitespace in front of it with `-` or `~`, by N spaces per
+                     nesting level
+";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("askama-fmt: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run() -> Result<bool, Error> {
+    let mut root = PathBuf::from(".");
+    let mut config = None;
+    let mut check = false;
+    let mut indent = None;
+    let mut files = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| Error::from(format!("missing value for {arg}\n\n{USAGE}")))
+        };
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(value()?),
+            "--config" => config = Some(value()?),
+            "--check" => check = true,
+            "--indent" => {
+                let value = value()?;
+                let width = value
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid value for --indent: {value:?}"))?;
+                indent = Some(" ".repeat(width));
+            }
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return Ok(true);
+            }
+            _ if arg.starts_with('-') => {
+                return Err(format!("unexpected argument {arg:?}\n\n{USAGE}").into())
+            }
+            _ => files.push(PathBuf::from(arg)),
+        }
+    }
+
+    let project = Project::open(&root, config.as_deref())?;
+    if files.is_empty() {
+        files = project.templates()?;
+    }
+
+    let mut formatted = true;
+    for path in &files {
+        let display = project.display_path(path).display();
+        let source =
+            fs::read_to_string(path).map_err(|e| format!("unable to read {display}: {e}"))?;
+        let output = match &indent {
+            Some(indent) => format::format_reindented(&project, path, &source, indent),
+            None => format::format(&project, path, &source),
+        }
+        .map_err(|e| format!("{display}: {e}"))?;
+        if output == source {
+            continue;
+        }
+        if check {
+            println!("{display}");
+            formatted = false;
+        } else {
+            fs::write(path, output).map_err(|e| format!("unable to write {display}: {e}"))?;
+        }
+    }
+    Ok(formatted)
+}
@@ -0,0 +1,209 @@
+//! A language server for Askama templates, communicating over stdio.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use askama_cli::analysis::{self, Position, Range};
+use askama_cli::Project;
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionOptions, CompletionParams, CompletionResponse, Diagnostic,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, Location, OneOf,
+    PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    Url,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![" ".into()]),
+            ..CompletionOptions::default()
+        }),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    Server::default().run(&connection)?;
+    // The writer thread stops when the connection is dropped.
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct Server {
+    /// The contents of the open documents
+    documents: HashMap<Url, String>,
+    /// The crates of the open documents, by crate root
+    projects: HashMap<PathBuf, Project>,
+}
+
+impl Server {
+    fn run(&mut self, connection: &Connection) -> Result<()> {
+        for msg in &connection.receiver {
+            match msg {
+                Message::Request(req) => {
+                    if connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(req);
+                    connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(not) => {
+                    if let Some(uri) = self.handle_notification(not)? {
+                        let diagnostics = self.diagnostics(&uri);
+                        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+                        let not = Notification::new(PublishDiagnostics::METHOD.into(), params);
+                        connection.sender.send(Message::Notification(not))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, req: Request) -> Response {
+        let result = match req.method.as_str() {
+            GotoDefinition::METHOD => serde_json::from_value(req.params)
+                .map(|params| serde_json::to_value(self.definition(params))),
+            Completion::METHOD => serde_json::from_value(req.params)
+                .map(|params| serde_json::to_value(self.completion(params))),
+            _ => return method_not_found(req.id, &req.method),
+        };
+        match result {
+            Ok(Ok(value)) => Response::new_ok(req.id, value),
+            Ok(Err(e)) | Err(e) => Response::new_err(
+                req.id,
+                lsp_server::ErrorCode::InvalidParams as i32,
+                e.to_string(),
+            ),
+        }
+    }
+
+    // Returns the document whose diagnostics need to be published.
+    fn handle_notification(&mut self, not: Notification) -> Result<Option<Url>> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(not.params)?;
+                let doc = params.text_document;
+                self.documents.insert(doc.uri.clone(), doc.text);
+                Ok(Some(doc.uri))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams = serde_json::from_value(not.params)?;
+                // With full synchronization, the last change has the whole document.
+                let uri = params.text_document.uri;
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                Ok(Some(uri))
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = serde_json::from_value(not.params)?;
+                self.documents.remove(&params.text_document.uri);
+                Ok(Some(params.text_document.uri))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn diagnostics(&mut self, uri: &Url) -> Vec<Diagnostic> {
+        let source = match self.documents.get(uri) {
+            Some(source) => source.clone(),
+            None => return Vec::new(),
+        };
+        let (project, path) = match self.project(uri) {
+            Some(found) => found,
+            None => return Vec::new(),
+        };
+        analysis::diagnostics(project, &path, &source)
+            .into_iter()
+            .map(|d| Diagnostic {
+                range: to_lsp_range(d.range),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("askama".into()),
+                message: d.message,
+                ..Diagnostic::default()
+            })
+            .collect()
+    }
+
+    fn definition(&mut self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let params = params.text_document_position_params;
+        let source = self.documents.get(&params.text_document.uri)?.clone();
+        let (project, path) = self.project(&params.text_document.uri)?;
+        let position = from_lsp_position(params.position);
+        let location = analysis::definition(project, &path, &source, position)?;
+        Some(GotoDefinitionResponse::Scalar(Location {
+            uri: Url::from_file_path(&location.path).ok()?,
+            range: to_lsp_range(location.range),
+        }))
+    }
+
+    fn completion(&mut self, params: CompletionParams) -> Option<CompletionResponse> {
+        let params = params.text_document_position;
+        let source = self.documents.get(&params.text_document.uri)?.clone();
+        let (project, path) = self.project(&params.text_document.uri)?;
+        let position = from_lsp_position(params.position);
+        let items = analysis::complete_blocks(project, &path, &source, position)
+            .into_iter()
+            .map(|name| CompletionItem::new_simple(name, "block".into()))
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+
+    // Returns the crate of the document at `uri`, which is the closest directory with a
+    // `Cargo.toml`, and the path of the document.
+    fn project(&mut self, uri: &Url) -> Option<(&Project, PathBuf)> {
+        let path = uri.to_file_path().ok()?;
+        let root = path
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join("Cargo.toml").is_file())
+            .map(Path::to_path_buf)?;
+        if !self.projects.contains_key(&root) {
+            match Project::open(&root, None) {
+                Ok(project) => {
+                    self.projects.insert(root.clone(), project);
+                }
+                Err(e) => {
+                    eprintln!("askama-lsp: {}: {e}", root.display());
+                    return None;
+                }
+            }
+        }
+        Some((&self.projects[&root], path))
+    }
+}
+
+fn method_not_found(id: RequestId, method: &str) -> Response {
+    Response::new_err(
+        id,
+        lsp_server::ErrorCode::MethodNotFound as i32,
+        format!("unsupported method {method:?}"),
+    )
+}
+
+fn from_lsp_position(position: lsp_types::Position) -> Position {
+    Position {
+        line: position.line,
+        character: position.character,
+    }
+}
+
+fn to_lsp_range(range: Range) -> lsp_types::Range {
+    let position = |p: Position| lsp_types::Position::new(p.line, p.character);
+    lsp_types::Range::new(position(range.start), position(range.end))
+}
@@ -0,0 +1,278 @@
+//! Checking of the templates of a crate
+//!
+//! Every template in the template directories is parsed, and the templates it
+//! uses with `extends`, `include`, `import` and `embed` are looked up the same
+//! way as when compiling. Templates that are neither used by another template
+//! nor by a `#[template(path = "...")]` attribute of the crate are reported as
+//! unreferenced.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+
+use askama_parser::{Expr, Node, WithSpan};
+
+use crate::{
+    error_line, line_of, read_template, rust_files, template_attrs, walk_nodes, Error, Project,
+};
+
+/// A problem found in the templates of a crate
+#[derive(Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The kind of problem
+    pub kind: Kind,
+    /// The file the problem was found in, relative to the crate root
+    pub path: PathBuf,
+    /// The line the problem was found on, if it is specific to a line
+    pub line: Option<usize>,
+    /// A description of the problem
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Returns `true` if the problem prevents the templates from compiling
+    pub fn is_error(&self) -> bool {
+        self.kind != Kind::Unreferenced
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.is_error() { "error" } else { "warning" };
+        write!(f, "{severity}: {}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// The kinds of problems found by [`check()`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// The template can't be parsed
+    Syntax,
+    /// A template used by a template or by the crate doesn't exist
+    Missing,
+    /// Templates that use each other
+    Cycle,
+    /// A template that isn't used by any template or by the crate
+    Unreferenced,
+}
+
+impl Kind {
+    /// Returns the name of the kind, as used in the JSON output
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Syntax => "syntax",
+            Kind::Missing => "missing",
+            Kind::Cycle => "cycle",
+            Kind::Unreferenced => "unreferenced",
+        }
+    }
+}
+
+/// Checks all templates of `project`
+///
+/// Diagnostics are returned in order of the template paths.
+pub fn check(project: &Project) -> Result<Vec<Diagnostic>, Error> {
+    let templates = project.templates()?;
+    let mut diagnostics = Vec::new();
+    let mut graph: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    let mut referenced = HashSet::new();
+
+    for path in &templates {
+        let source = read_template(path)?;
+        let nodes = match project.parse(path, &source) {
+            Ok(nodes) => nodes,
+            Err(e) => {
+                diagnostics.push(Diagnostic {
+                    kind: Kind::Syntax,
+                    path: project.display_path(path).to_path_buf(),
+                    line: e.offset().map(|offset| error_line(&source, offset)),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let mut edges = Vec::new();
+        for (names, optional) in used_templates(&nodes) {
+            let used = names
+                .iter()
+                .find_map(|name| project.config.find_template(name, Some(path)).ok());
+            match used {
+                Some(used) => {
+                    referenced.insert(used.clone());
+                    edges.push(used);
+                }
+                None if optional => {}
+                None => diagnostics.push(Diagnostic {
+                    kind: Kind::Missing,
+                    path: project.display_path(path).to_path_buf(),
+                    line: Some(line_of(&source, names[0])),
+                    message: missing_message(&names),
+                }),
+            }
+        }
+        graph.insert(path.clone(), edges);
+    }
+
+    for (prelude, _) in &project.config.prelude {
+        referenced.insert(prelude.clone());
+    }
+    for file in rust_files(&project.root, &project.config)? {
+        let source = read_template(&file)?;
+        for (name, _) in template_attrs(&source) {
+            match project.config.find_template(name, None) {
+                Ok(used) => {
+                    referenced.insert(used);
+                }
+                Err(_) => diagnostics.push(Diagnostic {
+                    kind: Kind::Missing,
+                    path: project.display_path(&file).to_path_buf(),
+                    line: Some(line_of(&source, name)),
+                    message: format!("template {name:?} not found"),
+                }),
+            }
+        }
+    }
+
+    for cycle in cycles(&templates, &graph) {
+        let names = cycle
+            .iter()
+            .chain(cycle.first())
+            .map(|path| project.display_path(path).display().to_string())
+            .collect::<Vec<_>>();
+        diagnostics.push(Diagnostic {
+            kind: Kind::Cycle,
+            path: project.display_path(&cycle[0]).to_path_buf(),
+            line: None,
+            message: format!("cyclic dependency: {}", names.join(" -> ")),
+        });
+    }
+
+    for path in &templates {
+        if !referenced.contains(path) {
+            diagnostics.push(Diagnostic {
+                kind: Kind::Unreferenced,
+                path: project.display_path(path).to_path_buf(),
+                line: None,
+                message: "template is not used by any template or by the crate".into(),
+            });
+        }
+    }
+
+    diagnostics.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    Ok(diagnostics)
+}
+
+/// Writes `diagnostics` as a JSON document
+pub fn to_json(diagnostics: &[Diagnostic]) -> String {
+    let diagnostics = diagnostics
+        .iter()
+        .map(|d| {
+            serde_json::json!({
+                "kind": d.kind.name(),
+                "severity": if d.is_error() { "error" } else { "warning" },
+                "path": d.path.display().to_string(),
+                "line": d.line,
+                "message": d.message,
+            })
+        })
+        .collect::<Vec<_>>();
+    let json = serde_json::json!({ "diagnostics": diagnostics });
+    serde_json::to_string_pretty(&json).unwrap()
+}
+
+// Returns the templates used by `nodes`, and whether they may be missing. Each of them is
+// given as a list of names, of which the first one that exists is used.
+pub(crate) fn used_templates<'a>(nodes: &'a [WithSpan<Node<'a>>]) -> Vec<(Vec<&'a str>, bool)> {
+    let mut used = Vec::new();
+    walk_nodes(nodes, &mut |node| match node {
+        Node::Extends(name)
+        | Node::Import(_, name, _)
+        | Node::FromImport(_, name, _)
+        | Node::Embed(_, name, _, _) => used.push((vec![*name], false)),
+        // Every template of an indexed list may be selected when rendering.
+        Node::Include(_, path @ Expr::Index(..), ignore_missing) => {
+            for name in include_paths(path) {
+                used.push((vec![name], *ignore_missing));
+            }
+        }
+        Node::Include(_, path, ignore_missing) => {
+            let names = include_paths(path);
+            if !names.is_empty() {
+                used.push((names, *ignore_missing));
+            }
+        }
+        _ => {}
+    });
+    used
+}
+
+// Returns the paths of an `include` tag. Only literal paths can be resolved without
+// rendering: a string, a list of strings, or an index into a list of strings.
+pub(crate) fn include_paths<'a>(path: &'a Expr<'a>) -> Vec<&'a str> {
+    let paths = match path {
+        Expr::StrLit(name) => return vec![name],
+        Expr::Array(paths) => paths,
+        Expr::Index(obj, _) => match &**obj {
+            Expr::Array(paths) => paths,
+            _ => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+    paths
+        .iter()
+        .filter_map(|path| match path {
+            Expr::StrLit(name) => Some(*name),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(crate) fn missing_message(names: &[&str]) -> String {
+    match names {
+        [name] => format!("template {name:?} not found"),
+        names => format!("none of the templates {names:?} could be found"),
+    }
+}
+
+// Finds the cycles of `graph`, each starting at its smallest path.
+fn cycles(templates: &[PathBuf], graph: &HashMap<PathBuf, Vec<PathBuf>>) -> Vec<Vec<PathBuf>> {
+    fn visit<'a>(
+        path: &'a PathBuf,
+        graph: &'a HashMap<PathBuf, Vec<PathBuf>>,
+        stack: &mut Vec<&'a PathBuf>,
+        done: &mut HashSet<&'a PathBuf>,
+        cycles: &mut Vec<Vec<PathBuf>>,
+    ) {
+        if let Some(start) = stack.iter().position(|p| *p == path) {
+            let mut cycle: Vec<PathBuf> = stack[start..].iter().map(|p| (*p).clone()).collect();
+            let min = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap();
+            cycle.rotate_left(min);
+            if !cycles.contains(&cycle) {
+                cycles.push(cycle);
+            }
+            return;
+        }
+        if done.contains(path) {
+            return;
+        }
+        stack.push(path);
+        for used in graph.get(path).into_iter().flatten() {
+            visit(used, graph, stack, done, cycles);
+        }
+        stack.pop();
+        done.insert(path);
+    }
+
+    let mut cycles = Vec::new();
+    let mut done = HashSet::new();
+    for path in templates {
+        visit(path, graph, &mut Vec::new(), &mut done, &mut cycles);
+    }
+    cycles.sort();
+    cycles
+}
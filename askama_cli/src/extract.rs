@@ -0,0 +1,301 @@
+//! Extraction of the translatable messages of templates
+//!
+//! Messages are written in `{% trans %}` blocks and with `_("key")`
+//! expressions. The extracted messages can be written as a gettext
+//! template (`.pot`) or as a Fluent (`.ftl`) skeleton for translators.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use askama_parser::{message_id, Expr, Node};
+
+use crate::{line_of, read_template, walk_exprs, walk_nodes, Error, Project};
+
+/// A translatable message
+#[derive(Debug, PartialEq, Eq)]
+pub struct Message {
+    /// The key of the message, as passed to the `Localizer`
+    pub key: String,
+    /// The Fluent identifier of the message, which is derived from the text
+    /// of `trans` blocks
+    pub id: String,
+    /// Whether the key is the text of a `trans` block
+    pub trans: bool,
+    /// The plural form of a `trans` block
+    pub plural: Option<String>,
+    /// The names of the arguments of the message
+    pub args: Vec<String>,
+    /// The locations of the message, as template path and line number
+    pub references: Vec<(PathBuf, usize)>,
+}
+
+/// The output format of the extracted messages
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// A gettext template (`.pot`)
+    Pot,
+    /// A Fluent skeleton (`.ftl`)
+    Fluent,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Format, Error> {
+        match s {
+            "pot" | "po" => Ok(Format::Pot),
+            "ftl" | "fluent" => Ok(Format::Fluent),
+            s => Err(format!("unknown format {s:?}, expected `pot` or `ftl`").into()),
+        }
+    }
+}
+
+/// Extracts the messages of all templates of `project`
+///
+/// Messages are returned in order of their first occurrence, and messages
+/// with the same key and plural form are merged.
+pub fn extract(project: &Project) -> Result<Vec<Message>, Error> {
+    let mut messages: Vec<Message> = Vec::new();
+    for path in project.templates()? {
+        let source = read_template(&path)?;
+        let nodes = project
+            .parse(&path, &source)
+            .map_err(|e| format!("{}: {e}", project.display_path(&path).display()))?;
+
+        let mut found = Vec::new();
+        let mut error = None;
+        walk_nodes(&nodes, &mut |node| {
+            if let Node::Trans(trans) = node {
+                match trans.message() {
+                    Ok((key, plural, mut names)) => {
+                        for (name, _) in &trans.vars {
+                            if !names.contains(name) {
+                                names.push(name);
+                            }
+                        }
+                        let line = trans_line(&source, node);
+                        let id = message_id(&key);
+                        found.push((key, id, true, plural, names, line));
+                    }
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                }
+            }
+            walk_exprs(node, &mut |expr| {
+                if let Some((key, names)) = localize_call(expr) {
+                    let line = line_of(&source, key);
+                    let key = unescape(key);
+                    found.push((key.clone(), key, false, None, names, line));
+                }
+            });
+        });
+        if let Some(e) = error {
+            return Err(format!("{}: {e}", project.display_path(&path).display()).into());
+        }
+
+        let reference = project.display_path(&path).to_path_buf();
+        for (key, id, trans, plural, names, line) in found {
+            let position = messages
+                .iter()
+                .position(|m| m.key == key && m.trans == trans && m.plural == plural);
+            let message = match position {
+                Some(i) => &mut messages[i],
+                None => {
+                    messages.push(Message {
+                        key,
+                        id,
+                        trans,
+                        plural,
+                        args: Vec::new(),
+                        references: Vec::new(),
+                    });
+                    messages.last_mut().unwrap()
+                }
+            };
+            for name in names {
+                if !message.args.iter().any(|arg| arg == name) {
+                    message.args.push(name.to_string());
+                }
+            }
+            message.references.push((reference.clone(), line));
+        }
+    }
+    Ok(messages)
+}
+
+/// Writes `messages` in the given `format`
+///
+/// Fluent messages are written with their identifier. The messages of `trans`
+/// blocks get their text as value, with a selector on `count` for plural
+/// forms, and other messages get their arguments as placeholders. `_()` keys
+/// that aren't valid Fluent identifiers are written as comments instead, so
+/// they can be renamed.
+pub fn write(messages: &[Message], format: Format) -> String {
+    match format {
+        Format::Pot => write_pot(messages),
+        Format::Fluent => write_fluent(messages),
+    }
+}
+
+fn write_pot(messages: &[Message]) -> String {
+    let mut out = String::new();
+    out.push_str("msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    for message in messages {
+        out.push('\n');
+        for (path, line) in &message.references {
+            let _ = writeln!(out, "#: {}:{line}", path.display());
+        }
+        let _ = writeln!(out, "msgid {}", po_string(&message.key));
+        match &message.plural {
+            Some(plural) => {
+                let _ = writeln!(out, "msgid_plural {}", po_string(plural));
+                out.push_str("msgstr[0] \"\"\nmsgstr[1] \"\"\n");
+            }
+            None => out.push_str("msgstr \"\"\n"),
+        }
+    }
+    out
+}
+
+fn write_fluent(messages: &[Message]) -> String {
+    let mut out = String::new();
+    for (i, message) in messages.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        for (path, line) in &message.references {
+            let _ = writeln!(out, "# {}:{line}", path.display());
+        }
+        if !is_fluent_identifier(&message.id) {
+            // Not a valid identifier, so the message needs to be renamed.
+            let _ = writeln!(out, "# {:?}", message.key);
+            continue;
+        }
+        if message.trans {
+            let singular = fluent_pattern(&message.key, &message.args);
+            match &message.plural {
+                Some(plural) => {
+                    let plural = fluent_pattern(plural, &message.args);
+                    let _ = writeln!(out, "{} = {{ $count ->", message.id);
+                    let _ = writeln!(out, "        [one] {singular}");
+                    let _ = writeln!(out, "       *[other] {plural}");
+                    out.push_str("    }\n");
+                }
+                None => {
+                    let _ = writeln!(out, "{} = {singular}", message.id);
+                }
+            }
+        } else {
+            let args = message
+                .args
+                .iter()
+                .map(|arg| format!("{{ ${arg} }}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let _ = writeln!(out, "{} = {args}", message.id);
+        }
+    }
+    out
+}
+
+// Writes the text of a `trans` block as a Fluent pattern: its variables become placeables,
+// other braces are quoted, and continuation lines are indented.
+fn fluent_pattern(text: &str, args: &[String]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let var = rest[1..]
+            .find('}')
+            .map(|end| &rest[1..end + 1])
+            .filter(|name| rest.starts_with('{') && args.iter().any(|arg| arg == name));
+        match var {
+            Some(name) => {
+                let _ = write!(out, "{{ ${name} }}");
+                rest = &rest[name.len() + 2..];
+            }
+            None => {
+                let _ = write!(out, "{{ \"{}\" }}", &rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.replace('\n', "\n    ")
+}
+
+// `trans` blocks start with their first literal or variable.
+fn trans_line(source: &str, node: &Node<'_>) -> usize {
+    let first = match node {
+        Node::Trans(trans) => trans.singular.iter().find_map(|node| match **node {
+            Node::Lit(lws, _, _) => Some(lws),
+            Node::Expr(_, Expr::Var(name)) => Some(name),
+            _ => None,
+        }),
+        _ => None,
+    };
+    first.map_or(1, |s| line_of(source, s))
+}
+
+// Returns the key and the argument names of a `_("key", name = value)` call.
+fn localize_call<'a>(expr: &'a Expr<'a>) -> Option<(&'a str, Vec<&'a str>)> {
+    let args = match expr {
+        Expr::Call(obj, args) if matches!(**obj, Expr::Var("_")) => args,
+        _ => return None,
+    };
+    let (key, args) = match args.split_first() {
+        Some((Expr::StrLit(key), args)) => (*key, args),
+        _ => return None,
+    };
+    let mut names = Vec::new();
+    for arg in args {
+        if let Expr::NamedArgument(name, _) = arg {
+            names.push(*name);
+        }
+    }
+    Some((key, names))
+}
+
+fn is_fluent_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// String literals of templates are written like Rust string literals.
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('r') => out.push('\r'),
+                Some(c) => out.push(c),
+                None => out.push('\\'),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn po_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,360 @@
+//! Formatting of templates
+//!
+//! Templates are parsed and printed again from their syntax tree. Tags are
+//! written with a single space between the delimiters and their contents, as in
+//! `{%- if x -%}`, and expressions are written with consistent spacing. The
+//! text of a template, including the contents of `{% raw %}` blocks, is never
+//! changed, so formatting doesn't change what a template renders.
+//!
+//! The whitespace in front of a tag is part of the text of the template and is
+//! rendered like any other text, so [`format()`] leaves the indentation of tags
+//! as written. [`format_reindented()`] also re-indents the lines that hold only a
+//! tag by its nesting level, if the tag trims the whitespace in front of it with
+//! `-` or `~`. The trimmed whitespace is never rendered (`~` keeps only the line
+//! break), so this doesn't change the output either.
+
+use std::path::Path;
+
+use askama_parser::{Expr, Node, Syntax, Target, Whitespace, WithSpan, Ws};
+
+use crate::{Error, Project};
+
+/// Returns `source`, the contents of the template at `path`, formatted with
+/// the syntax of the template
+pub fn format(project: &Project, path: &Path, source: &str) -> Result<String, Error> {
+    Ok(Formatter::run(project, path, source)?.out)
+}
+
+/// Like [`format()`], but also re-indents the lines that hold only a tag that trims the
+/// whitespace in front of it, with `indent` per nesting level
+pub fn format_reindented(
+    project: &Project,
+    path: &Path,
+    source: &str,
+    indent: &str,
+) -> Result<String, Error> {
+    let f = Formatter::run(project, path, source)?;
+    let mut out = f.out;
+    for line in f.tag_lines.iter().rev() {
+        // Only lines that hold nothing but the tag are re-indented.
+        let rest = &out[line.tag_end..];
+        let rest = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        if rest.trim().is_empty() {
+            out.replace_range(line.start..line.tag_start, &indent.repeat(line.depth));
+        }
+    }
+    Ok(out)
+}
+
+struct Formatter<'a> {
+    syntax: &'a Syntax<'a>,
+    out: String,
+    // The nesting level of the tags that are written.
+    depth: usize,
+    // The tags at the start of a line that trim the whitespace in front of them.
+    tag_lines: Vec<TagLine>,
+}
+
+struct TagLine {
+    start: usize,
+    tag_start: usize,
+    tag_end: usize,
+    depth: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn run(project: &'a Project, path: &Path, source: &str) -> Result<Self, Error> {
+        let nodes = project.parse(path, source)?;
+        let mut f = Formatter {
+            syntax: project.syntax(path),
+            out: String::with_capacity(source.len()),
+            depth: 0,
+            tag_lines: Vec::new(),
+        };
+        f.nodes(&nodes);
+
+        // Guard against formatting bugs, which would silently change templates.
+        let formatted = project.parse(path, &f.out)?;
+        if formatted != nodes {
+            return Err("formatting changes the meaning of the template".into());
+        }
+        Ok(f)
+    }
+
+    fn nodes(&mut self, nodes: &[WithSpan<Node<'_>>]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn nested(&mut self, levels: usize, nodes: &[WithSpan<Node<'_>>]) {
+        self.depth += levels;
+        self.nodes(nodes);
+        self.depth -= levels;
+    }
+
+    fn node(&mut self, node: &Node<'_>) {
+        match node {
+            Node::Lit(lws, val, rws) => {
+                self.out.push_str(lws);
+                self.out.push_str(val);
+                self.out.push_str(rws);
+            }
+            Node::Comment(ws, text) => {
+                self.out.push_str(self.syntax.comment_start);
+                self.out.push_str(ws_char(ws.0));
+                self.out.push(' ');
+                if !text.is_empty() {
+                    self.out.push_str(text);
+                    self.out.push(' ');
+                }
+                self.out.push_str(ws_char(ws.1));
+                self.out.push_str(self.syntax.comment_end);
+            }
+            Node::Expr(ws, expr) => {
+                self.out.push_str(self.syntax.expr_start);
+                self.out.push_str(ws_char(ws.0));
+                self.out.push(' ');
+                self.out.push_str(&format_expr(expr));
+                self.out.push(' ');
+                self.out.push_str(ws_char(ws.1));
+                self.out.push_str(self.syntax.expr_end);
+            }
+            Node::Call(ws, scope, name, args) => {
+                let scope = scope.map(|scope| format!("{scope}::")).unwrap_or_default();
+                self.tag(*ws, &format!("call {scope}{name}({})", format_list(args)));
+            }
+            Node::LetDecl(ws, target) => self.tag(*ws, &format!("let {}", format_target(target))),
+            Node::Let(ws, target, expr) => {
+                let (target, expr) = (format_target(target), format_expr(expr));
+                self.tag(*ws, &format!("let {target} = {expr}"));
+            }
+            Node::Cond(conds, end_ws) => {
+                for (i, (ws, test, nodes)) in conds.iter().enumerate() {
+                    let mut tag = String::from(if i == 0 { "if" } else { "else" });
+                    if let Some(test) = test {
+                        if i > 0 {
+                            tag.push_str(" if");
+                        }
+                        if let Some(target) = &test.target {
+                            tag.push_str(" let ");
+                            tag.push_str(&format_target(target));
+                            tag.push_str(" =");
+                        }
+                        tag.push(' ');
+                        tag.push_str(&format_expr(&test.expr));
+                    }
+                    self.tag(*ws, &tag);
+                    self.nested(1, nodes);
+                }
+                self.tag(*end_ws, "endif");
+            }
+            Node::Match(ws1, expr, prefix, arms, ws2) => {
+                self.tag(*ws1, &format!("match {}", format_expr(expr)));
+                self.nested(1, prefix);
+                self.depth += 1;
+                for (i, (ws, target, nodes)) in arms.iter().enumerate() {
+                    match target {
+                        Target::Name("_") if i > 0 && i == arms.len() - 1 => self.tag(*ws, "else"),
+                        target => self.tag(*ws, &format!("when {}", format_target(target))),
+                    }
+                    self.nested(1, nodes);
+                }
+                self.depth -= 1;
+                self.tag(*ws2, "endmatch");
+            }
+            Node::Loop(l) => {
+                let mut tag = format!("for {} in {}", format_target(&l.var), format_expr(&l.iter));
+                if let Some(cond) = &l.cond {
+                    tag.push_str(" if ");
+                    tag.push_str(&format_expr(cond));
+                }
+                self.tag(l.ws1, &tag);
+                self.nested(1, &l.body);
+                // An empty `else` block can only be left out if it has no whitespace control.
+                if l.else_block.is_empty() && l.ws2.1.is_none() && l.ws3.0.is_none() {
+                    self.tag(Ws(l.ws2.0, l.ws3.1), "endfor");
+                } else {
+                    self.tag(l.ws2, "else");
+                    self.nested(1, &l.else_block);
+                    self.tag(l.ws3, "endfor");
+                }
+            }
+            Node::Extends(path) => self.tag(Ws(None, None), &format!("extends \"{path}\"")),
+            Node::BlockDef(def) => {
+                let mut tag = format!("block {}", def.name);
+                if def.required {
+                    tag.push_str(" required");
+                }
+                self.tag(def.ws1, &tag);
+                self.nested(1, &def.nodes);
+                self.tag(def.ws2, "endblock");
+            }
+            Node::Include(ws, path, ignore_missing) => {
+                let mut tag = format!("include {}", format_expr(path));
+                if *ignore_missing {
+                    tag.push_str(" ignore missing");
+                }
+                self.tag(*ws, &tag);
+            }
+            Node::Embed(ws1, path, nodes, ws2) => {
+                self.tag(*ws1, &format!("embed \"{path}\""));
+                self.nested(1, nodes);
+                self.tag(*ws2, "endembed");
+            }
+            Node::Import(ws, path, scope) => {
+                self.tag(*ws, &format!("import \"{path}\" as {scope}"));
+            }
+            Node::FromImport(ws, path, names) => {
+                let names = names
+                    .iter()
+                    .map(|(name, alias)| {
+                        if name == alias {
+                            name.to_string()
+                        } else {
+                            format!("{name} as {alias}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.tag(*ws, &format!("from \"{path}\" import {names}"));
+            }
+            Node::Macro(name, m) => {
+                self.tag(m.ws1, &format!("macro {name}({})", m.args.join(", ")));
+                self.nested(1, &m.nodes);
+                self.tag(m.ws2, "endmacro");
+            }
+            Node::Trans(trans) => {
+                let vars = trans
+                    .vars
+                    .iter()
+                    .map(|(name, expr)| format!(" {name} = {}", format_expr(expr)))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                self.tag(trans.ws1, &format!("trans{vars}"));
+                self.nested(1, &trans.singular);
+                if let Some((ws, nodes)) = &trans.plural {
+                    self.tag(*ws, "pluralize");
+                    self.nested(1, nodes);
+                }
+                self.tag(trans.ws2, "endtrans");
+            }
+            Node::Raw(ws1, lws, val, rws, ws2) => {
+                self.tag(*ws1, "raw");
+                self.out.push_str(lws);
+                self.out.push_str(val);
+                self.out.push_str(rws);
+                // The contents of the block are left alone, including the line of `endraw`.
+                self.out.push_str(self.syntax.block_start);
+                self.tag_contents(*ws2, "endraw");
+            }
+            Node::Break(ws) => self.tag(*ws, "break"),
+            Node::Continue(ws) => self.tag(*ws, "continue"),
+            // Nodes added to the parser later are left out, which the guard in `format()`
+            // reports as an error.
+            _ => {}
+        }
+    }
+
+    fn tag(&mut self, ws: Ws, contents: &str) {
+        // Without a line break in front of it, `~` would keep a space that re-indenting
+        // with an empty indentation removes.
+        let start = self.out.rfind('\n').map(|i| i + 1);
+        let tag_start = self.out.len();
+        self.out.push_str(self.syntax.block_start);
+        self.tag_contents(ws, contents);
+
+        let trims = matches!(ws.0, Some(Whitespace::Suppress | Whitespace::Minimize));
+        let start = start.filter(|start| self.out[*start..tag_start].trim().is_empty());
+        if let (true, Some(start)) = (trims, start) {
+            self.tag_lines.push(TagLine {
+                start,
+                tag_start,
+                tag_end: self.out.len(),
+                depth: self.depth,
+            });
+        }
+    }
+
+    fn tag_contents(&mut self, ws: Ws, contents: &str) {
+        self.out.push_str(ws_char(ws.0));
+        self.out.push(' ');
+        self.out.push_str(contents);
+        self.out.push(' ');
+        self.out.push_str(ws_char(ws.1));
+        self.out.push_str(self.syntax.block_end);
+    }
+}
+
+fn ws_char(ws: Option<Whitespace>) -> &'static str {
+    match ws {
+        Some(Whitespace::Preserve) => "+",
+        Some(Whitespace::Suppress) => "-",
+        Some(Whitespace::Minimize) => "~",
+        None => "",
+    }
+}
+
+fn format_expr(expr: &Expr<'_>) -> String {
+    match expr {
+        Expr::BoolLit(s) | Expr::NumLit(s) | Expr::Var(s) => s.to_string(),
+        Expr::StrLit(s) => format!("\"{s}\""),
+        Expr::CharLit(s) => format!("'{s}'"),
+        Expr::Path(path) => path.join("::"),
+        Expr::Array(items) => format!("[{}]", format_list(items)),
+        Expr::Attr(obj, attr) => format!("{}.{attr}", format_expr(obj)),
+        Expr::Index(obj, key) => format!("{}[{}]", format_expr(obj), format_expr(key)),
+        Expr::Filter(name, args) => match args.split_first() {
+            Some((obj, [])) => format!("{}|{name}", format_expr(obj)),
+            Some((obj, args)) => format!("{}|{name}({})", format_expr(obj), format_list(args)),
+            None => name.to_string(),
+        },
+        Expr::Unary(op, expr) => format!("{op}{}", format_expr(expr)),
+        Expr::BinOp(op, lhs, rhs) => format!("{} {op} {}", format_expr(lhs), format_expr(rhs)),
+        Expr::Range(op, lhs, rhs) => {
+            let lhs = lhs.as_deref().map(format_expr).unwrap_or_default();
+            let rhs = rhs.as_deref().map(format_expr).unwrap_or_default();
+            format!("{lhs}{op}{rhs}")
+        }
+        Expr::Group(expr) => format!("({})", format_expr(expr)),
+        Expr::Tuple(items) if items.len() == 1 => format!("({},)", format_expr(&items[0])),
+        Expr::Tuple(items) => format!("({})", format_list(items)),
+        Expr::Call(obj, args) => format!("{}({})", format_expr(obj), format_list(args)),
+        Expr::RustMacro(name, args) => format!("{name}!({args})"),
+        Expr::Try(expr) => format!("{}?", format_expr(expr)),
+        Expr::NamedArgument(name, expr) => format!("{name} = {}", format_expr(expr)),
+        // Like unknown nodes, unknown expressions make the guard in `format()` fail.
+        _ => String::new(),
+    }
+}
+
+fn format_list(exprs: &[Expr<'_>]) -> String {
+    exprs.iter().map(format_expr).collect::<Vec<_>>().join(", ")
+}
+
+fn format_target(target: &Target<'_>) -> String {
+    match target {
+        Target::Name(s) | Target::NumLit(s) | Target::BoolLit(s) => s.to_string(),
+        Target::StrLit(s) => format!("\"{s}\""),
+        Target::CharLit(s) => format!("'{s}'"),
+        Target::Path(path) => path.join("::"),
+        Target::Tuple(path, targets) if path.is_empty() && targets.len() == 1 => {
+            format!("({},)", format_target(&targets[0]))
+        }
+        Target::Tuple(path, targets) => {
+            let targets = targets.iter().map(format_target).collect::<Vec<_>>();
+            format!("{}({})", path.join("::"), targets.join(", "))
+        }
+        Target::Struct(path, fields) if fields.is_empty() => format!("{} {{}}", path.join("::")),
+        Target::Struct(path, fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, target)| match target {
+                    Target::Name(target) if target == name => name.to_string(),
+                    target => format!("{name}: {}", format_target(target)),
+                })
+                .collect::<Vec<_>>();
+            format!("{} {{ {} }}", path.join("::"), fields.join(", "))
+        }
+    }
+}
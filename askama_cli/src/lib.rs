@@ -0,0 +1,358 @@
+//! Command-line tools for Askama templates
+//!
+//! The tools share the configuration handling of `askama_derive`, from
+//! `askama_config`, and parse templates with `askama_parser`, so templates are
+//! read exactly as they are when compiling the crate that uses them.
+
+#![deny(elided_lifetimes_in_paths)]
+
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::{fmt, fs};
+
+pub mod analysis;
+pub mod check;
+pub mod extract;
+pub mod format;
+
+use askama_config::config::{read_config_file_in, Config};
+use askama_parser::{json, Expr, Node, ParseError, Syntax, WithSpan};
+
+use check::used_templates;
+
+/// A crate whose templates the tools operate on
+pub struct Project {
+    root: PathBuf,
+    config: Config<'static>,
+    // The names of the syntaxes of templates that don't use the default syntax.
+    syntaxes: HashMap<PathBuf, String>,
+}
+
+impl Project {
+    /// Reads the configuration of the crate at `root`, from `askama.toml` or
+    /// from the file at `config_path`, relative to `root`. The translation
+    /// catalogs aren't read, so they don't need to exist yet.
+    ///
+    /// The syntax of each template is taken from the `#[template]` attributes
+    /// of the crate, see [`Project::syntax()`].
+    pub fn open(root: &Path, config_path: Option<&str>) -> Result<Project, Error> {
+        // The configuration borrows from its source for the lifetime of the tool.
+        let source: &'static str = Box::leak(read_config_file_in(root, config_path)?.into());
+        let config = Config::with_root(root, source, None)?;
+        let mut project = Project {
+            root: root.to_path_buf(),
+            config,
+            syntaxes: HashMap::new(),
+        };
+        project.syntaxes = project.template_syntaxes()?;
+        Ok(project)
+    }
+
+    /// Returns the paths of all files in the template directories, in sorted order
+    pub fn templates(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut paths = Vec::new();
+        for dir in &self.config.dirs {
+            if dir.is_dir() {
+                collect_files(dir, &mut paths)?;
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+
+    /// Returns `path` relative to the crate root, for display
+    pub fn display_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    /// Returns the syntax tree of `source`, the contents of the template at
+    /// `path`, as JSON, like `print = "json"` does
+    pub fn ast_json(&self, path: &Path, source: &str) -> Result<String, Error> {
+        let nodes = self.parse(path, source)?;
+        let path = self.display_path(path).to_string_lossy();
+        Ok(format!(
+            "{{\"version\":{},\"path\":{},\"nodes\":{}}}",
+            json::VERSION,
+            serde_json::Value::from(path),
+            json::nodes_to_json(source, &nodes),
+        ))
+    }
+
+    /// Returns the syntax of the template at `path`
+    ///
+    /// Like when compiling, a template uses the syntax given in the
+    /// `#[template]` attribute that refers to it, and the templates it extends,
+    /// includes or imports use the same syntax. Other templates use the default
+    /// syntax of the configuration.
+    pub fn syntax(&self, path: &Path) -> &Syntax<'static> {
+        let name = self
+            .syntaxes
+            .get(&canonical(path))
+            .map_or(self.config.default_syntax, |name| name.as_str());
+        &self.config.syntaxes[name]
+    }
+
+    fn parse<'a>(
+        &self,
+        path: &Path,
+        source: &'a str,
+    ) -> Result<Vec<WithSpan<Node<'a>>>, ParseError> {
+        askama_parser::parse(source, self.syntax(path))
+    }
+
+    // Finds the templates of the `#[template]` attributes of the crate, and gives them and
+    // the templates they use the syntax of the attribute. If a template is used with several
+    // syntaxes, the first one found is used.
+    fn template_syntaxes(&self) -> Result<HashMap<PathBuf, String>, Error> {
+        let mut queue = VecDeque::new();
+        for file in rust_files(&self.root, &self.config)? {
+            let source = read_template(&file)?;
+            for (path, syntax) in template_attrs(&source) {
+                let syntax = syntax.unwrap_or(self.config.default_syntax);
+                if let Ok(path) = self.config.find_template(path, None) {
+                    if self.config.syntaxes.contains_key(syntax) {
+                        queue.push_back((path, syntax.to_string()));
+                    }
+                }
+            }
+        }
+
+        let mut syntaxes = HashMap::new();
+        while let Some((path, syntax)) = queue.pop_front() {
+            if syntaxes.contains_key(&canonical(&path)) {
+                continue;
+            }
+            // Problems with the template are reported when it is checked.
+            if let Ok(source) = read_template(&path) {
+                let nodes = askama_parser::parse(&source, &self.config.syntaxes[&syntax]);
+                for (names, _) in nodes.iter().flat_map(|nodes| used_templates(nodes)) {
+                    let used = names
+                        .iter()
+                        .find_map(|name| self.config.find_template(name, Some(&path)).ok());
+                    if let Some(used) = used {
+                        queue.push_back((used, syntax.clone()));
+                    }
+                }
+            }
+            syntaxes.insert(canonical(&path), syntax);
+        }
+        syntaxes.retain(|_, syntax| syntax != self.config.default_syntax);
+        Ok(syntaxes)
+    }
+}
+
+// Returns the `path` and `syntax` arguments of the `#[template(...)]` attributes in `source`
+// that have a `path`.
+pub(crate) fn template_attrs(source: &str) -> Vec<(&str, Option<&str>)> {
+    let mut attrs = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("#[template(") {
+        rest = &rest[start + "#[template(".len()..];
+        let attr = &rest[..rest.find(")]").unwrap_or(rest.len())];
+        let arg = |name: &str| {
+            attr.split(',').find_map(|arg| {
+                arg.trim_start()
+                    .strip_prefix(name)
+                    .and_then(|arg| arg.trim_start().strip_prefix('='))
+                    .and_then(|arg| arg.trim_start().strip_prefix('"'))
+                    .and_then(|arg| arg.split('"').next())
+            })
+        };
+        if let Some(path) = arg("path") {
+            attrs.push((path, arg("syntax")));
+        }
+    }
+    attrs
+}
+
+// The Rust files of the crate, not including the build output and the templates.
+pub(crate) fn rust_files(root: &Path, config: &Config<'_>) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    for dir in ["src", "tests", "examples", "benches"] {
+        let dir = root.join(dir);
+        if dir.is_dir() {
+            collect_files(&dir, &mut files)?;
+        }
+    }
+    files.retain(|file| {
+        file.extension().map_or(false, |ext| ext == "rs")
+            && !config.dirs.iter().any(|dir| file.starts_with(dir))
+    });
+    files.sort();
+    Ok(files)
+}
+
+// Template paths are compared as absolute paths, since the paths given to the tools may be
+// relative.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn collect_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("unable to read {}: {e}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("unable to read {}: {e}", dir.display()))?
+            .path();
+        if path.is_dir() {
+            collect_files(&path, paths)?;
+        } else {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_template(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|e| format!("unable to read {}: {e}", path.display()).into())
+}
+
+/// Returns the line number of `s`, which must be a slice of `source`
+fn line_of(source: &str, s: &str) -> usize {
+    source[..offset_of(source, s)].matches('\n').count() + 1
+}
+
+/// Returns the line number of a syntax error at the byte `offset` of `source`,
+/// which is the row in the message of the error
+fn error_line(source: &str, offset: usize) -> usize {
+    source[..offset].lines().count().max(1)
+}
+
+/// Returns the byte offset of `s`, which must be a slice of `source`
+fn offset_of(source: &str, s: &str) -> usize {
+    (s.as_ptr() as usize)
+        .saturating_sub(source.as_ptr() as usize)
+        .min(source.len())
+}
+
+/// Calls `f` for every node of `nodes` and of their nested nodes, in source order
+fn walk_nodes<'a>(nodes: &'a [WithSpan<Node<'a>>], f: &mut impl FnMut(&'a Node<'a>)) {
+    for node in nodes {
+        f(node);
+        match &**node {
+            Node::Cond(conds, _) => {
+                for (_, _, nodes) in conds {
+                    walk_nodes(nodes, f);
+                }
+            }
+            Node::Match(_, _, _, arms, _) => {
+                for (_, _, nodes) in arms {
+                    walk_nodes(nodes, f);
+                }
+            }
+            Node::Loop(l) => {
+                walk_nodes(&l.body, f);
+                walk_nodes(&l.else_block, f);
+            }
+            Node::BlockDef(def) => walk_nodes(&def.nodes, f),
+            Node::Embed(_, _, nodes, _) => walk_nodes(nodes, f),
+            Node::Macro(_, m) => walk_nodes(&m.nodes, f),
+            Node::Trans(trans) => {
+                walk_nodes(&trans.singular, f);
+                if let Some((_, nodes)) = &trans.plural {
+                    walk_nodes(nodes, f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Calls `f` for every expression of `node` and their subexpressions, not including the
+/// expressions of nested nodes
+fn walk_exprs<'a>(node: &'a Node<'a>, f: &mut impl FnMut(&'a Expr<'a>)) {
+    match node {
+        Node::Expr(_, expr) | Node::Let(_, _, expr) | Node::Include(_, expr, _) => {
+            walk_expr(expr, f)
+        }
+        Node::Call(_, _, _, args) => args.iter().for_each(|arg| walk_expr(arg, f)),
+        Node::Cond(conds, _) => {
+            for (_, test, _) in conds {
+                if let Some(test) = test {
+                    walk_expr(&test.expr, f);
+                }
+            }
+        }
+        Node::Match(_, expr, _, _, _) => walk_expr(expr, f),
+        Node::Loop(l) => {
+            walk_expr(&l.iter, f);
+            if let Some(cond) = &l.cond {
+                walk_expr(cond, f);
+            }
+        }
+        Node::Trans(trans) => trans.vars.iter().for_each(|(_, expr)| walk_expr(expr, f)),
+        _ => {}
+    }
+}
+
+fn walk_expr<'a>(expr: &'a Expr<'a>, f: &mut impl FnMut(&'a Expr<'a>)) {
+    f(expr);
+    match expr {
+        Expr::Array(exprs) | Expr::Filter(_, exprs) | Expr::Tuple(exprs) => {
+            exprs.iter().for_each(|expr| walk_expr(expr, f))
+        }
+        Expr::Attr(expr, _)
+        | Expr::Unary(_, expr)
+        | Expr::Group(expr)
+        | Expr::Try(expr)
+        | Expr::NamedArgument(_, expr) => walk_expr(expr, f),
+        Expr::Index(lhs, rhs) | Expr::BinOp(_, lhs, rhs) => {
+            walk_expr(lhs, f);
+            walk_expr(rhs, f);
+        }
+        Expr::Range(_, lhs, rhs) => {
+            lhs.iter().chain(rhs).for_each(|expr| walk_expr(expr, f));
+        }
+        Expr::Call(obj, args) => {
+            walk_expr(obj, f);
+            args.iter().for_each(|arg| walk_expr(arg, f));
+        }
+        _ => {}
+    }
+}
+
+/// An error reported by the tools
+#[derive(Debug, Clone)]
+pub struct Error {
+    msg: Cow<'static, str>,
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.msg)
+    }
+}
+
+impl From<&'static str> for Error {
+    #[inline]
+    fn from(s: &'static str) -> Self {
+        Self { msg: s.into() }
+    }
+}
+
+impl From<String> for Error {
+    #[inline]
+    fn from(s: String) -> Self {
+        Self { msg: s.into() }
+    }
+}
+
+impl From<askama_config::CompileError> for Error {
+    #[inline]
+    fn from(e: askama_config::CompileError) -> Self {
+        e.to_string().into()
+    }
+}
+
+impl From<ParseError> for Error {
+    #[inline]
+    fn from(e: ParseError) -> Self {
+        e.to_string().into()
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+
+use askama_cli::analysis::{self, Location, Position, Range};
+use askama_cli::Project;
+
+fn project() -> (Project, PathBuf) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/project");
+    let project = Project::open(&root, None).unwrap();
+    (project, root.join("templates/article.html"))
+}
+
+fn pos(line: u32, character: u32) -> Position {
+    Position { line, character }
+}
+
+fn range(start: Position, end: Position) -> Range {
+    Range { start, end }
+}
+
+#[test]
+fn test_diagnostics() {
+    let (project, path) = project();
+    let diagnostics = analysis::diagnostics(&project, &path, "{% if x %}\n{% el %}");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].range, range(pos(1, 3), pos(1, 3)));
+
+    let source = "<p>\n  {% include \"nope.html\" %}";
+    let diagnostics = analysis::diagnostics(&project, &path, source);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].range, range(pos(1, 14), pos(1, 23)));
+    assert_eq!(diagnostics[0].message, "template \"nope.html\" not found");
+
+    let source = "{% if x %}{% import \"macros.html\" as m %}{% endif %}";
+    let diagnostics = analysis::diagnostics(&project, &path, source);
+    assert_eq!(
+        diagnostics[0].message,
+        "extends, macro or import blocks not allowed below top level"
+    );
+    assert_eq!(diagnostics[0].range, range(pos(0, 10), pos(0, 41)));
+
+    assert!(analysis::diagnostics(&project, &path, "{% extends \"page.html\" %}").is_empty());
+}
+
+#[test]
+fn test_definition_of_template() {
+    let (project, path) = project();
+    let source = "{% extends \"layout.html\" %}";
+    assert_eq!(
+        analysis::definition(&project, &path, source, pos(0, 15)),
+        Some(Location {
+            path: project_file("layout.html"),
+            range: Range::default(),
+        })
+    );
+    assert_eq!(
+        analysis::definition(&project, &path, source, pos(0, 1)),
+        None
+    );
+}
+
+#[test]
+fn test_definition_of_macro() {
+    let (project, path) = project();
+    let button = Location {
+        path: project_file("macros.html"),
+        range: range(pos(0, 9), pos(0, 15)),
+    };
+
+    let source = "{% import \"macros.html\" as m %}\n{% call m::button(\"Go\") %}";
+    assert_eq!(
+        analysis::definition(&project, &path, source, pos(1, 12)),
+        Some(button)
+    );
+
+    let source = "{% from \"macros.html\" import button as b %}{% call b(\"Go\") %}";
+    assert_eq!(
+        analysis::definition(&project, &path, source, pos(0, 52)),
+        Some(Location {
+            path: project_file("macros.html"),
+            range: range(pos(0, 9), pos(0, 15)),
+        })
+    );
+
+    // Macros of the parent templates can be called, too.
+    let source = "{% extends \"page.html\" %}{% block body %}{% call link(\"/\") %}{% endblock %}";
+    assert_eq!(
+        analysis::definition(&project, &path, source, pos(0, 51)),
+        Some(Location {
+            path: project_file("layout.html"),
+            range: range(pos(2, 9), pos(2, 13)),
+        })
+    );
+}
+
+#[test]
+fn test_complete_blocks() {
+    let (project, path) = project();
+    let source = "{% extends \"page.html\" %}\n{% block ";
+    assert_eq!(
+        analysis::complete_blocks(&project, &path, source, pos(1, 9)),
+        vec!["body", "nav", "title"]
+    );
+    let source = "{% extends \"page.html\" %}\n{%- block n";
+    assert_eq!(
+        analysis::complete_blocks(&project, &path, source, pos(1, 11)),
+        vec!["nav"]
+    );
+    let source = "{% extends \"page.html\" %}\n{% if ";
+    assert!(analysis::complete_blocks(&project, &path, source, pos(1, 6)).is_empty());
+}
+
+fn project_file(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/project/templates")
+        .join(name)
+}
@@ -0,0 +1,22 @@
+use std::fs;
+use std::path::Path;
+
+use askama_cli::Project;
+use serde_json::Value;
+
+#[test]
+fn test_ast_json() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/project");
+    let project = Project::open(&root, None).unwrap();
+    let path = root.join("templates/page.html");
+    let source = fs::read_to_string(&path).unwrap();
+    let json: Value = serde_json::from_str(&project.ast_json(&path, &source).unwrap()).unwrap();
+
+    assert_eq!(json["version"], 1);
+    assert_eq!(json["path"], "templates/page.html");
+    let nodes = json["nodes"].as_array().unwrap();
+    assert_eq!(nodes[0]["type"], "extends");
+    assert_eq!(nodes[0]["path"], "layout.html");
+    let offset = nodes[0]["offset"].as_u64().unwrap() as usize;
+    assert!(source[offset..].starts_with("layout.html"));
+}
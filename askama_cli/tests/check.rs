@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+
+use askama_cli::check::{self, Diagnostic, Kind};
+use askama_cli::Project;
+
+fn project() -> Project {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/check");
+    Project::open(&root, None).unwrap()
+}
+
+fn diagnostic(kind: Kind, path: &str, line: Option<usize>, message: &str) -> Diagnostic {
+    Diagnostic {
+        kind,
+        path: PathBuf::from(path),
+        line,
+        message: message.into(),
+    }
+}
+
+#[test]
+fn test_check() {
+    let diagnostics = check::check(&project()).unwrap();
+    let syntax = match diagnostics.iter().find(|d| d.kind == Kind::Syntax) {
+        Some(d) => d.message.clone(),
+        None => panic!("missing syntax error"),
+    };
+    assert_eq!(
+        diagnostics,
+        vec![
+            diagnostic(Kind::Missing, "src/lib.rs", Some(10), "template \"gone.html\" not found"),
+            diagnostic(
+                Kind::Missing,
+                "templates/alt.html",
+                Some(3),
+                "template \"alt-lost.html\" not found",
+            ),
+            diagnostic(
+                Kind::Missing,
+                "templates/alt.html",
+                Some(4),
+                "none of the templates [\"nope-a.html\", \"nope-b.html\"] could be found",
+            ),
+            diagnostic(Kind::Syntax, "templates/broken.html", Some(2), &syntax),
+            diagnostic(
+                Kind::Cycle,
+                "templates/cycle-a.html",
+                None,
+                "cyclic dependency: templates/cycle-a.html -> templates/cycle-b.html -> templates/cycle-a.html",
+            ),
+            diagnostic(Kind::Missing, "templates/page.html", Some(5), "template \"missing.html\" not found"),
+            diagnostic(
+                Kind::Unreferenced,
+                "templates/unused.html",
+                None,
+                "template is not used by any template or by the crate",
+            ),
+        ]
+    );
+    assert!(!diagnostics[6].is_error());
+}
+
+#[test]
+fn test_to_json() {
+    let diagnostics = vec![diagnostic(
+        Kind::Missing,
+        "templates/page.html",
+        Some(5),
+        "template \"missing.html\" not found",
+    )];
+    let json: serde_json::Value = serde_json::from_str(&check::to_json(&diagnostics)).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "diagnostics": [{
+                "kind": "missing",
+                "severity": "error",
+                "path": "templates/page.html",
+                "line": 5,
+                "message": "template \"missing.html\" not found",
+            }],
+        })
+    );
+}
@@ -0,0 +1,15 @@
+#[derive(Template)]
+#[template(path = "page.html")]
+struct Page;
+
+#[derive(Template)]
+#[template(path = "broken.html", escape = "none")]
+struct Broken;
+
+#[derive(Template)]
+#[template(path = "gone.html")]
+struct Gone;
+
+#[derive(Template)]
+#[template(path = "alt.html", syntax = "alt")]
+struct Alt;
@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+use askama_cli::extract::{self, Format, Message};
+use askama_cli::Project;
+
+fn project() -> Project {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/project");
+    Project::open(&root, None).unwrap()
+}
+
+#[test]
+fn test_extract() {
+    let messages = extract::extract(&project()).unwrap();
+    assert_eq!(
+        messages,
+        vec![
+            Message {
+                key: "Hello {name}!".into(),
+                id: "hello-name".into(),
+                trans: true,
+                plural: None,
+                args: vec!["name".into()],
+                references: vec![
+                    (PathBuf::from("templates/index.html"), 1),
+                    (PathBuf::from("templates/shop/cart.html"), 9),
+                ],
+            },
+            Message {
+                key: "search-placeholder".into(),
+                id: "search-placeholder".into(),
+                trans: false,
+                plural: None,
+                args: vec!["site".into()],
+                references: vec![(PathBuf::from("templates/index.html"), 2)],
+            },
+            Message {
+                key: "One item".into(),
+                id: "one-item".into(),
+                trans: true,
+                plural: Some("{count} items".into()),
+                args: vec!["count".into()],
+                references: vec![(PathBuf::from("templates/shop/cart.html"), 4)],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_write_pot() {
+    let messages = extract::extract(&project()).unwrap();
+    let pot = extract::write(&messages, Format::Pot);
+    assert!(pot.starts_with("msgid \"\"\nmsgstr \"\"\n"));
+    assert!(pot.contains(
+        "\n#: templates/shop/cart.html:4\n\
+         msgid \"One item\"\n\
+         msgid_plural \"{count} items\"\n\
+         msgstr[0] \"\"\n\
+         msgstr[1] \"\"\n"
+    ));
+}
+
+#[test]
+fn test_write_fluent() {
+    let messages = extract::extract(&project()).unwrap();
+    let ftl = extract::write(&messages, Format::Fluent);
+    assert!(ftl.contains(
+        "# templates/index.html:2\n\
+         search-placeholder = { $site }\n"
+    ));
+    assert!(ftl.contains(
+        "# templates/shop/cart.html:9\n\
+         hello-name = Hello { $name }!\n"
+    ));
+    assert!(ftl.contains(
+        "# templates/shop/cart.html:4\n\
+         one-item = { $count ->\n        \
+         [one] One item\n       \
+         *[other] { $count } items\n    \
+         }\n"
+    ));
+}
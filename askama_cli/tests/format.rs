@@ -0,0 +1,137 @@
+use std::fs;
+use std::path::Path;
+
+use askama_cli::{format, Project};
+
+fn project() -> Project {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/project");
+    Project::open(&root, None).unwrap()
+}
+
+#[test]
+fn test_format_tags() {
+    let source = "{%if x%}\n  {{-x|upper}}\n{%-else if let Some( y )=z~%}{{y . name}}{%endif +%}";
+    assert_eq!(
+        format::format(&project(), Path::new("test.html"), source).unwrap(),
+        "{% if x %}\n  {{- x|upper }}\n{%- else if let Some(y) = z ~%}{{ y.name }}{% endif +%}"
+    );
+}
+
+#[test]
+fn test_format_exprs() {
+    let source = "{{a+b*(c-1)}}{{f(x,y = 2)[0]?}}{{(a,)}}{{1..=n}}{{x|join( \", \" )}}{{!a&&-b}}";
+    assert_eq!(
+        format::format(&project(), Path::new("test.html"), source).unwrap(),
+        "{{ a + b * (c - 1) }}{{ f(x, y = 2)[0]? }}{{ (a,) }}{{ 1..=n }}{{ x|join(\", \") }}{{ !a && -b }}"
+    );
+}
+
+#[test]
+fn test_format_blocks() {
+    let source = "\
+{%extends \"base.html\"%}
+{%from \"macros.html\" import a,b as c%}
+{%block   content  required%}
+{%for (i,v) in items if v > 0%}{{i}}{%else-%}none{%endfor%}
+{%match x%}  {#first#}
+{%when Some with (y)%}{{y}}{%when _%}{%endmatch%}
+{%macro m(a,b)%}{{a}}{%endmacro m%}
+{%call m(1,2)%}{%let Point{x,y:z}=p%}{%include \"a.html\" ignore missing%}
+{%trans name=user.name,n=3%}Hi {{name}}{%pluralize%}Hey {{name}}{%endtrans%}
+{%-endblock content-%}";
+    assert_eq!(
+        format::format(&project(), Path::new("test.html"), source).unwrap(),
+        "\
+{% extends \"base.html\" %}
+{% from \"macros.html\" import a, b as c %}
+{% block content required %}
+{% for (i, v) in items if v > 0 %}{{ i }}{% else -%}none{% endfor %}
+{% match x %}  {# first #}
+{% when Some(y) %}{{ y }}{% else %}{% endmatch %}
+{% macro m(a, b) %}{{ a }}{% endmacro %}
+{% call m(1, 2) %}{% let Point { x, y: z } = p %}{% include \"a.html\" ignore missing %}
+{% trans name = user.name, n = 3 %}Hi {{ name }}{% pluralize %}Hey {{ name }}{% endtrans %}
+{%- endblock -%}"
+    );
+}
+
+#[test]
+fn test_format_keeps_raw_and_comments() {
+    let source = "{%raw%} {%if  x%} {{ y  }} {%endraw%}{#-   multi\n   line  -#}";
+    assert_eq!(
+        format::format(&project(), Path::new("test.html"), source).unwrap(),
+        "{% raw %} {%if  x%} {{ y  }} {% endraw %}{#- multi\n   line -#}"
+    );
+}
+
+// The indentation of tags is text, which is rendered.
+#[test]
+fn test_format_keeps_indentation() {
+    let source = "<ul>\n{%for x in xs%}\n        <li>{{x}}</li>\n  {%endfor%}\n</ul>";
+    assert_eq!(
+        format::format(&project(), Path::new("test.html"), source).unwrap(),
+        "<ul>\n{% for x in xs %}\n        <li>{{ x }}</li>\n  {% endfor %}\n</ul>"
+    );
+}
+
+// Only the lines holding just a tag whose leading whitespace is trimmed are re-indented.
+#[test]
+fn test_format_reindented() {
+    let source = "\
+<ul>
+{%- for x in xs %}
+{%- if x.ok %}
+        <li>{{ x }}</li>
+      {%~ else %}
+<li>-</li>
+  {%- endif %}
+{%- match x.kind %}
+{%- when Some(k) %}
+ {%- block k %}{{ k }}{% endblock %}
+{%- let y = k %}
+{%- endmatch %}
+    {% endfor %}{%- raw %}
+{%- endraw %}
+</ul>";
+    assert_eq!(
+        format::format_reindented(&project(), Path::new("test.html"), source, "  ").unwrap(),
+        "\
+<ul>
+{%- for x in xs %}
+  {%- if x.ok %}
+        <li>{{ x }}</li>
+  {%~ else %}
+<li>-</li>
+  {%- endif %}
+  {%- match x.kind %}
+    {%- when Some(k) %}
+ {%- block k %}{{ k }}{% endblock %}
+      {%- let y = k %}
+  {%- endmatch %}
+    {% endfor %}{%- raw %}
+{%- endraw %}
+</ul>"
+    );
+}
+
+// Formatting checks that the formatted template has the same syntax tree.
+#[test]
+fn test_format_is_idempotent() {
+    let project = project();
+    for entry in
+        fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("../testing/templates")).unwrap()
+    {
+        let path = entry.unwrap().path();
+        let source = fs::read_to_string(&path).unwrap();
+        let formatted = format::format(&project, &path, &source)
+            .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        assert_eq!(
+            format::format(&project, &path, &formatted).unwrap(),
+            formatted,
+            "{}",
+            path.display()
+        );
+        let reindented = format::format_reindented(&project, &path, &source, "    ").unwrap();
+        format::format(&project, &path, &reindented).unwrap();
+    }
+}
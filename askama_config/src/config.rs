@@ -6,23 +6,51 @@ use std::{env, fs};
 #[cfg(feature = "serde")]
 use serde::Deserialize;
 
+use askama_parser::{Syntax, Whitespace};
+
+use crate::i18n::read_catalog_keys;
 use crate::CompileError;
 
 #[derive(Debug)]
-pub(crate) struct Config<'a> {
-    pub(crate) dirs: Vec<PathBuf>,
-    pub(crate) syntaxes: BTreeMap<String, Syntax<'a>>,
-    pub(crate) default_syntax: &'a str,
-    pub(crate) escapers: Vec<(HashSet<String>, String)>,
-    pub(crate) whitespace: WhitespaceHandling,
+pub struct Config<'a> {
+    pub dirs: Vec<PathBuf>,
+    pub syntaxes: BTreeMap<String, Syntax<'a>>,
+    pub default_syntax: &'a str,
+    pub escapers: Vec<(HashSet<String>, String)>,
+    pub whitespace: WhitespaceHandling,
+    /// Templates imported into every template, with the scope of the import. Without a scope,
+    /// the macros of the template can be called as if they were defined in every template.
+    pub prelude: Vec<(PathBuf, Option<&'a str>)>,
+    /// Translation catalogs, which define the message keys templates may use.
+    pub catalogs: Vec<PathBuf>,
+    /// The message keys defined by the catalogs, if any catalogs are configured.
+    pub messages: Option<HashSet<String>>,
+    /// The allowlist of the `sanitize` filter, if it replaces the default one.
+    pub sanitize: Option<Sanitize<'a>>,
+    /// The syntect theme used to highlight code blocks in the `markdown` filter, if any.
+    pub highlight_theme: Option<&'a str>,
 }
 
 impl<'a> Config<'a> {
-    pub(crate) fn new(
+    pub fn new(
         s: &'a str,
         template_whitespace: Option<&String>,
     ) -> std::result::Result<Config<'a>, CompileError> {
         let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let mut config = Config::with_root(&root, s, template_whitespace)?;
+        config.read_catalogs()?;
+        Ok(config)
+    }
+
+    /// Like `new()`, but for the crate at `root` instead of the crate being compiled.
+    /// The translation catalogs aren't read, since they may not exist yet, e.g. before
+    /// the messages of the templates are first extracted. Use `read_catalogs()` to
+    /// read them.
+    pub fn with_root(
+        root: &Path,
+        s: &'a str,
+        template_whitespace: Option<&String>,
+    ) -> std::result::Result<Config<'a>, CompileError> {
         let default_dirs = vec![root.join("templates")];
 
         let mut syntaxes = BTreeMap::new();
@@ -34,22 +62,25 @@ impl<'a> Config<'a> {
             RawConfig::from_toml_str(s)?
         };
 
-        let (dirs, default_syntax, mut whitespace) = match raw.general {
+        let (dirs, default_syntax, mut whitespace, raw_prelude) = match raw.general {
             Some(General {
                 dirs,
                 default_syntax,
                 whitespace,
+                prelude,
             }) => (
                 dirs.map_or(default_dirs, |v| {
                     v.into_iter().map(|dir| root.join(dir)).collect()
                 }),
                 default_syntax.unwrap_or(DEFAULT_SYNTAX_NAME),
                 whitespace,
+                prelude.unwrap_or_default(),
             ),
             None => (
                 default_dirs,
                 DEFAULT_SYNTAX_NAME,
                 WhitespaceHandling::default(),
+                Vec::new(),
             ),
         };
         if let Some(template_whitespace) = template_whitespace {
@@ -95,16 +126,83 @@ impl<'a> Config<'a> {
             escapers.push((str_set(extensions), (*path).to_string()));
         }
 
-        Ok(Config {
+        let mut config = Config {
             dirs,
             syntaxes,
             default_syntax,
             escapers,
             whitespace,
-        })
+            prelude: Vec::new(),
+            catalogs: Vec::new(),
+            messages: None,
+            sanitize: None,
+            highlight_theme: None,
+        };
+        for entry in raw_prelude {
+            let (path, scope) = match entry.rsplit_once(" as ") {
+                Some((path, scope)) => (path.trim(), Some(scope.trim())),
+                None => (entry.trim(), None),
+            };
+            if let Some(scope) = scope {
+                if scope.is_empty() || scope.contains(|c: char| !c.is_alphanumeric() && c != '_') {
+                    return Err(format!("invalid scope in prelude entry {entry:?}").into());
+                }
+            }
+            let path = config.find_template(path, None)?;
+            config.prelude.push((path, scope));
+        }
+
+        if let Some(i18n) = raw.i18n {
+            config.catalogs = i18n
+                .catalogs
+                .into_iter()
+                .map(|catalog| root.join(catalog))
+                .collect();
+            config.messages = Some(HashSet::new());
+        }
+
+        if let Some(sanitize) = raw.sanitize {
+            sanitize.validate()?;
+            config.sanitize = Some(sanitize);
+        }
+
+        if let Some(markdown) = raw.markdown {
+            config.highlight_theme = match (markdown.highlighter, markdown.theme) {
+                (None | Some("none"), None) => None,
+                (Some("none"), Some(_)) => {
+                    return Err("a markdown `theme` can't be used without a `highlighter`".into())
+                }
+                (None | Some("syntect"), theme) => {
+                    let theme = theme.unwrap_or(DEFAULT_HIGHLIGHT_THEME);
+                    check_highlight_theme(theme)?;
+                    Some(theme)
+                }
+                (Some(highlighter), _) => {
+                    return Err(format!(
+                        "invalid value for `highlighter`: \"{highlighter}\", \
+                         expected \"syntect\" or \"none\""
+                    )
+                    .into())
+                }
+            };
+        }
+        if let (Some(sanitize), Some(_)) = (&config.sanitize, config.highlight_theme) {
+            sanitize.check_highlighting()?;
+        }
+        Ok(config)
     }
 
-    pub(crate) fn find_template(
+    /// Reads the message keys defined by the translation catalogs into `messages`.
+    pub fn read_catalogs(&mut self) -> std::result::Result<(), CompileError> {
+        if let Some(messages) = &mut self.messages {
+            for path in &self.catalogs {
+                read_catalog_keys(path, messages)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn find_template(
         &self,
         path: &str,
         start_at: Option<&Path>,
@@ -131,29 +229,6 @@ impl<'a> Config<'a> {
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct Syntax<'a> {
-    pub(crate) block_start: &'a str,
-    pub(crate) block_end: &'a str,
-    pub(crate) expr_start: &'a str,
-    pub(crate) expr_end: &'a str,
-    pub(crate) comment_start: &'a str,
-    pub(crate) comment_end: &'a str,
-}
-
-impl Default for Syntax<'static> {
-    fn default() -> Self {
-        Self {
-            block_start: "{%",
-            block_end: "%}",
-            expr_start: "{{",
-            expr_end: "}}",
-            comment_start: "{#",
-            comment_end: "#}",
-        }
-    }
-}
-
 impl<'a> TryFrom<RawSyntax<'a>> for Syntax<'a> {
     type Error = CompileError;
 
@@ -168,36 +243,7 @@ impl<'a> TryFrom<RawSyntax<'a>> for Syntax<'a> {
             comment_end: raw.comment_end.unwrap_or(default.comment_end),
         };
 
-        for s in [
-            syntax.block_start,
-            syntax.block_end,
-            syntax.expr_start,
-            syntax.expr_end,
-            syntax.comment_start,
-            syntax.comment_end,
-        ] {
-            if s.len() < 2 {
-                return Err(
-                    format!("delimiters must be at least two characters long: {s:?}").into(),
-                );
-            } else if s.chars().any(|c| c.is_whitespace()) {
-                return Err(format!("delimiters may not contain white spaces: {s:?}").into());
-            }
-        }
-
-        for (s1, s2) in [
-            (syntax.block_start, syntax.expr_start),
-            (syntax.block_start, syntax.comment_start),
-            (syntax.expr_start, syntax.comment_start),
-        ] {
-            if s1.starts_with(s2) || s2.starts_with(s1) {
-                return Err(format!(
-                    "a delimiter may not be the prefix of another delimiter: {s1:?} vs {s2:?}",
-                )
-                .into());
-            }
-        }
-
+        syntax.validate()?;
         Ok(syntax)
     }
 }
@@ -209,6 +255,9 @@ struct RawConfig<'a> {
     general: Option<General<'a>>,
     syntax: Option<Vec<RawSyntax<'a>>>,
     escaper: Option<Vec<RawEscaper<'a>>>,
+    i18n: Option<RawI18n<'a>>,
+    sanitize: Option<Sanitize<'a>>,
+    markdown: Option<RawMarkdown<'a>>,
 }
 
 impl RawConfig<'_> {
@@ -227,7 +276,7 @@ impl RawConfig<'_> {
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "serde", derive(Deserialize))]
 #[cfg_attr(feature = "serde", serde(field_identifier, rename_all = "lowercase"))]
-pub(crate) enum WhitespaceHandling {
+pub enum WhitespaceHandling {
     /// The default behaviour. It will leave the whitespace characters "as is".
     Preserve,
     /// It'll remove all the whitespace characters before and after the jinja block.
@@ -244,6 +293,16 @@ impl Default for WhitespaceHandling {
     }
 }
 
+impl From<WhitespaceHandling> for Whitespace {
+    fn from(ws: WhitespaceHandling) -> Self {
+        match ws {
+            WhitespaceHandling::Suppress => Whitespace::Suppress,
+            WhitespaceHandling::Preserve => Whitespace::Preserve,
+            WhitespaceHandling::Minimize => Whitespace::Minimize,
+        }
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(Deserialize))]
 struct General<'a> {
     #[cfg_attr(feature = "serde", serde(borrow))]
@@ -251,6 +310,7 @@ struct General<'a> {
     default_syntax: Option<&'a str>,
     #[cfg_attr(feature = "serde", serde(default))]
     whitespace: WhitespaceHandling,
+    prelude: Option<Vec<&'a str>>,
 }
 
 #[cfg_attr(feature = "serde", derive(Deserialize))]
@@ -270,10 +330,125 @@ struct RawEscaper<'a> {
     extensions: Vec<&'a str>,
 }
 
-pub(crate) fn read_config_file(
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+struct RawI18n<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    catalogs: Vec<&'a str>,
+}
+
+/// The `[markdown]` section, which configures the syntax highlighting of the `markdown` filter.
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+struct RawMarkdown<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    highlighter: Option<&'a str>,
+    theme: Option<&'a str>,
+}
+
+/// The `[sanitize]` section, which configures the allowlist of the `sanitize` filter.
+/// The lists that aren't given keep the defaults of ammonia.
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[derive(Debug, Default)]
+pub struct Sanitize<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub tags: Option<Vec<&'a str>>,
+    pub tag_attributes: Option<BTreeMap<&'a str, Vec<&'a str>>>,
+    pub generic_attributes: Option<Vec<&'a str>>,
+    pub url_schemes: Option<Vec<&'a str>>,
+    /// The `rel` attribute added to links, or no attribute if it's empty.
+    pub link_rel: Option<&'a str>,
+    /// Whether the output of the `markdown` filter gets sanitized as well.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub markdown: bool,
+}
+
+impl Sanitize<'_> {
+    // Rejects the settings that would make ammonia panic at runtime.
+    fn validate(&self) -> std::result::Result<(), CompileError> {
+        let tags = self.tags.iter().flatten();
+        let attribute_tags = self.tag_attributes.iter().flat_map(|tags| tags.keys());
+        if let Some(tag) = tags
+            .chain(attribute_tags)
+            .find(|tag| matches!(**tag, "script" | "style"))
+        {
+            return Err(format!("the content of `{tag}` tags can't be sanitized").into());
+        }
+
+        let rel_allowed = self
+            .generic_attributes
+            .iter()
+            .flatten()
+            .chain(
+                self.tag_attributes
+                    .iter()
+                    .flat_map(|tags| tags.get("a"))
+                    .flatten(),
+            )
+            .any(|attr| *attr == "rel");
+        if rel_allowed && self.link_rel != Some("") {
+            return Err(
+                "the `rel` attribute of links can only be allowed if `link_rel` is empty".into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Rejects sanitizing the output of the `markdown` filter if the allowlist removes the
+    /// inline styles of syntect's highlighting.
+    pub fn check_highlighting(&self) -> std::result::Result<(), CompileError> {
+        if !self.markdown {
+            return Ok(());
+        }
+        // ammonia keeps `pre` and `span` tags by default.
+        let tag_kept = |tag| self.tags.as_ref().map_or(true, |tags| tags.contains(&tag));
+        let style_kept = |tag| {
+            self.generic_attributes
+                .iter()
+                .flatten()
+                .any(|attr| *attr == "style")
+                || self
+                    .tag_attributes
+                    .iter()
+                    .flat_map(|tags| tags.get(tag))
+                    .flatten()
+                    .any(|attr| *attr == "style")
+        };
+        if ["pre", "span"]
+            .iter()
+            .all(|tag| tag_kept(*tag) && style_kept(tag))
+        {
+            return Ok(());
+        }
+        Err(
+            "sanitizing the output of the `markdown` filter would remove the inline styles of \
+             the syntax highlighting; the `[sanitize]` allowlist has to keep the `style` \
+             attribute of `pre` and `span` tags"
+                .into(),
+        )
+    }
+}
+
+/// Checks that `theme` is one of the themes bundled with syntect.
+pub fn check_highlight_theme(theme: &str) -> std::result::Result<(), CompileError> {
+    if HIGHLIGHT_THEMES.contains(&theme) {
+        return Ok(());
+    }
+    Err(format!(
+        "unknown syntax highlighting theme \"{theme}\", expected one of: {}",
+        HIGHLIGHT_THEMES.join(", ")
+    )
+    .into())
+}
+
+pub fn read_config_file(config_path: Option<&str>) -> std::result::Result<String, CompileError> {
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    read_config_file_in(&root, config_path)
+}
+
+/// Like `read_config_file()`, but for the crate at `root` instead of the crate being compiled.
+pub fn read_config_file_in(
+    root: &Path,
     config_path: Option<&str>,
 ) -> std::result::Result<String, CompileError> {
-    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
     let filename = match config_path {
         Some(config_path) => root.join(config_path),
         None => root.join(CONFIG_FILE_NAME),
@@ -297,7 +472,7 @@ where
 }
 
 #[allow(clippy::match_wild_err_arm)]
-pub(crate) fn get_template_source(tpl_path: &Path) -> std::result::Result<String, CompileError> {
+pub fn get_template_source(tpl_path: &Path) -> std::result::Result<String, CompileError> {
     match fs::read_to_string(tpl_path) {
         Err(_) => Err(format!(
             "unable to open template file '{}'",
@@ -320,6 +495,17 @@ static DEFAULT_ESCAPERS: &[(&[&str], &str)] = &[
     (&["md", "none", "txt", "yml", ""], "::askama::Text"),
     (&["j2", "jinja", "jinja2"], "::askama::Html"),
 ];
+static DEFAULT_HIGHLIGHT_THEME: &str = "base16-ocean.dark";
+// The themes of `syntect::highlighting::ThemeSet::load_defaults()`.
+static HIGHLIGHT_THEMES: &[&str] = &[
+    "InspiredGitHub",
+    "Solarized (dark)",
+    "Solarized (light)",
+    "base16-eighties.dark",
+    "base16-mocha.dark",
+    "base16-ocean.dark",
+    "base16-ocean.light",
+];
 
 #[cfg(test)]
 mod tests {
@@ -648,6 +834,30 @@ mod tests {
         assert_eq!(config.whitespace, WhitespaceHandling::Minimize);
     }
 
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_prelude() {
+        let config = Config::new(
+            r#"
+            [general]
+            prelude = ["a.html as a", "sub/b.html"]
+            "#,
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.prelude.len(), 2);
+        assert_eq_rooted(&config.prelude[0].0, "a.html");
+        assert_eq!(config.prelude[0].1, Some("a"));
+        assert_eq_rooted(&config.prelude[1].0, "sub/b.html");
+        assert_eq!(config.prelude[1].1, None);
+
+        let config = Config::new("[general]\nprelude = [\"a.html as a-b\"]", None);
+        assert_eq!(
+            config.unwrap_err().msg,
+            "invalid scope in prelude entry \"a.html as a-b\""
+        );
+    }
+
     #[test]
     fn test_config_whitespace_error() {
         let config = Config::new(r#""#, Some(&"trim".to_owned()));
@@ -657,4 +867,106 @@ mod tests {
             panic!("Config::new should have return an error");
         }
     }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_sanitize() {
+        let config = Config::new(
+            r#"
+            [sanitize]
+            tags = ["a", "b", "p"]
+            tag_attributes = { a = ["href", "rel"] }
+            link_rel = ""
+            markdown = true
+            "#,
+            None,
+        )
+        .unwrap();
+        let sanitize = config.sanitize.unwrap();
+        assert_eq!(sanitize.tags, Some(vec!["a", "b", "p"]));
+        assert_eq!(sanitize.tag_attributes.unwrap()["a"], vec!["href", "rel"]);
+        assert_eq!(sanitize.generic_attributes, None);
+        assert_eq!(sanitize.link_rel, Some(""));
+        assert!(sanitize.markdown);
+
+        let config = Config::new("[sanitize]\ntags = [\"b\", \"script\"]", None);
+        assert_eq!(
+            config.unwrap_err().msg,
+            "the content of `script` tags can't be sanitized"
+        );
+        let config = Config::new("[sanitize]\ngeneric_attributes = [\"rel\"]", None);
+        assert_eq!(
+            config.unwrap_err().msg,
+            "the `rel` attribute of links can only be allowed if `link_rel` is empty"
+        );
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_markdown_highlighting() {
+        let config = Config::new("[markdown]\nhighlighter = \"syntect\"", None).unwrap();
+        assert_eq!(config.highlight_theme, Some("base16-ocean.dark"));
+        let config = Config::new("[markdown]\ntheme = \"InspiredGitHub\"", None).unwrap();
+        assert_eq!(config.highlight_theme, Some("InspiredGitHub"));
+        let config = Config::new("[markdown]\nhighlighter = \"none\"", None).unwrap();
+        assert_eq!(config.highlight_theme, None);
+
+        let config = Config::new("[markdown]\nhighlighter = \"pygments\"", None);
+        assert_eq!(
+            config.unwrap_err().msg,
+            "invalid value for `highlighter`: \"pygments\", expected \"syntect\" or \"none\""
+        );
+        let config = Config::new("[markdown]\ntheme = \"monokai\"", None);
+        assert!(config
+            .unwrap_err()
+            .msg
+            .starts_with("unknown syntax highlighting theme \"monokai\""));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_sanitized_markdown_highlighting() {
+        let config = Config::new(
+            "[sanitize]\nmarkdown = true\n[markdown]\nhighlighter = \"syntect\"",
+            None,
+        );
+        assert!(config
+            .unwrap_err()
+            .msg
+            .starts_with("sanitizing the output of the `markdown` filter would remove"));
+        let config = Config::new(
+            "[sanitize]\nmarkdown = true\ntag_attributes = { pre = [\"style\"] }\n\
+             [markdown]\nhighlighter = \"syntect\"",
+            None,
+        );
+        assert!(config.is_err());
+        let config = Config::new(
+            "[sanitize]\nmarkdown = true\ntags = [\"p\", \"pre\"]\n\
+             generic_attributes = [\"style\"]\n[markdown]\nhighlighter = \"syntect\"",
+            None,
+        );
+        assert!(config.is_err());
+
+        let config = Config::new(
+            "[sanitize]\nmarkdown = true\ngeneric_attributes = [\"style\"]\n\
+             [markdown]\nhighlighter = \"syntect\"",
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.highlight_theme, Some("base16-ocean.dark"));
+        let config = Config::new(
+            "[sanitize]\nmarkdown = true\n\
+             tag_attributes = { pre = [\"style\"], span = [\"style\"] }\n\
+             [markdown]\ntheme = \"InspiredGitHub\"",
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.highlight_theme, Some("InspiredGitHub"));
+        let config = Config::new(
+            "[sanitize]\nmarkdown = true\n[markdown]\nhighlighter = \"none\"",
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.highlight_theme, None);
+    }
 }
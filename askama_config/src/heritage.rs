@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use askama_parser::{BlockDef, Loop, Macro, Node, WithSpan};
+
+use crate::config::Config;
+use crate::CompileError;
+
+pub struct Heritage<'a> {
+    pub root: &'a Context<'a>,
+    /// The templates of the inheritance chain, starting with the most derived one.
+    pub chain: Vec<&'a Context<'a>>,
+    pub blocks: BlockAncestry<'a>,
+}
+
+impl Heritage<'_> {
+    pub fn new<'n>(
+        mut ctx: &'n Context<'n>,
+        contexts: &'n HashMap<&'n Path, Context<'n>>,
+    ) -> Result<Heritage<'n>, CompileError> {
+        let mut blocks: BlockAncestry<'n> = ctx
+            .blocks
+            .iter()
+            .map(|(name, def)| (*name, vec![(ctx, *def)]))
+            .collect();
+        let mut chain = vec![ctx];
+
+        while let Some(ref path) = ctx.extends {
+            ctx = &contexts[path.as_path()];
+            chain.push(ctx);
+            for (name, def) in &ctx.blocks {
+                blocks.entry(name).or_insert_with(Vec::new).push((ctx, def));
+            }
+        }
+
+        // The most derived definition of a block is the one that gets rendered.
+        for (name, ancestry) in &blocks {
+            if ancestry[0].1.required {
+                return Err(format!("required block '{name}' is not overridden").into());
+            }
+        }
+
+        Ok(Heritage {
+            root: ctx,
+            chain,
+            blocks,
+        })
+    }
+}
+
+type BlockAncestry<'a> = HashMap<&'a str, Vec<(&'a Context<'a>, &'a BlockDef<'a>)>>;
+
+pub struct Context<'a> {
+    pub nodes: &'a [WithSpan<Node<'a>>],
+    pub extends: Option<PathBuf>,
+    pub blocks: HashMap<&'a str, &'a BlockDef<'a>>,
+    pub macros: HashMap<&'a str, &'a Macro<'a>>,
+    pub imports: HashMap<&'a str, PathBuf>,
+    /// Macros imported with `from`, by local name: the template path and the macro name.
+    pub from_imports: HashMap<&'a str, (PathBuf, &'a str)>,
+}
+
+impl Context<'_> {
+    pub fn new<'n>(
+        config: &Config<'n>,
+        path: &Path,
+        nodes: &'n [WithSpan<Node<'n>>],
+    ) -> Result<Context<'n>, CompileError> {
+        let mut extends = None;
+        let mut blocks = Vec::new();
+        let mut macros = HashMap::new();
+        let mut imports: HashMap<_, _> = config
+            .prelude
+            .iter()
+            .filter(|(prelude, _)| prelude != path)
+            .filter_map(|(prelude, scope)| Some((*scope.as_ref()?, prelude.clone())))
+            .collect();
+        let mut from_imports = HashMap::new();
+        let mut nested = vec![nodes];
+        let mut top = true;
+
+        while let Some(nodes) = nested.pop() {
+            for n in nodes {
+                let at = |e: CompileError| e.with_span(n.span());
+                match &**n {
+                    Node::Extends(extends_path) if top => match extends {
+                        Some(_) => return Err(at("multiple extend blocks found".into())),
+                        None => {
+                            let found = config.find_template(extends_path, Some(path));
+                            extends = Some(found.map_err(at)?);
+                        }
+                    },
+                    Node::Macro(name, m) if top => {
+                        macros.insert(*name, m);
+                    }
+                    Node::Import(_, import_path, scope) if top => {
+                        let path = config.find_template(import_path, Some(path)).map_err(at)?;
+                        imports.insert(*scope, path);
+                    }
+                    Node::FromImport(_, import_path, names) if top => {
+                        let path = config.find_template(import_path, Some(path)).map_err(at)?;
+                        for (name, alias) in names {
+                            from_imports.insert(*alias, (path.clone(), *name));
+                        }
+                    }
+                    Node::Extends(_)
+                    | Node::Macro(_, _)
+                    | Node::Import(_, _, _)
+                    | Node::FromImport(_, _, _)
+                        if !top =>
+                    {
+                        return Err(at(
+                            "extends, macro or import blocks not allowed below top level".into(),
+                        ));
+                    }
+                    Node::BlockDef(def) => {
+                        blocks.push(def);
+                        nested.push(&def.nodes);
+                    }
+                    Node::Cond(branches, _) => {
+                        for (_, _, nodes) in branches {
+                            nested.push(nodes);
+                        }
+                    }
+                    Node::Loop(Loop {
+                        body, else_block, ..
+                    }) => {
+                        nested.push(body);
+                        nested.push(else_block);
+                    }
+                    Node::Match(_, _, _, arms, _) => {
+                        for (_, _, arm) in arms {
+                            nested.push(arm);
+                        }
+                    }
+                    // This includes `embed`: the blocks inside it override those of the
+                    // embedded template, so they are not part of this template's blocks.
+                    _ => {}
+                }
+            }
+            top = false;
+        }
+
+        let blocks: HashMap<_, _> = blocks.iter().map(|def| (def.name, *def)).collect();
+
+        Ok(Context {
+            nodes,
+            extends,
+            blocks,
+            macros,
+            imports,
+            from_imports,
+        })
+    }
+}
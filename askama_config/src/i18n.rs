@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::CompileError;
+
+/// Reads the message keys defined in the Fluent (`.ftl`) or gettext (`.po`) catalog at `path`.
+pub(crate) fn read_catalog_keys(
+    path: &Path,
+    keys: &mut HashSet<String>,
+) -> Result<(), CompileError> {
+    let source = fs::read_to_string(path)
+        .map_err(|_| CompileError::from(format!("unable to read catalog {:?}", path.display())))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("ftl") => keys.extend(fluent_keys(&source)),
+        Some("po" | "pot") => keys.extend(po_keys(&source)?),
+        _ => {
+            return Err(format!(
+                "unsupported catalog {:?}, expected a `.ftl` or `.po` file",
+                path.display()
+            )
+            .into())
+        }
+    }
+    Ok(())
+}
+
+// Message definitions start at the beginning of a line, with an identifier followed by `=`.
+fn fluent_keys(source: &str) -> impl Iterator<Item = String> + '_ {
+    source.lines().filter_map(|line| {
+        if !line.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let end = line
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(line.len());
+        let (id, rest) = line.split_at(end);
+        rest.trim_start().starts_with('=').then(|| id.to_string())
+    })
+}
+
+fn po_keys(source: &str) -> Result<Vec<String>, CompileError> {
+    let mut keys = Vec::new();
+    let mut current: Option<String> = None;
+    for line in source.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("msgid ") {
+            keys.extend(current.take());
+            current = Some(po_string(rest)?);
+        } else if line.starts_with('"') {
+            if let Some(current) = &mut current {
+                current.push_str(&po_string(line)?);
+            }
+        } else {
+            keys.extend(current.take());
+        }
+    }
+    keys.extend(current);
+    // The empty key is the header of the catalog.
+    keys.retain(|key| !key.is_empty());
+    Ok(keys)
+}
+
+fn po_string(s: &str) -> Result<String, CompileError> {
+    let inner = s
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| CompileError::from(format!("invalid string in catalog: {s}")))?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(c) => out.push(c),
+            None => return Err(format!("invalid string in catalog: {s}").into()),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fluent_keys() {
+        let source = "# comment\nhello = Hello\n-brand = Askama\nitems =\n    { $count ->\n        [one] One item\n       *[other] { $count } items\n    }\nhello-user = Hello, { $name }!\n";
+        let keys: Vec<_> = fluent_keys(source).collect();
+        assert_eq!(keys, vec!["hello", "items", "hello-user"]);
+    }
+
+    #[test]
+    fn test_po_keys() {
+        let source = r#"msgid ""
+msgstr ""
+"Language: de\n"
+
+#: templates/index.html
+msgid "Hello {name}"
+msgstr "Hallo {name}"
+
+msgid "One item"
+msgid_plural "{count} items"
+msgstr[0] "Ein Element"
+msgstr[1] "{count} Elemente"
+
+msgid ""
+"Multi\t"
+"line \"quoted\""
+msgstr ""
+"#;
+        assert_eq!(
+            po_keys(source).unwrap(),
+            vec!["Hello {name}", "One item", "Multi\tline \"quoted\""]
+        );
+    }
+}
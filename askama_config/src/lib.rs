@@ -0,0 +1,84 @@
+//! Configuration handling of the [Askama](https://github.com/djc/askama)
+//! templating engine
+//!
+//! [`config::Config`] reads the `askama.toml` of a crate and finds its
+//! templates, and [`heritage`] resolves the blocks of templates that extend
+//! other templates. The code generator of `askama_derive` and the tools of
+//! `askama_cli` share this crate to read templates the same way.
+
+#![deny(elided_lifetimes_in_paths)]
+#![deny(unreachable_pub)]
+
+use std::borrow::Cow;
+use std::fmt;
+
+use askama_parser::Span;
+
+pub mod config;
+pub mod heritage;
+mod i18n;
+
+/// An error in the configuration or in the templates it refers to
+#[derive(Debug, Clone)]
+pub struct CompileError {
+    msg: Cow<'static, str>,
+    span: Option<Span>,
+}
+
+impl CompileError {
+    /// Returns the error with the location of its cause in the source of a template
+    pub fn with_span(self, span: Span) -> Self {
+        Self {
+            span: Some(span),
+            ..self
+        }
+    }
+
+    /// Returns the location of the cause of the error in the source of the
+    /// template, if it is known
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+impl fmt::Display for CompileError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(&self.msg)
+    }
+}
+
+impl From<&'static str> for CompileError {
+    #[inline]
+    fn from(s: &'static str) -> Self {
+        Self {
+            msg: s.into(),
+            span: None,
+        }
+    }
+}
+
+impl From<String> for CompileError {
+    #[inline]
+    fn from(s: String) -> Self {
+        Self {
+            msg: s.into(),
+            span: None,
+        }
+    }
+}
+
+impl From<askama_parser::ParseError> for CompileError {
+    #[inline]
+    fn from(e: askama_parser::ParseError) -> Self {
+        Self {
+            msg: e.to_string().into(),
+            span: e.offset().map(|offset| Span {
+                start: offset,
+                end: offset,
+            }),
+        }
+    }
+}
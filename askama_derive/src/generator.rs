@@ -1,22 +1,28 @@
-use crate::config::{get_template_source, read_config_file, Config, WhitespaceHandling};
-use crate::heritage::{Context, Heritage};
 use crate::input::{Print, Source, TemplateInput};
-use crate::parser::{Cond, CondTest, Expr, Loop, Node, Target, When, Whitespace, Ws};
+use crate::suggest;
 use crate::CompileError;
+#[cfg(feature = "markdown")]
+use askama_config::config::check_highlight_theme;
+use askama_config::config::{get_template_source, read_config_file, Config, WhitespaceHandling};
+use askama_config::heritage::{Context, Heritage};
+use askama_parser::{
+    json, Cond, CondTest, Expr, Loop, Node, Target, Trans, When, Whitespace, WithSpan, Ws,
+};
 
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::punctuated::Punctuated;
 
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
-use std::{cmp, hash, mem, str};
+use std::{cmp, hash, mem, ptr, str};
 
 /// The actual implementation for askama_derive::Template
 pub(crate) fn derive_template(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
     match build_template(&ast) {
-        Ok(source) => source.parse().unwrap(),
+        Ok(tokens) => tokens.into(),
         Err(e) => e.into_compile_error(),
     }
 }
@@ -28,7 +34,7 @@ pub(crate) fn derive_template(input: TokenStream) -> TokenStream {
 /// parsed, and the parse tree is fed to the code generator. Will print
 /// the parse tree and/or generated source according to the `print` key's
 /// value as passed to the `template()` attribute.
-fn build_template(ast: &syn::DeriveInput) -> Result<String, CompileError> {
+fn build_template(ast: &syn::DeriveInput) -> Result<proc_macro2::TokenStream, CompileError> {
     let template_args = TemplateArgs::new(ast)?;
     let config_toml = read_config_file(template_args.config_path.as_deref())?;
     let config = Config::new(&config_toml, template_args.whitespace.as_ref())?;
@@ -51,7 +57,7 @@ fn build_template(ast: &syn::DeriveInput) -> Result<String, CompileError> {
 
     let ctx = &contexts[input.path.as_path()];
     let heritage = if !ctx.blocks.is_empty() || ctx.extends.is_some() {
-        Some(Heritage::new(ctx, &contexts))
+        Some(Heritage::new(ctx, &contexts)?)
     } else {
         None
     };
@@ -59,19 +65,78 @@ fn build_template(ast: &syn::DeriveInput) -> Result<String, CompileError> {
     if input.print == Print::Ast || input.print == Print::All {
         eprintln!("{:?}", templates[input.path.as_path()].nodes());
     }
+    if input.print == Print::Json {
+        let parsed = &templates[input.path.as_path()];
+        eprintln!(
+            "{{\"version\":{},\"nodes\":{}}}",
+            json::VERSION,
+            json::nodes_to_json(parsed.source(), parsed.nodes()),
+        );
+    }
 
-    let code = Generator::new(
+    let mut generator = Generator::new(
         &input,
         &contexts,
         heritage.as_ref(),
         MapChain::new(),
         config.whitespace,
-    )
-    .build(&contexts[input.path.as_path()])?;
+    );
+    let code = generator.build(&contexts[input.path.as_path()])?;
     if input.print == Print::Code || input.print == Print::All {
         eprintln!("{code}");
     }
-    Ok(code)
+
+    let mut tokens: proc_macro2::TokenStream = code.parse().unwrap();
+    if input.warn_unused_fields {
+        tokens.extend(unused_field_warnings(&input, &generator.used_fields));
+    }
+    Ok(tokens)
+}
+
+// Warns about the fields of the template struct that no template reads, by using a
+// deprecated constant for each of them. Rust has no other way for a derive macro to warn.
+fn unused_field_warnings(input: &TemplateInput<'_>, used: &UsedFields) -> proc_macro2::TokenStream {
+    let names = match used {
+        UsedFields::Names(names) => names,
+        UsedFields::All => return proc_macro2::TokenStream::new(),
+    };
+    input
+        .fields()
+        .filter(|field| !names.contains(&field.to_string()))
+        .map(|field| {
+            let note = format!("field `{field}` is not used by the template");
+            quote_spanned! {field.span()=>
+                const _: () = {
+                    #[deprecated(note = #note)]
+                    #[allow(non_upper_case_globals)]
+                    const #field: () = ();
+                    #field
+                };
+            }
+        })
+        .collect()
+}
+
+/// The fields of the template struct that the templates read
+enum UsedFields {
+    Names(HashSet<String>),
+    /// `self` is used as a value, so any field may be read
+    All,
+}
+
+impl UsedFields {
+    fn insert(&mut self, name: &str) {
+        if let UsedFields::Names(names) = self {
+            names.insert(name.to_string());
+        }
+    }
+
+    fn extend(&mut self, other: UsedFields) {
+        match other {
+            UsedFields::Names(names) => names.iter().for_each(|name| self.insert(name)),
+            UsedFields::All => *self = UsedFields::All,
+        }
+    }
 }
 
 #[derive(Default)]
@@ -83,6 +148,9 @@ pub(crate) struct TemplateArgs {
     pub(crate) syntax: Option<String>,
     pub(crate) config_path: Option<String>,
     pub(crate) whitespace: Option<String>,
+    pub(crate) context: Option<String>,
+    pub(crate) default_context: bool,
+    pub(crate) warn_unused_fields: bool,
 }
 
 impl TemplateArgs {
@@ -112,6 +180,14 @@ impl TemplateArgs {
         for item in template_args {
             let pair = match item {
                 syn::Meta::NameValue(pair) => pair,
+                syn::Meta::Path(path) if path.is_ident("warn_unused_fields") => {
+                    args.warn_unused_fields = true;
+                    continue;
+                }
+                syn::Meta::Path(path) if path.is_ident("default_context") => {
+                    args.default_context = true;
+                    continue;
+                }
                 _ => {
                     return Err(format!(
                         "unsupported attribute argument {:?}",
@@ -191,6 +267,12 @@ impl TemplateArgs {
                 } else {
                     return Err("whitespace value must be string literal".into());
                 }
+            } else if ident == "context" {
+                if let syn::Lit::Str(s) = value.lit {
+                    args.context = Some(s.value())
+                } else {
+                    return Err("context value must be string literal".into());
+                }
             } else {
                 return Err(format!("unsupported attribute key {ident:?} found").into());
             }
@@ -207,10 +289,15 @@ fn find_used_templates(
 ) -> Result<(), CompileError> {
     let mut dependency_graph = Vec::new();
     let mut check = vec![(input.path.clone(), source)];
+    for (prelude, _) in &input.config.prelude {
+        if *prelude != input.path {
+            check.push((prelude.clone(), get_template_source(prelude)?));
+        }
+    }
     while let Some((path, source)) = check.pop() {
         let parsed = Parsed::new(source, input.syntax)?;
         for n in parsed.nodes() {
-            match n {
+            match &**n {
                 Node::Extends(extends) => {
                     let extends = input.config.find_template(extends, Some(&path))?;
                     let dependency_path = (path.clone(), extends.clone());
@@ -228,7 +315,7 @@ fn find_used_templates(
                     let source = get_template_source(&extends)?;
                     check.push((extends, source));
                 }
-                Node::Import(_, import, _) => {
+                Node::Import(_, import, _) | Node::FromImport(_, import, _) => {
                     let import = input.config.find_template(import, Some(&path))?;
                     let source = get_template_source(&import)?;
                     check.push((import, source));
@@ -236,22 +323,62 @@ fn find_used_templates(
                 _ => {}
             }
         }
+
+        let mut embeds = Vec::new();
+        collect_embeds(parsed.nodes(), &mut embeds);
+        for embed in embeds {
+            let embed = input.config.find_template(embed, Some(&path))?;
+            if embed != path && !map.contains_key(&embed) {
+                let source = get_template_source(&embed)?;
+                check.push((embed, source));
+            }
+        }
+
         map.insert(path, parsed);
     }
     Ok(())
 }
 
+// Unlike `extends` and `import`, `embed` can be used anywhere in a template.
+fn collect_embeds<'a>(nodes: &'a [WithSpan<Node<'_>>], embeds: &mut Vec<&'a str>) {
+    for n in nodes {
+        match &**n {
+            Node::Embed(_, path, nodes, _) => {
+                embeds.push(path);
+                collect_embeds(nodes, embeds);
+            }
+            Node::BlockDef(def) => collect_embeds(&def.nodes, embeds),
+            Node::Cond(conds, _) => {
+                for (_, _, nodes) in conds {
+                    collect_embeds(nodes, embeds);
+                }
+            }
+            Node::Match(_, _, _, arms, _) => {
+                for (_, _, nodes) in arms {
+                    collect_embeds(nodes, embeds);
+                }
+            }
+            Node::Loop(Loop {
+                body, else_block, ..
+            }) => {
+                collect_embeds(body, embeds);
+                collect_embeds(else_block, embeds);
+            }
+            Node::Macro(_, m) => collect_embeds(&m.nodes, embeds),
+            _ => {}
+        }
+    }
+}
+
 mod _parsed {
     use std::mem;
 
-    use crate::config::Syntax;
-    use crate::parser::{parse, Node};
     use crate::CompileError;
+    use askama_parser::{parse, Node, Syntax, WithSpan};
 
     pub(super) struct Parsed {
-        #[allow(dead_code)]
         source: String,
-        nodes: Vec<Node<'static>>,
+        nodes: Vec<WithSpan<Node<'static>>>,
     }
 
     impl Parsed {
@@ -265,9 +392,13 @@ mod _parsed {
         }
 
         // The return value's lifetime must be limited to `self` to uphold the unsafe invariant.
-        pub(super) fn nodes(&self) -> &[Node<'_>] {
+        pub(super) fn nodes(&self) -> &[WithSpan<Node<'_>>] {
             &self.nodes
         }
+
+        pub(super) fn source(&self) -> &str {
+            &self.source
+        }
     }
 }
 
@@ -300,6 +431,10 @@ struct Generator<'a> {
     // If set to `suppress`, the whitespace characters will be removed by default unless `+` is
     // used.
     whitespace: WhitespaceHandling,
+    // The fields of the template struct that were read, for `warn_unused_fields`
+    used_fields: UsedFields,
+    // The filters of `NEW_BUILT_IN_FILTERS` that were applied without the `filters::` prefix
+    new_built_in_filters: BTreeSet<&'static str>,
 }
 
 impl<'a> Generator<'a> {
@@ -322,13 +457,25 @@ impl<'a> Generator<'a> {
             buf_writable: vec![],
             named: 0,
             whitespace,
+            used_fields: UsedFields::Names(HashSet::new()),
+            new_built_in_filters: BTreeSet::new(),
         }
     }
 
     // Takes a Context and generates the relevant implementations.
-    fn build(mut self, ctx: &'a Context<'_>) -> Result<String, CompileError> {
+    fn build(&mut self, ctx: &'a Context<'_>) -> Result<String, CompileError> {
         let mut buf = Buffer::new(0);
 
+        // Without a default context, a template with a render context can only be rendered
+        // with `render_with()`, so it gets neither `Template` nor any of the integrations.
+        // Calling the methods of `Template` is reported by `impl_render_with()`.
+        let input = self.input;
+        if let (Some(ty), false) = (&input.context, input.default_context) {
+            self.impl_render_with(ctx, &mut buf, ty)?;
+            self.write_filters_fallback(&mut buf)?;
+            return Ok(buf.buf);
+        }
+
         self.impl_template(ctx, &mut buf)?;
         self.impl_display(&mut buf)?;
 
@@ -349,21 +496,154 @@ impl<'a> Generator<'a> {
         #[cfg(feature = "with-warp")]
         self.impl_warp_reply(&mut buf)?;
 
+        self.write_filters_fallback(&mut buf)?;
         Ok(buf.buf)
     }
 
+    // Let `filters` resolve without a local `filters` module, for the checks written by
+    // `write_new_filter_checks()`.
+    fn write_filters_fallback(&self, buf: &mut Buffer) -> Result<(), CompileError> {
+        if !self.new_built_in_filters.is_empty() {
+            buf.writeln("#[allow(unused_imports)]")?;
+            buf.writeln("use ::askama::helpers::fallback::*;")?;
+        }
+        Ok(())
+    }
+
+    // A built-in filter of `NEW_BUILT_IN_FILTERS` takes the place of a local filter with the
+    // same name, which was called by the same template in older versions of Askama. Make such
+    // a collision a compile error, which names both filters.
+    fn write_new_filter_checks(&self, buf: &mut Buffer) -> Result<(), CompileError> {
+        for name in &self.new_built_in_filters {
+            buf.writeln("{")?;
+            buf.writeln("#[allow(unused_imports)]")?;
+            buf.writeln(&format!("use ::askama::helpers::built_in_filters::{name};"))?;
+            buf.writeln("{")?;
+            buf.writeln("#[allow(unused_imports)]")?;
+            buf.writeln("use filters::*;")?;
+            buf.writeln(&format!("let _: {name} = {name};"))?;
+            buf.writeln("}")?;
+            buf.writeln("}")?;
+        }
+        Ok(())
+    }
+
     // Implement `Template` for the given context struct.
     fn impl_template(
         &mut self,
         ctx: &'a Context<'_>,
         buf: &mut Buffer,
     ) -> Result<(), CompileError> {
+        let size_hint = match self.input.context {
+            Some(ref ty) => self.impl_render_with(ctx, buf, ty)?,
+            None => 0,
+        };
+
         self.write_header(buf, "::askama::Template", None)?;
         buf.writeln(
             "fn render_into(&self, writer: &mut (impl ::std::fmt::Write + ?Sized)) -> \
              ::askama::Result<()> {",
         )?;
+        let size_hint = match self.input.context {
+            Some(ref ty) => {
+                buf.writeln(&format!(
+                    "self.render_into_with(writer, &<{} as ::core::default::Default>::default())",
+                    quote!(#ty),
+                ))?;
+                buf.writeln("}")?;
+                size_hint
+            }
+            None => self.write_render_body(ctx, buf)?,
+        };
+
+        buf.writeln("const EXTENSION: ::std::option::Option<&'static ::std::primitive::str> = ")?;
+        buf.writeln(&format!("{:?}", self.input.extension()))?;
+        buf.writeln(";")?;
+
+        buf.writeln("const SIZE_HINT: ::std::primitive::usize = ")?;
+        buf.writeln(&format!("{size_hint}"))?;
+        buf.writeln(";")?;
+
+        buf.writeln("const MIME_TYPE: &'static ::std::primitive::str = ")?;
+        buf.writeln(&format!("{:?}", &self.input.mime_type))?;
+        buf.writeln(";")?;
+
+        buf.writeln("}")?;
+        Ok(())
+    }
+
+    // Implement `render_with()` and `render_into_with()`, which render the template with an
+    // injected render context of type `ty`.
+    fn impl_render_with(
+        &mut self,
+        ctx: &'a Context<'_>,
+        buf: &mut Buffer,
+        ty: &syn::Type,
+    ) -> Result<usize, CompileError> {
+        let (impl_generics, ty_generics, where_clause) = self.input.ast.generics.split_for_impl();
+        let ident = &self.input.ast.ident;
+        let vis = &self.input.ast.vis;
+        buf.writeln(&format!(
+            "{} {{",
+            quote!(impl #impl_generics #ident #ty_generics #where_clause)
+        ))?;
+
+        buf.writeln(&format!(
+            "{} {{",
+            quote!(
+                #vis fn render_into_with(
+                    &self,
+                    writer: &mut (impl ::std::fmt::Write + ?Sized),
+                    __askama_ctx: &#ty,
+                ) -> ::askama::Result<()>
+            )
+        ))?;
+        let size_hint = self.write_render_body(ctx, buf)?;
+
+        buf.writeln(&format!(
+            "{} {{",
+            quote!(#vis fn render_with(&self, ctx: &#ty) -> ::askama::Result<::std::string::String>)
+        ))?;
+        buf.writeln("let mut buf = ::std::string::String::new();")?;
+        buf.writeln(&format!("let _ = buf.try_reserve({size_hint});"))?;
+        buf.writeln("self.render_into_with(&mut buf, ctx)?;")?;
+        buf.writeln("::askama::Result::Ok(buf)")?;
+        buf.writeln("}")?;
+
+        // Without a default context, the methods of `Template` can't be called. Stand-ins
+        // with an unsatisfiable bound turn calls to them into an error that names the cause.
+        if !self.input.default_context {
+            for method in [
+                quote!(fn render(&self) -> ::askama::Result<::std::string::String>),
+                quote!(fn render_into(
+                    &self,
+                    writer: &mut (impl ::std::fmt::Write + ?Sized),
+                ) -> ::askama::Result<()>),
+                quote!(fn write_into(
+                    &self,
+                    writer: &mut (impl ::std::io::Write + ?Sized),
+                ) -> ::std::io::Result<()>),
+            ] {
+                buf.writeln(&format!(
+                    "#[doc(hidden)] {} where for<'__askama> &'__askama Self: \
+                     ::askama::helpers::DefaultContextRequired {{",
+                    quote!(#vis #method),
+                ))?;
+                buf.writeln("::core::unreachable!()")?;
+                buf.writeln("}")?;
+            }
+        }
+
+        buf.writeln("}")?;
+        Ok(size_hint)
+    }
 
+    // Write the body of the rendering function, including its closing brace.
+    fn write_render_body(
+        &mut self,
+        ctx: &'a Context<'_>,
+        buf: &mut Buffer,
+    ) -> Result<usize, CompileError> {
         // Make sure the compiler understands that the generated code depends on the template files.
         for path in self.contexts.keys() {
             // Skip the fake path of templates defined in rust source.
@@ -382,6 +662,16 @@ impl<'a> Generator<'a> {
             }
         }
 
+        for path in &self.input.config.catalogs {
+            let path = path.to_str().unwrap();
+            buf.writeln(
+                &quote! {
+                    include_bytes!(#path);
+                }
+                .to_string(),
+            )?;
+        }
+
         let size_hint = if let Some(heritage) = self.heritage {
             self.handle(heritage.root, heritage.root.nodes, buf, AstLevel::Top)
         } else {
@@ -389,23 +679,10 @@ impl<'a> Generator<'a> {
         }?;
 
         self.flush_ws(Ws(None, None));
+        self.write_new_filter_checks(buf)?;
         buf.writeln("::askama::Result::Ok(())")?;
         buf.writeln("}")?;
-
-        buf.writeln("const EXTENSION: ::std::option::Option<&'static ::std::primitive::str> = ")?;
-        buf.writeln(&format!("{:?}", self.input.extension()))?;
-        buf.writeln(";")?;
-
-        buf.writeln("const SIZE_HINT: ::std::primitive::usize = ")?;
-        buf.writeln(&format!("{size_hint}"))?;
-        buf.writeln(";")?;
-
-        buf.writeln("const MIME_TYPE: &'static ::std::primitive::str = ")?;
-        buf.writeln(&format!("{:?}", &self.input.mime_type))?;
-        buf.writeln(";")?;
-
-        buf.writeln("}")?;
-        Ok(())
+        Ok(size_hint)
     }
 
     // Implement `Display` for the given context struct.
@@ -651,17 +928,17 @@ impl<'a> Generator<'a> {
     fn handle(
         &mut self,
         ctx: &'a Context<'_>,
-        nodes: &'a [Node<'_>],
+        nodes: &'a [WithSpan<Node<'_>>],
         buf: &mut Buffer,
         level: AstLevel,
     ) -> Result<usize, CompileError> {
         let mut size_hint = 0;
         for n in nodes {
-            match *n {
+            match **n {
                 Node::Lit(lws, val, rws) => {
                     self.visit_lit(lws, val, rws);
                 }
-                Node::Comment(ws) => {
+                Node::Comment(ws, _) => {
                     self.write_comment(ws);
                 }
                 Node::Expr(ws, ref val) => {
@@ -676,17 +953,23 @@ impl<'a> Generator<'a> {
                 Node::Cond(ref conds, ws) => {
                     size_hint += self.write_cond(ctx, buf, conds, ws)?;
                 }
-                Node::Match(ws1, ref expr, ref arms, ws2) => {
+                Node::Match(ws1, ref expr, _, ref arms, ws2) => {
                     size_hint += self.write_match(ctx, buf, ws1, expr, arms, ws2)?;
                 }
                 Node::Loop(ref loop_block) => {
                     size_hint += self.write_loop(ctx, buf, loop_block)?;
                 }
-                Node::BlockDef(ws1, name, _, ws2) => {
-                    size_hint += self.write_block(buf, Some(name), Ws(ws1.0, ws2.1))?;
+                Node::BlockDef(ref def) => {
+                    size_hint += self.write_block(buf, Some(def.name), Ws(def.ws1.0, def.ws2.1))?;
                 }
-                Node::Include(ws, path) => {
-                    size_hint += self.handle_include(ctx, buf, ws, path)?;
+                Node::Include(ws, ref path, ignore_missing) => {
+                    size_hint += self.handle_include(ctx, buf, ws, path, ignore_missing)?;
+                }
+                Node::Embed(ws1, path, ref nodes, ws2) => {
+                    size_hint += self.write_embed(ctx, buf, ws1, path, nodes, ws2)?;
+                }
+                Node::Trans(ref trans) => {
+                    size_hint += self.write_trans(buf, trans)?;
                 }
                 Node::Call(ws, scope, name, ref args) => {
                     size_hint += self.write_call(ctx, buf, ws, scope, name, args)?;
@@ -703,7 +986,7 @@ impl<'a> Generator<'a> {
                     self.visit_lit(lws, val, rws);
                     self.handle_ws(ws2);
                 }
-                Node::Import(ws, _, _) => {
+                Node::Import(ws, _, _) | Node::FromImport(ws, _, _) => {
                     if level != AstLevel::Top {
                         return Err("import blocks only allowed at the top level".into());
                     }
@@ -726,6 +1009,7 @@ impl<'a> Generator<'a> {
                     self.write_buf_writable(buf)?;
                     buf.writeln("continue;")?;
                 }
+                _ => return Err("this tag is not supported by this version of askama".into()),
             }
         }
 
@@ -881,7 +1165,7 @@ impl<'a> Generator<'a> {
             }
             // If accessing `self` then it most likely needs to be
             // borrowed, to prevent an attempt of moving.
-            _ if expr_code.starts_with("self.") => {
+            _ if expr_code.starts_with("self.") || expr_code.starts_with("__askama_ctx.") => {
                 buf.writeln(&format!("let _iter = (&{expr_code}).into_iter();"))
             }
             // If accessing a field then it most likely needs to be
@@ -938,28 +1222,91 @@ impl<'a> Generator<'a> {
             return self.write_block(buf, None, ws);
         }
 
-        let (def, own_ctx) = match scope {
-            Some(s) => {
-                let path = ctx.imports.get(s).ok_or_else(|| {
-                    CompileError::from(format!("no import found for scope {s:?}"))
-                })?;
-                let mctx = self
-                    .contexts
-                    .get(path.as_path())
-                    .ok_or_else(|| CompileError::from(format!("context for {path:?} not found")))?;
-                let def = mctx.macros.get(name).ok_or_else(|| {
-                    CompileError::from(format!("macro {name:?} not found in scope {s:?}"))
-                })?;
-                (def, mctx)
+        // Look the macro up in the calling template, then in the templates of the
+        // inheritance chain, starting with the most derived one.
+        let chain = self.heritage.map_or(&[][..], |heritage| &heritage.chain);
+        let candidates = Some(ctx)
+            .into_iter()
+            .chain(chain.iter().copied().filter(|c| !ptr::eq(*c, ctx)));
+
+        let mut found = None;
+        for candidate in candidates {
+            match scope {
+                Some(s) => {
+                    if let Some(path) = candidate.imports.get(s) {
+                        let mctx = self.contexts.get(path.as_path()).ok_or_else(|| {
+                            CompileError::from(format!("context for {path:?} not found"))
+                        })?;
+                        let def = mctx.macros.get(name).ok_or_else(|| {
+                            CompileError::from(format!(
+                                "macro {name:?} not found in scope {s:?}{}",
+                                suggest::hint(name, mctx.macros.keys().copied()),
+                            ))
+                        })?;
+                        found = Some((def, mctx));
+                        break;
+                    }
+                }
+                None => {
+                    if let Some(def) = candidate.macros.get(name) {
+                        found = Some((def, candidate));
+                        break;
+                    }
+                    if let Some((path, mname)) = candidate.from_imports.get(name) {
+                        let mctx = self.contexts.get(path.as_path()).ok_or_else(|| {
+                            CompileError::from(format!("context for {path:?} not found"))
+                        })?;
+                        let def = mctx.macros.get(mname).ok_or_else(|| {
+                            CompileError::from(format!(
+                                "macro {mname:?} not found in {path:?}{}",
+                                suggest::hint(mname, mctx.macros.keys().copied()),
+                            ))
+                        })?;
+                        found = Some((def, mctx));
+                        break;
+                    }
+                }
             }
-            None => {
-                let def = ctx
-                    .macros
-                    .get(name)
-                    .ok_or_else(|| CompileError::from(format!("macro {name:?} not found")))?;
-                (def, ctx)
+        }
+        // Last, look in the templates of the prelude that were imported without a scope.
+        if found.is_none() && scope.is_none() {
+            for (path, _) in self
+                .input
+                .config
+                .prelude
+                .iter()
+                .filter(|(_, s)| s.is_none())
+            {
+                if let Some(mctx) = self.contexts.get(path.as_path()) {
+                    if let Some(def) = mctx.macros.get(name) {
+                        found = Some((def, mctx));
+                        break;
+                    }
+                }
             }
-        };
+        }
+        let (def, own_ctx) = found.ok_or_else(|| {
+            let contexts = Some(ctx).into_iter().chain(chain.iter().copied());
+            CompileError::from(match scope {
+                Some(s) => format!(
+                    "no import found for scope {s:?}{}",
+                    suggest::hint(s, contexts.flat_map(|c| c.imports.keys().copied())),
+                ),
+                None => {
+                    let prelude = self
+                        .input
+                        .config
+                        .prelude
+                        .iter()
+                        .filter(|(_, s)| s.is_none())
+                        .filter_map(|(path, _)| self.contexts.get(path.as_path()));
+                    let names = contexts
+                        .flat_map(|c| c.macros.keys().chain(c.from_imports.keys()).copied())
+                        .chain(prelude.flat_map(|c| c.macros.keys().copied()));
+                    format!("macro {name:?} not found{}", suggest::hint(name, names))
+                }
+            })
+        })?;
 
         self.flush_ws(ws); // Cannot handle_ws() here: whitespace from macro definition comes first
         self.locals.push();
@@ -980,7 +1327,7 @@ impl<'a> Generator<'a> {
                 // don't reintroduce a new variable. This is
                 // to avoid moving non-copyable values.
                 Expr::Var(name) => {
-                    let var = self.locals.resolve_or_self(name);
+                    let var = self.resolve_var(name);
                     self.locals.insert(arg, LocalMeta::with_ref(var));
                 }
                 Expr::Attr(obj, attr) => {
@@ -1031,15 +1378,118 @@ impl<'a> Generator<'a> {
         ctx: &'a Context<'_>,
         buf: &mut Buffer,
         ws: Ws,
-        path: &str,
+        path: &Expr<'_>,
+        ignore_missing: bool,
     ) -> Result<usize, CompileError> {
         self.flush_ws(ws);
         self.write_buf_writable(buf)?;
-        let path = self
+
+        let size_hint = match path {
+            Expr::StrLit(path) => match self.find_include(path, ignore_missing)? {
+                Some(path) => self.write_include(ctx, buf, path)?,
+                None => 0,
+            },
+            // Like in Jinja, a list of templates includes the first one that exists.
+            Expr::Array(paths) => {
+                let paths = include_candidates(paths)?;
+                let mut found = None;
+                for path in &paths {
+                    if let Some(path) = self.find_include(path, true)? {
+                        found = Some(path);
+                        break;
+                    }
+                }
+                match found {
+                    Some(path) => self.write_include(ctx, buf, path)?,
+                    None if ignore_missing => 0,
+                    None => {
+                        return Err(format!("none of the templates {paths:?} could be found").into())
+                    }
+                }
+            }
+            // An index into a list of templates is resolved at runtime: every candidate is
+            // compiled ahead of time, and the generated code dispatches on the position of the
+            // selected path. The key is either an index or one of the paths.
+            Expr::Index(obj, key) => {
+                let paths = match obj.as_ref() {
+                    Expr::Array(paths) => include_candidates(paths)?,
+                    _ => return Err(INVALID_INCLUDE_PATH.into()),
+                };
+
+                buf.write("match ::askama::helpers::IncludeKey::position(&(");
+                self.visit_expr(buf, key)?;
+                buf.write("), &[");
+                for (i, path) in paths.iter().enumerate() {
+                    if i > 0 {
+                        buf.write(", ");
+                    }
+                    self.visit_str_lit(buf, path);
+                }
+                buf.writeln("]) {")?;
+
+                // A path that is listed more than once gets a single arm for all its positions.
+                let mut arm_sizes = Vec::with_capacity(paths.len());
+                for (i, path) in paths.iter().enumerate() {
+                    if paths[..i].contains(path) {
+                        continue;
+                    }
+                    buf.write("::core::option::Option::Some(");
+                    let positions = paths.iter().enumerate().filter(|(_, p)| *p == path);
+                    for (j, (pos, _)) in positions.enumerate() {
+                        if j > 0 {
+                            buf.write(" | ");
+                        }
+                        buf.write(&pos.to_string());
+                    }
+                    buf.writeln(") => {")?;
+                    arm_sizes.push(match self.find_include(path, ignore_missing)? {
+                        Some(path) => self.write_include(ctx, buf, path)?,
+                        None => 0,
+                    });
+                    buf.writeln("}")?;
+                }
+                if ignore_missing {
+                    buf.writeln("_ => {}")?;
+                } else {
+                    buf.writeln(&format!(
+                        "_ => return ::core::result::Result::Err(::askama::Error::Custom({:?}.into())),",
+                        format!("the included template is not one of {paths:?}"),
+                    ))?;
+                }
+                buf.writeln("}")?;
+                median(&mut arm_sizes)
+            }
+            _ => return Err(INVALID_INCLUDE_PATH.into()),
+        };
+
+        self.prepare_ws(ws);
+        Ok(size_hint)
+    }
+
+    // Resolves the path of an included template. Returns `None` if the template
+    // does not exist and `ignore_missing` was requested.
+    fn find_include(
+        &self,
+        path: &str,
+        ignore_missing: bool,
+    ) -> Result<Option<PathBuf>, CompileError> {
+        match self
             .input
             .config
-            .find_template(path, Some(&self.input.path))?;
+            .find_template(path, Some(&self.input.path))
+        {
+            Ok(path) => Ok(Some(path)),
+            Err(_) if ignore_missing => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
 
+    fn write_include(
+        &mut self,
+        ctx: &'a Context<'_>,
+        buf: &mut Buffer,
+        path: PathBuf,
+    ) -> Result<usize, CompileError> {
         // Make sure the compiler understands that the generated code depends on the template file.
         {
             let path = path.to_str().unwrap();
@@ -1076,8 +1526,171 @@ impl<'a> Generator<'a> {
 
         let mut size_hint = child.handle(ctx, nodes, buf, AstLevel::Nested)?;
         size_hint += child.write_buf_writable(buf)?;
-        self.prepare_ws(ws);
+        self.used_fields.extend(child.used_fields);
+        Ok(size_hint)
+    }
+
+    fn write_trans(
+        &mut self,
+        buf: &mut Buffer,
+        trans: &'a Trans<'_>,
+    ) -> Result<usize, CompileError> {
+        let (key, plural, mut names) = trans.message()?;
+        for (name, _) in &trans.vars {
+            if !names.contains(name) {
+                names.push(name);
+            }
+        }
+        self._write_trans(buf, trans, key, plural, names)
+    }
+
+    #[cfg(not(feature = "i18n"))]
+    fn _write_trans(
+        &mut self,
+        _: &mut Buffer,
+        _: &'a Trans<'_>,
+        _: String,
+        _: Option<String>,
+        _: Vec<&str>,
+    ) -> Result<usize, CompileError> {
+        Err("`trans` blocks require the `i18n` feature to be enabled".into())
+    }
+
+    #[cfg(feature = "i18n")]
+    fn _write_trans(
+        &mut self,
+        buf: &mut Buffer,
+        trans: &'a Trans<'_>,
+        key: String,
+        plural: Option<String>,
+        names: Vec<&str>,
+    ) -> Result<usize, CompileError> {
+        // Fluent catalogs define the derived id, gettext catalogs the text itself.
+        let id = askama_parser::message_id(&key);
+        self.check_message_key(&[&id, &key])?;
+
+        self.flush_ws(trans.ws1);
+        self.write_buf_writable(buf)?;
+
+        // Like the result of `_()`, the translated message is escaped as a whole.
+        let mut args = Buffer::new(0);
+        for name in names {
+            let value = match trans.vars.iter().find(|(var, _)| *var == name) {
+                Some((_, expr)) => self.visit_expr_root(expr)?,
+                None => self.resolve_var(name),
+            };
+            args.write(&format!("({name:?}, &({value})), "));
+        }
+        let (plural, count) = match plural {
+            Some(plural) => {
+                let count = match trans.vars.iter().find(|(var, _)| *var == "count") {
+                    Some((_, expr)) => self.visit_expr_root(expr)?,
+                    None => self.resolve_var("count"),
+                };
+                (
+                    format!("::core::option::Option::Some({plural:?})"),
+                    format!(
+                        "::core::option::Option::Some(::askama::i18n::Count::as_count(&({count})))"
+                    ),
+                )
+            }
+            None => (
+                "::core::option::Option::None".to_string(),
+                "::core::option::Option::None".to_string(),
+            ),
+        };
+
+        buf.writeln(&format!(
+            "::std::write!(writer, \"{{}}\", ::askama::MarkupDisplay::new_unsafe(\
+             ::askama::i18n::Localizer::translate({}, &::askama::i18n::Message {{ \
+             id: {:?}, text: {:?}, plural: {}, count: {}, args: &[{}] }}), {}))?;",
+            self.localizer(),
+            id,
+            key,
+            plural,
+            count,
+            args.buf,
+            self.input.escaper,
+        ))?;
+
+        self.prepare_ws(trans.ws2);
+        Ok(key.len())
+    }
+
+    #[cfg(feature = "i18n")]
+    fn localizer(&self) -> &'static str {
+        match self.input.context {
+            Some(_) => "__askama_ctx",
+            None => "self",
+        }
+    }
+
+    // Checks that the catalogs define one of the `keys` of a message, if any are configured.
+    #[cfg(feature = "i18n")]
+    fn check_message_key(&self, keys: &[&str]) -> Result<(), CompileError> {
+        match self.input.config.messages {
+            Some(ref messages) if !keys.iter().any(|key| messages.contains(*key)) => Err(format!(
+                "message key {:?} not found in the translation catalogs",
+                keys[0]
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
 
+    // An embed site acts as an anonymous child template of the embedded template: its
+    // blocks override the embedded template's blocks, but only for this one site.
+    #[allow(clippy::too_many_arguments)]
+    fn write_embed(
+        &mut self,
+        ctx: &'a Context<'_>,
+        buf: &mut Buffer,
+        ws1: Ws,
+        path: &str,
+        nodes: &'a [WithSpan<Node<'_>>],
+        ws2: Ws,
+    ) -> Result<usize, CompileError> {
+        self.flush_ws(ws1);
+        self.write_buf_writable(buf)?;
+
+        let mut embed = Context::new(self.input.config, &self.input.path, nodes)?;
+        if embed.extends.is_some() {
+            return Err("extends blocks not allowed inside embed blocks".into());
+        }
+        embed.extends = Some(
+            self.input
+                .config
+                .find_template(path, Some(&self.input.path))?,
+        );
+
+        // Macros and imports of the embedding template remain available in the blocks.
+        for (name, def) in &ctx.macros {
+            embed.macros.entry(name).or_insert(def);
+        }
+        for (scope, path) in &ctx.imports {
+            embed.imports.entry(scope).or_insert_with(|| path.clone());
+        }
+        for (name, import) in &ctx.from_imports {
+            embed
+                .from_imports
+                .entry(name)
+                .or_insert_with(|| import.clone());
+        }
+
+        let heritage = Heritage::new(&embed, self.contexts)?;
+        let locals = MapChain::with_parent(&self.locals);
+        let mut child = Generator::new(
+            self.input,
+            self.contexts,
+            Some(&heritage),
+            locals,
+            self.whitespace,
+        );
+
+        let mut size_hint = child.handle(heritage.root, heritage.root.nodes, buf, AstLevel::Top)?;
+        size_hint += child.write_buf_writable(buf)?;
+        self.used_fields.extend(child.used_fields);
+        self.prepare_ws(ws2);
         Ok(size_hint)
     }
 
@@ -1191,24 +1804,21 @@ impl<'a> Generator<'a> {
             .heritage
             .as_ref()
             .ok_or_else(|| CompileError::from("no block ancestors available"))?;
-        let (ctx, def) = heritage.blocks[cur.0].get(cur.1).ok_or_else(|| {
+        let ancestry = &heritage.blocks[cur.0];
+        let (def_ctx, def) = ancestry.get(cur.1).ok_or_else(|| {
             CompileError::from(match name {
                 None => format!("no super() block found for block '{}'", cur.0),
-                Some(name) => format!("no block found for name '{name}'"),
+                Some(name) => format!(
+                    "no block found for name '{name}'{}",
+                    suggest::hint(name, heritage.blocks.keys().copied()),
+                ),
             })
         })?;
 
-        // Get the nodes and whitespace suppression data from the block definition
-        let (ws1, nodes, ws2) = if let Node::BlockDef(ws1, _, nodes, ws2) = def {
-            (ws1, nodes, ws2)
-        } else {
-            unreachable!()
-        };
-
         // Handle inner whitespace suppression spec and process block nodes
-        self.prepare_ws(*ws1);
+        self.prepare_ws(def.ws1);
         self.locals.push();
-        let size_hint = self.handle(ctx, nodes, buf, AstLevel::Block)?;
+        let size_hint = self.handle(def_ctx, &def.nodes, buf, AstLevel::Block)?;
 
         if !self.locals.is_current_empty() {
             // Need to flush the buffer before popping the variable stack
@@ -1216,7 +1826,7 @@ impl<'a> Generator<'a> {
         }
 
         self.locals.pop();
-        self.flush_ws(*ws2);
+        self.flush_ws(def.ws2);
 
         // Restore original block context and set whitespace suppression for
         // succeeding whitespace according to the outer WS spec
@@ -1376,9 +1986,66 @@ impl<'a> Generator<'a> {
             Expr::RustMacro(name, args) => self.visit_rust_macro(buf, name, args),
             Expr::Try(ref expr) => self.visit_try(buf, expr.as_ref())?,
             Expr::Tuple(ref exprs) => self.visit_tuple(buf, exprs)?,
+            Expr::NamedArgument(name, _) => {
+                return Err(format!("named argument `{name}` is only allowed in `_()`").into());
+            }
+            _ => return Err("this expression is not supported by this version of askama".into()),
         })
     }
 
+    #[cfg(not(feature = "i18n"))]
+    fn _visit_localize(
+        &mut self,
+        _buf: &mut Buffer,
+        _args: &[Expr<'_>],
+    ) -> Result<DisplayWrap, CompileError> {
+        Err("`_()` requires the `i18n` feature to be enabled".into())
+    }
+
+    #[cfg(feature = "i18n")]
+    fn _visit_localize(
+        &mut self,
+        buf: &mut Buffer,
+        args: &[Expr<'_>],
+    ) -> Result<DisplayWrap, CompileError> {
+        let (key, args) = match args {
+            [Expr::StrLit(key), args @ ..] => (*key, args),
+            _ => return Err("`_()` expects a string literal as message key".into()),
+        };
+        // The catalogs contain the value of the literal, with its escape sequences resolved.
+        let value = syn::parse_str::<syn::LitStr>(&format!("\"{key}\""))
+            .map_err(|_| CompileError::from(format!("invalid message key \"{key}\"")))?
+            .value();
+        self.check_message_key(&[&value])?;
+
+        let mut count = "::core::option::Option::None".to_string();
+        let mut message_args = Buffer::new(0);
+        for arg in args {
+            let (name, value) = match arg {
+                Expr::NamedArgument(name, value) => (*name, self.visit_expr_root(value)?),
+                _ => return Err("`_()` expects named arguments after the message key".into()),
+            };
+            if name == "count" {
+                count = format!(
+                    "::core::option::Option::Some(::askama::i18n::Count::as_count(&({value})))"
+                );
+            }
+            message_args.write(&format!("({name:?}, &({value})), "));
+        }
+
+        buf.write(&format!(
+            "::askama::i18n::Localizer::translate({}, &::askama::i18n::Message {{ \
+             id: \"{}\", text: \"{}\", plural: ::core::option::Option::None, count: {}, \
+             args: &[{}] }})",
+            self.localizer(),
+            key,
+            key,
+            count,
+            message_args.buf,
+        ));
+        Ok(DisplayWrap::Unwrapped)
+    }
+
     fn visit_try(
         &mut self,
         buf: &mut Buffer,
@@ -1391,6 +2058,25 @@ impl<'a> Generator<'a> {
     }
 
     fn visit_rust_macro(&mut self, buf: &mut Buffer, name: &str, args: &str) -> DisplayWrap {
+        // The arguments are Rust code, which can only read fields as `self.field`.
+        for (i, _) in args.match_indices("self") {
+            let before = args[..i].chars().next_back();
+            if before.map_or(false, |c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            let after = &args[i + 4..];
+            match after.strip_prefix('.') {
+                Some(attr) => {
+                    let end = attr
+                        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .unwrap_or(attr.len());
+                    self.used_fields.insert(&attr[..end]);
+                }
+                None if after.starts_with(|c: char| c.is_alphanumeric() || c == '_') => {}
+                None => self.used_fields = UsedFields::All,
+            }
+        }
+
         buf.write(name);
         buf.write("!(");
         buf.write(args);
@@ -1414,14 +2100,57 @@ impl<'a> Generator<'a> {
         buf: &mut Buffer,
         args: &[Expr<'_>],
     ) -> Result<DisplayWrap, CompileError> {
-        let (md, options) = match args {
-            [md] => (md, None),
-            [md, options] => (md, Some(options)),
-            _ => return Err("markdown filter expects no more than one option argument".into()),
+        let (md, options, highlighter) = match args {
+            [md] => (md, None, None),
+            // A string literal can't be the options, so it's the highlighting theme.
+            [md, theme @ Expr::StrLit(_)] => (md, None, Some(theme)),
+            [md, options] => (md, Some(options), None),
+            [md, options, highlighter] => (md, Some(options), Some(highlighter)),
+            _ => return Err("markdown filter expects no more than two arguments".into()),
         };
 
+        // A string literal names a syntect theme, other highlighters are passed as is.
+        let theme = match highlighter {
+            Some(Expr::StrLit(theme)) => Some(*theme),
+            Some(_) => None,
+            None => self.input.config.highlight_theme,
+        };
+        if let Some(theme) = theme {
+            check_highlight_theme(theme)?;
+            #[cfg(not(feature = "syntect"))]
+            return Err(
+                "highlighting code in the `markdown` filter with syntect requires the `syntect` \
+                 feature to be enabled"
+                    .into(),
+            );
+        }
+
+        // The output can be sanitized like the one of the `sanitize` filter.
+        let sanitize = matches!(&self.input.config.sanitize, Some(config) if config.markdown);
+        if let (Some(config), Some(_)) = (&self.input.config.sanitize, theme) {
+            config.check_highlighting()?;
+        }
+        #[cfg(not(feature = "ammonia"))]
+        if sanitize {
+            return Err(
+                "sanitizing the output of the `markdown` filter requires the `ammonia` \
+                        feature to be enabled"
+                    .into(),
+            );
+        }
+        if sanitize {
+            buf.write(&format!(
+                "::askama::filters::sanitize({}, ",
+                self.input.escaper
+            ));
+        }
+
+        let func = match theme.is_some() || highlighter.is_some() {
+            true => "markdown_with_highlighter",
+            false => "markdown",
+        };
         buf.write(&format!(
-            "::askama::filters::markdown({}, ",
+            "::askama::filters::{func}({}, ",
             self.input.escaper
         ));
         self.visit_expr(buf, md)?;
@@ -1433,17 +2162,226 @@ impl<'a> Generator<'a> {
             }
             None => buf.write(", ::core::option::Option::None"),
         }
+        match (theme, highlighter) {
+            (Some(theme), _) => buf.write(&format!(
+                ", &*::askama::filters::syntect_adapter({theme:?})"
+            )),
+            (None, Some(highlighter)) => {
+                buf.write(", ");
+                self.visit_expr(buf, highlighter)?;
+            }
+            (None, None) => {}
+        }
         buf.write(")?");
 
+        #[cfg(feature = "ammonia")]
+        if sanitize {
+            buf.write(&format!(", {})?", self.sanitize_allowlist()));
+        }
+
         Ok(DisplayWrap::Wrapped)
     }
 
+    #[cfg(not(feature = "ammonia"))]
+    fn _visit_sanitize_filter(
+        &mut self,
+        _buf: &mut Buffer,
+        _args: &[Expr<'_>],
+    ) -> Result<DisplayWrap, CompileError> {
+        Err("the `sanitize` filter requires the `ammonia` feature to be enabled".into())
+    }
+
+    #[cfg(feature = "ammonia")]
+    fn _visit_sanitize_filter(
+        &mut self,
+        buf: &mut Buffer,
+        args: &[Expr<'_>],
+    ) -> Result<DisplayWrap, CompileError> {
+        let html = match args {
+            [html] => html,
+            _ => return Err("sanitize filter takes no arguments".into()),
+        };
+
+        buf.write(&format!(
+            "::askama::filters::sanitize({}, &(",
+            self.input.escaper
+        ));
+        self.visit_expr(buf, html)?;
+        buf.write(&format!("), {})?", self.sanitize_allowlist()));
+        Ok(DisplayWrap::Wrapped)
+    }
+
+    // Writes the allowlist of the `[sanitize]` section of the config as a `static`, so that
+    // the ammonia builder is cached across renders.
+    #[cfg(feature = "ammonia")]
+    fn sanitize_allowlist(&self) -> String {
+        let config = match &self.input.config.sanitize {
+            Some(config) => config,
+            None => return "::core::option::Option::None".into(),
+        };
+        let mut allowlist = String::from("::askama::filters::Allowlist::new()");
+        let list = |list: &[&str]| format!("&{list:?}");
+        if let Some(tags) = &config.tags {
+            allowlist.push_str(&format!(".tags({})", list(tags)));
+        }
+        if let Some(tags) = &config.tag_attributes {
+            let tags: Vec<String> = tags
+                .iter()
+                .map(|(tag, attributes)| format!("({tag:?}, {})", list(attributes)))
+                .collect();
+            allowlist.push_str(&format!(".tag_attributes(&[{}])", tags.join(", ")));
+        }
+        if let Some(attributes) = &config.generic_attributes {
+            allowlist.push_str(&format!(".generic_attributes({})", list(attributes)));
+        }
+        if let Some(schemes) = &config.url_schemes {
+            allowlist.push_str(&format!(".url_schemes({})", list(schemes)));
+        }
+        if let Some(rel) = config.link_rel {
+            allowlist.push_str(&format!(".link_rel({rel:?})"));
+        }
+        format!(
+            "{{ static ALLOWLIST: ::askama::filters::Allowlist<'static> = {allowlist}; \
+             ::core::option::Option::Some(&ALLOWLIST) }}"
+        )
+    }
+
+    // The number filters have optional arguments: the number of decimals of `numberformat`
+    // and `percent`, which defaults to 0, and the locale, which comes last.
+    fn _visit_number_filter(
+        &mut self,
+        buf: &mut Buffer,
+        name: &str,
+        args: &[Expr<'_>],
+    ) -> Result<DisplayWrap, CompileError> {
+        let min = match name {
+            "currency" => 2,
+            _ => 1,
+        };
+        if args.len() < min || args.len() > 3 {
+            return Err(match name {
+                "currency" => "currency filter expects a currency code and an optional locale",
+                "numberformat" => "numberformat filter expects no more than two arguments",
+                _ => "percent filter expects no more than two arguments",
+            }
+            .into());
+        }
+        let (args, locale) = match args {
+            [args @ .., locale] if args.len() == 2 => (args, Some(locale)),
+            args => (args, None),
+        };
+        #[cfg(not(feature = "icu"))]
+        if locale.is_some() {
+            return Err(format!(
+                "the locale argument of the `{name}` filter requires the `icu` feature to be enabled"
+            )
+            .into());
+        }
+
+        buf.write(&format!("::askama::filters::{name}("));
+        self._visit_args(buf, args)?;
+        if args.len() < 2 {
+            buf.write(", 0");
+        }
+        match locale {
+            Some(locale) => {
+                buf.write(", ::core::option::Option::Some(");
+                self._visit_args(buf, std::slice::from_ref(locale))?;
+                buf.write(")");
+            }
+            None => buf.write(", ::core::option::Option::None"),
+        }
+        buf.write(")?");
+        Ok(DisplayWrap::Unwrapped)
+    }
+
+    // The value is always borrowed, as it's usually an `Option` that can't be moved out of
+    // the template. If either the value or the default is already escaped, like a value
+    // marked as `safe`, the other one gets escaped here, so the result can stay wrapped.
+    fn _visit_default_filter(
+        &mut self,
+        buf: &mut Buffer,
+        name: &str,
+        args: &[Expr<'_>],
+    ) -> Result<DisplayWrap, CompileError> {
+        let (input, default, empty) = match (name, args) {
+            ("unwrap_or_empty", [input]) => (input, None, None),
+            ("unwrap_or_empty", _) => {
+                return Err("unwrap_or_empty filter takes no arguments".into());
+            }
+            ("default", [input, default]) => (input, Some(default), None),
+            ("default", [input, default, empty]) => (input, Some(default), Some(empty)),
+            _ => {
+                return Err(
+                    "default filter expects a default value and an optional boolean \
+                            to also replace empty values"
+                        .into(),
+                );
+            }
+        };
+
+        let mut input_buf = Buffer::new(0);
+        let input_wrap = self.visit_expr(&mut input_buf, input)?;
+        let default = match default {
+            Some(default) => default,
+            None => {
+                buf.write(&format!(
+                    "::askama::filters::unwrap_or_empty(&({}))?",
+                    input_buf.buf
+                ));
+                return Ok(input_wrap);
+            }
+        };
+        let mut default_buf = Buffer::new(0);
+        let default_wrap = self.visit_expr(&mut default_buf, default)?;
+
+        buf.write(&format!(
+            "::askama::filters::default(&({}), ",
+            input_buf.buf
+        ));
+        match (input_wrap, default_wrap) {
+            (DisplayWrap::Wrapped, DisplayWrap::Unwrapped) => buf.write(&format!(
+                "::askama::MarkupDisplay::new_unsafe(&({}), {})",
+                default_buf.buf, self.input.escaper
+            )),
+            _ => buf.write(&format!("&({})", default_buf.buf)),
+        }
+        match empty {
+            Some(empty) => {
+                buf.write(", ");
+                self.visit_expr(buf, empty)?;
+            }
+            None => buf.write(", false"),
+        }
+        buf.write(")?");
+        Ok(match (input_wrap, default_wrap) {
+            (DisplayWrap::Unwrapped, DisplayWrap::Unwrapped) => DisplayWrap::Unwrapped,
+            (DisplayWrap::Unwrapped, DisplayWrap::Wrapped) => {
+                buf.write(&format!(".escape_value({})", self.input.escaper));
+                DisplayWrap::Wrapped
+            }
+            _ => DisplayWrap::Wrapped,
+        })
+    }
+
     fn visit_filter(
         &mut self,
         buf: &mut Buffer,
         mut name: &str,
         args: &[Expr<'_>],
     ) -> Result<DisplayWrap, CompileError> {
+        // A filter of the local `filters` module, even if a built-in filter has the same name.
+        if let Some(name) = name.strip_prefix("filters::") {
+            buf.write(&format!("filters::{name}("));
+            self._visit_args(buf, args)?;
+            buf.write(")?");
+            return Ok(DisplayWrap::Unwrapped);
+        }
+
+        if let Some(name) = crate::NEW_BUILT_IN_FILTERS.iter().find(|n| **n == name) {
+            self.new_built_in_filters.insert(name);
+        }
+
         if matches!(name, "escape" | "e") {
             self._visit_escape_filter(buf, args)?;
             return Ok(DisplayWrap::Wrapped);
@@ -1458,6 +2396,15 @@ impl<'a> Generator<'a> {
             return Ok(DisplayWrap::Unwrapped);
         } else if name == "markdown" {
             return self._visit_markdown_filter(buf, args);
+        } else if name == "sanitize" {
+            return self._visit_sanitize_filter(buf, args);
+        } else if matches!(name, "numberformat" | "percent" | "currency") {
+            return self._visit_number_filter(buf, name, args);
+        } else if matches!(name, "default" | "unwrap_or_empty") {
+            return self._visit_default_filter(buf, name, args);
+        } else if ITER_FILTERS.contains(&name) {
+            self._visit_iter_filter(buf, name, args)?;
+            return Ok(DisplayWrap::Unwrapped);
         }
 
         if name == "tojson" {
@@ -1472,8 +2419,23 @@ impl<'a> Generator<'a> {
         if name == "yaml" {
             return Err("the `yaml` filter requires the `serde-yaml` feature to be enabled".into());
         }
+        #[cfg(not(any(feature = "chrono", feature = "time")))]
+        if matches!(
+            name,
+            "date" | "datetime" | "isoformat" | "timesince" | "timeuntil"
+        ) {
+            return Err(format!(
+                "the `{name}` filter requires the `chrono` or `time` feature to be enabled"
+            )
+            .into());
+        }
+
+        #[cfg(not(feature = "slugify"))]
+        if name == "slugify" {
+            return Err("the `slugify` filter requires the `slugify` feature to be enabled".into());
+        }
 
-        const FILTERS: [&str; 2] = ["safe", "yaml"];
+        const FILTERS: [&str; 3] = ["safe", "urlize", "yaml"];
         if FILTERS.contains(&name) {
             buf.write(&format!(
                 "::askama::filters::{}({}, ",
@@ -1481,6 +2443,14 @@ impl<'a> Generator<'a> {
             ));
         } else if crate::BUILT_IN_FILTERS.contains(&name) {
             buf.write(&format!("::askama::filters::{name}("));
+        } else if suggest::typo_of(name, crate::BUILT_IN_FILTERS.iter().copied()).is_some() {
+            // Most likely a misspelled built-in filter, but it may as well be a local filter.
+            // Without a local `filters` module, `filters` refers to Askama's filters instead,
+            // so that the compiler suggests the built-in filter if the path can't be resolved.
+            buf.write(&format!(
+                "({{ #[allow(unused_imports)] use ::askama::filters as filters; \
+                 {{ #[allow(unused_imports)] use self::*; filters::{name} }} }})("
+            ));
         } else {
             buf.write(&format!("filters::{name}("));
         }
@@ -1569,20 +2539,119 @@ impl<'a> Generator<'a> {
         buf: &mut Buffer,
         args: &[Expr<'_>],
     ) -> Result<(), CompileError> {
-        buf.write("::askama::filters::join((&");
+        buf.write("::askama::filters::join(");
         for (i, arg) in args.iter().enumerate() {
-            if i > 0 {
+            if i == 0 {
+                self._visit_iter_input(buf, arg)?;
+            } else {
                 buf.write(", &");
+                self.visit_expr(buf, arg)?;
             }
-            self.visit_expr(buf, arg)?;
-            if i == 0 {
-                buf.write(").into_iter()");
+        }
+        buf.write(")?");
+        Ok(())
+    }
+
+    // The collection filters take an iterator over their input. The output of another
+    // filter is consumed, anything else is borrowed, like the iterable of a `for` loop.
+    fn _visit_iter_filter(
+        &mut self,
+        buf: &mut Buffer,
+        name: &str,
+        args: &[Expr<'_>],
+    ) -> Result<(), CompileError> {
+        let (input, args) = match args.split_first() {
+            Some(split) => split,
+            None => return Err(format!("the `{name}` filter requires an input").into()),
+        };
+        match (name, args) {
+            ("sort_by" | "map" | "groupby", [Expr::StrLit(_)]) => {}
+            ("select" | "reject", [Expr::StrLit(_)] | [Expr::StrLit(_), _] | [Expr::Path(_)]) => {}
+            ("sort_by" | "map" | "groupby", _) => {
+                return Err(format!(
+                    "{name} filter expects the name of an attribute as a string literal"
+                )
+                .into())
+            }
+            ("select" | "reject", _) => {
+                return Err(format!(
+                    "{name} filter expects the name of an attribute as a string literal and \
+                     an optional value to compare it with, or the path of a test function"
+                )
+                .into())
+            }
+            ("batch", [_] | [_, _]) | ("slice", [_]) => {}
+            ("batch", _) => {
+                return Err("batch filter expects a size and an optional fill item".into())
+            }
+            ("slice", _) => return Err("slice filter expects the number of slices".into()),
+            (_, []) => {}
+            _ => return Err(format!("the `{name}` filter takes no arguments").into()),
+        }
+
+        buf.write(&format!("::askama::filters::{name}("));
+        self._visit_iter_input(buf, input)?;
+        if matches!(name, "batch" | "slice") {
+            // The size is a `usize`, so it's passed by value.
+            buf.write(", ");
+            self.visit_expr(buf, &args[0])?;
+            match args.get(1) {
+                // The fill item is borrowed like the items of the input.
+                Some(fill) => {
+                    buf.write(", ::core::option::Option::Some(&(");
+                    self.visit_expr(buf, fill)?;
+                    buf.write("))");
+                }
+                None if name == "batch" => buf.write(", ::core::option::Option::None"),
+                None => {}
+            }
+            buf.write(")?");
+            return Ok(());
+        }
+        // The attribute arguments become closures accessing the attribute of the items.
+        match args {
+            [Expr::StrLit(attr)] => {
+                let attr = attr_path(attr)?;
+                buf.write(&match name {
+                    "sort_by" => {
+                        format!(", |a, b| ::askama::filters::sort_order(&a{attr}, &b{attr})")
+                    }
+                    "map" => format!(", |_item| &_item{attr}"),
+                    "groupby" => format!(", |_item| ::core::clone::Clone::clone(&_item{attr})"),
+                    _ => format!(", |_item| _item{attr}"),
+                });
             }
+            [Expr::StrLit(attr), value] => {
+                let attr = attr_path(attr)?;
+                buf.write(", { let _value = &(");
+                self.visit_expr(buf, value)?;
+                buf.write(&format!("); move |_item| _item{attr} == *_value }}"));
+            }
+            [test @ Expr::Path(_)] => {
+                buf.write(", |_item| (");
+                self.visit_expr(buf, test)?;
+                buf.write(")(_item)");
+            }
+            _ => {}
         }
         buf.write(")?");
         Ok(())
     }
 
+    fn _visit_iter_input(
+        &mut self,
+        buf: &mut Buffer,
+        input: &Expr<'_>,
+    ) -> Result<(), CompileError> {
+        match input {
+            Expr::Filter(..) => buf.write("("),
+            _ => buf.write("(&"),
+        }
+        self.visit_expr(buf, input)?;
+        buf.write(").into_iter()");
+        Ok(())
+    }
+
     fn _visit_args(&mut self, buf: &mut Buffer, args: &[Expr<'_>]) -> Result<(), CompileError> {
         if args.is_empty() {
             return Ok(());
@@ -1637,8 +2706,16 @@ impl<'a> Generator<'a> {
                     buf.write("_loop_item.last");
                     return Ok(DisplayWrap::Unwrapped);
                 } else {
-                    return Err("unknown loop variable".into());
+                    return Err(format!(
+                        "unknown loop variable {attr:?}{}",
+                        suggest::hint(attr, LOOP_VARIABLES),
+                    )
+                    .into());
                 }
+            } else if name == "self" {
+                self.used_fields.insert(attr);
+                buf.write(&format!("self.{}", normalize_identifier(attr)));
+                return Ok(DisplayWrap::Unwrapped);
             }
         }
         self.visit_expr(buf, obj)?;
@@ -1686,13 +2763,20 @@ impl<'a> Generator<'a> {
                     }
                     _ => return Err("loop.cycle(…) expects exactly one argument".into()),
                 },
-                s => return Err(format!("unknown loop method: {s:?}").into()),
+                s => {
+                    return Err(format!(
+                        "unknown loop method: {s:?}{}",
+                        suggest::hint(s, LOOP_METHODS),
+                    )
+                    .into())
+                }
             },
+            Expr::Var("_") => return self._visit_localize(buf, args),
             left => {
                 match left {
                     Expr::Var(name) => match self.locals.resolve(name) {
                         Some(resolved) => buf.write(&resolved),
-                        None => buf.write(&format!("(&self.{})", normalize_identifier(name))),
+                        None => buf.write(&format!("(&{})", self.resolve_var(name))),
                     },
                     left => {
                         self.visit_expr(buf, left)?;
@@ -1804,14 +2888,31 @@ impl<'a> Generator<'a> {
 
     fn visit_var(&mut self, buf: &mut Buffer, s: &str) -> DisplayWrap {
         if s == "self" {
+            self.used_fields = UsedFields::All;
             buf.write(s);
             return DisplayWrap::Unwrapped;
         }
 
-        buf.write(normalize_identifier(&self.locals.resolve_or_self(s)));
+        buf.write(normalize_identifier(&self.resolve_var(s)));
         DisplayWrap::Unwrapped
     }
 
+    // Resolves a variable to a local, a field of the template, or, if the template has a
+    // render context, a field of the context.
+    fn resolve_var(&mut self, name: &str) -> String {
+        if let Some(resolved) = self.locals.resolve(name) {
+            return resolved;
+        }
+        let name = normalize_identifier(name);
+        match self.input.context {
+            Some(_) if !self.input.has_field(name) => format!("__askama_ctx.{name}"),
+            _ => {
+                self.used_fields.insert(name);
+                format!("self.{name}")
+            }
+        }
+    }
+
     fn visit_bool_lit(&mut self, buf: &mut Buffer, s: &str) -> DisplayWrap {
         buf.write(s);
         DisplayWrap::Unwrapped
@@ -2114,11 +3215,30 @@ impl MapChain<'_, &str, LocalMeta> {
             None => name.to_string(),
         })
     }
+}
 
-    fn resolve_or_self(&self, name: &str) -> String {
-        let name = normalize_identifier(name);
-        self.resolve(name).unwrap_or_else(|| format!("self.{name}"))
-    }
+// The variables and methods of `loop` in the body of a `for` loop
+const LOOP_VARIABLES: [&str; 4] = ["index", "index0", "first", "last"];
+const LOOP_METHODS: [&str; 1] = ["cycle"];
+
+// Filters that take an iterator over their input.
+const ITER_FILTERS: [&str; 16] = [
+    "batch", "first", "groupby", "last", "length", "map", "max", "min", "reject", "reverse",
+    "select", "slice", "sort", "sort_by", "sum", "unique",
+];
+
+const INVALID_INCLUDE_PATH: &str = "include path must be a string literal, an array of \
+    string literals or an index into an array of string literals";
+
+// Collects the template paths from the elements of an array expression used in `include`.
+fn include_candidates<'e>(paths: &[Expr<'e>]) -> Result<Vec<&'e str>, CompileError> {
+    paths
+        .iter()
+        .map(|path| match path {
+            Expr::StrLit(path) => Ok(*path),
+            _ => Err(INVALID_INCLUDE_PATH.into()),
+        })
+        .collect()
 }
 
 fn median(sizes: &mut [usize]) -> usize {
@@ -2210,6 +3330,27 @@ static USE_RAW: [(&str, &str); 47] = [
     ("try", "r#try"),
 ];
 
+// Turns the attribute path of a filter argument like `"author.name"` into the field
+// accesses `.author.name`.
+fn attr_path(path: &str) -> Result<String, CompileError> {
+    let mut attr = String::new();
+    for segment in path.split('.') {
+        let valid = match segment.as_bytes() {
+            [] => false,
+            [b'0'..=b'9', ..] => segment.bytes().all(|b| b.is_ascii_digit()),
+            _ => segment
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_'),
+        };
+        if !valid {
+            return Err(format!("invalid attribute path {path:?}").into());
+        }
+        attr.push('.');
+        attr.push_str(normalize_identifier(segment));
+    }
+    Ok(attr)
+}
+
 fn normalize_identifier(ident: &str) -> &str {
     if let Some(word) = USE_RAW.iter().find(|x| x.0 == ident) {
         word.1
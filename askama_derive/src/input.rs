@@ -1,10 +1,11 @@
-use crate::config::{Config, Syntax};
 use crate::generator::TemplateArgs;
 use crate::CompileError;
 
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use askama_config::config::Config;
+use askama_parser::Syntax;
 use mime::Mime;
 
 pub(crate) struct TemplateInput<'a> {
@@ -17,6 +18,9 @@ pub(crate) struct TemplateInput<'a> {
     pub(crate) ext: Option<String>,
     pub(crate) mime_type: String,
     pub(crate) path: PathBuf,
+    pub(crate) context: Option<syn::Type>,
+    pub(crate) default_context: bool,
+    pub(crate) warn_unused_fields: bool,
 }
 
 impl TemplateInput<'_> {
@@ -34,6 +38,9 @@ impl TemplateInput<'_> {
             escaping,
             ext,
             syntax,
+            context,
+            default_context,
+            warn_unused_fields,
             ..
         } = args;
 
@@ -81,6 +88,16 @@ impl TemplateInput<'_> {
             CompileError::from(format!("no escaper defined for extension '{escaping}'"))
         })?;
 
+        let context = context
+            .map(|ty| {
+                syn::parse_str(&ty)
+                    .map_err(|e| CompileError::from(format!("invalid context type {ty:?}: {e}")))
+            })
+            .transpose()?;
+        if default_context && context.is_none() {
+            return Err("`default_context` requires a `context` type".into());
+        }
+
         let mime_type =
             extension_to_mime_type(ext_default_to_path(ext.as_deref(), &path).unwrap_or("txt"))
                 .to_string();
@@ -95,9 +112,35 @@ impl TemplateInput<'_> {
             ext,
             mime_type,
             path,
+            context,
+            default_context,
+            warn_unused_fields,
         })
     }
 
+    /// The named fields of the template struct
+    pub(crate) fn fields(&self) -> impl Iterator<Item = &syn::Ident> {
+        let fields = match &self.ast.data {
+            syn::Data::Struct(data) => Some(&data.fields),
+            _ => None,
+        };
+        fields
+            .into_iter()
+            .flatten()
+            .filter_map(|f| f.ident.as_ref())
+    }
+
+    /// Whether the template struct has a named field called `name`.
+    pub(crate) fn has_field(&self, name: &str) -> bool {
+        match &self.ast.data {
+            syn::Data::Struct(data) => data
+                .fields
+                .iter()
+                .any(|field| matches!(&field.ident, Some(ident) if ident == name)),
+            _ => false,
+        }
+    }
+
     #[inline]
     pub(crate) fn extension(&self) -> Option<&str> {
         ext_default_to_path(self.ext.as_deref(), &self.path)
@@ -133,6 +176,7 @@ pub(crate) enum Print {
     All,
     Ast,
     Code,
+    Json,
     None,
 }
 
@@ -145,6 +189,7 @@ impl FromStr for Print {
             "all" => All,
             "ast" => Ast,
             "code" => Code,
+            "json" => Json,
             "none" => None,
             v => return Err(format!("invalid value for print option: {v}",).into()),
         })
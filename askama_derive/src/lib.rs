@@ -7,11 +7,9 @@ use std::fmt;
 use proc_macro::TokenStream;
 use proc_macro2::Span;
 
-mod config;
 mod generator;
-mod heritage;
 mod input;
-mod parser;
+mod suggest;
 
 #[proc_macro_derive(Template, attributes(template))]
 pub fn derive_template(input: TokenStream) -> TokenStream {
@@ -62,38 +60,130 @@ impl From<String> for CompileError {
     }
 }
 
+impl From<askama_config::CompileError> for CompileError {
+    #[inline]
+    fn from(e: askama_config::CompileError) -> Self {
+        Self::new(e.to_string(), Span::call_site())
+    }
+}
+
+impl From<askama_parser::ParseError> for CompileError {
+    #[inline]
+    fn from(e: askama_parser::ParseError) -> Self {
+        Self::new(e.to_string(), Span::call_site())
+    }
+}
+
 // This is used by the code generator to decide whether a named filter is part of
 // Askama or should refer to a local `filters` module. It should contain all the
 // filters shipped with Askama, even the optional ones (since optional inclusion
 // in the const vector based on features seems impossible right now).
 const BUILT_IN_FILTERS: &[&str] = &[
     "abs",
+    "batch",
     "capitalize",
     "center",
+    "currency",
+    "default",
     "e",
     "escape",
     "filesizeformat",
+    "first",
     "fmt",
     "format",
+    "groupby",
     "indent",
     "into_f64",
     "into_isize",
     "join",
+    "last",
+    "length",
     "linebreaks",
     "linebreaksbr",
     "paragraphbreaks",
     "lower",
     "lowercase",
+    "map",
+    "max",
+    "min",
+    "numberformat",
+    "percent",
+    "reject",
+    "replace",
+    "reverse",
     "safe",
+    "select",
+    "slice",
+    "sort",
+    "sort_by",
+    "split",
+    "striptags",
+    "sum",
+    "title",
     "trim",
     "truncate",
+    "truncatechars_html",
+    "truncatewords",
+    "unique",
+    "unwrap_or_empty",
     "upper",
     "uppercase",
     "urlencode",
     "urlencode_strict",
+    "urlize",
     "wordcount",
+    "wordwrap",
     // optional features, reserve the names anyway:
+    "date",
+    "datetime",
+    "isoformat",
     "json",
     "markdown",
+    "sanitize",
+    "slugify",
+    "timesince",
+    "timeuntil",
     "yaml",
 ];
+
+// The built-in filters that were added after local filters of the same name could be called
+// without the `filters::` prefix. The generated code makes sure that such a local filter
+// doesn't exist, instead of silently applying the built-in filter in its place.
+const NEW_BUILT_IN_FILTERS: &[&str] = &[
+    "batch",
+    "currency",
+    "date",
+    "datetime",
+    "default",
+    "first",
+    "groupby",
+    "isoformat",
+    "last",
+    "length",
+    "map",
+    "max",
+    "min",
+    "numberformat",
+    "percent",
+    "reject",
+    "replace",
+    "reverse",
+    "sanitize",
+    "select",
+    "slice",
+    "slugify",
+    "sort",
+    "sort_by",
+    "split",
+    "striptags",
+    "sum",
+    "timesince",
+    "timeuntil",
+    "title",
+    "truncatechars_html",
+    "truncatewords",
+    "unique",
+    "unwrap_or_empty",
+    "urlize",
+    "wordwrap",
+];
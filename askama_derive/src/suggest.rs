@@ -0,0 +1,105 @@
+/// Returns the candidate closest to `name` by edit distance, if it's close enough to
+/// likely be a typo of it.
+pub(crate) fn did_you_mean<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max = name.chars().count() / 3;
+    candidates
+        .into_iter()
+        .filter(|c| *c != name)
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.cmp(y)))
+        .map(|(_, c)| c)
+}
+
+/// Returns the candidate that `name` is a single typo of, i.e. that is one insertion,
+/// deletion, substitution or transposition of adjacent characters away from it.
+pub(crate) fn typo_of<'a, I>(name: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    if name.chars().count() < 3 {
+        return None;
+    }
+    candidates
+        .into_iter()
+        .filter(|c| edit_distance(name, c) == 1)
+        .min()
+}
+
+/// Formats the suggestion of `did_you_mean()` to be appended to an error message.
+pub(crate) fn hint<'a, I>(name: &str, candidates: I) -> String
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    match did_you_mean(name, candidates) {
+        Some(c) => format!(", did you mean {c:?}?"),
+        None => String::new(),
+    }
+}
+
+// Levenshtein distance, counting the transposition of two adjacent characters as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut prev2 = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        cur[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cur[j] = cur[j].min(prev2[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("upper", "upper"), 0);
+        assert_eq!(edit_distance("uper", "upper"), 1);
+        assert_eq!(edit_distance("contnet", "content"), 1);
+        assert_eq!(edit_distance("idnex", "index"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        let filters = ["lower", "upper", "trim", "truncate"];
+        assert_eq!(did_you_mean("uper", filters), Some("upper"));
+        assert_eq!(did_you_mean("truncat", filters), Some("truncate"));
+        assert_eq!(did_you_mean("upper", filters), None);
+        assert_eq!(did_you_mean("myfilter", filters), None);
+        assert_eq!(did_you_mean("x", ["e"]), None);
+        assert_eq!(
+            hint("buton", ["button", "link"]),
+            ", did you mean \"button\"?"
+        );
+        assert_eq!(hint("foo", ["button", "link"]), "");
+    }
+
+    #[test]
+    fn test_typo_of() {
+        let filters = ["lower", "upper", "trim", "truncate"];
+        assert_eq!(typo_of("uper", filters), Some("upper"));
+        assert_eq!(typo_of("trmi", filters), Some("trim"));
+        assert_eq!(typo_of("mytrim", filters), None);
+        assert_eq!(typo_of("upper", filters), None);
+        assert_eq!(typo_of("e", ["es"]), None);
+    }
+}
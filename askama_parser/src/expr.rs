@@ -12,8 +12,10 @@ use super::{
     bool_lit, char_lit, identifier, nested_parenthesis, not_ws, num_lit, path, str_lit, ws,
 };
 
+/// An expression, as in `{{ expr }}`
 #[derive(Debug, PartialEq)]
-pub(crate) enum Expr<'a> {
+#[non_exhaustive]
+pub enum Expr<'a> {
     BoolLit(&'a str),
     NumLit(&'a str),
     StrLit(&'a str),
@@ -32,6 +34,7 @@ pub(crate) enum Expr<'a> {
     Call(Box<Expr<'a>>, Vec<Expr<'a>>),
     RustMacro(&'a str, &'a str),
     Try(Box<Expr<'a>>),
+    NamedArgument(&'a str, Box<Expr<'a>>),
 }
 
 impl Expr<'_> {
@@ -45,7 +48,7 @@ impl Expr<'_> {
 
     /// Returns `true` if enough assumptions can be made,
     /// to determine that `self` is copyable.
-    pub(crate) fn is_copyable(&self) -> bool {
+    pub fn is_copyable(&self) -> bool {
         self.is_copyable_within_op(false)
     }
 
@@ -73,7 +76,7 @@ impl Expr<'_> {
     }
 
     /// Returns `true` if this is an `Attr` where the `obj` is `"self"`.
-    pub(crate) fn is_attr_self(&self) -> bool {
+    pub fn is_attr_self(&self) -> bool {
         match self {
             Expr::Attr(obj, _) if matches!(obj.as_ref(), Expr::Var("self")) => true,
             Expr::Attr(obj, _) if matches!(obj.as_ref(), Expr::Attr(..)) => obj.is_attr_self(),
@@ -84,7 +87,7 @@ impl Expr<'_> {
     /// Returns `true` if the outcome of this expression may be used multiple times in the same
     /// `write!()` call, without evaluating the expression again, i.e. the expression should be
     /// side-effect free.
-    pub(crate) fn is_cacheable(&self) -> bool {
+    pub fn is_cacheable(&self) -> bool {
         match self {
             // Literals are the definition of pure:
             Expr::BoolLit(_) => true,
@@ -107,6 +110,7 @@ impl Expr<'_> {
             }
             Expr::Group(arg) => arg.is_cacheable(),
             Expr::Tuple(args) => args.iter().all(|arg| arg.is_cacheable()),
+            Expr::NamedArgument(_, arg) => arg.is_cacheable(),
             // We have too little information to tell if the expression is pure:
             Expr::Call(_, _) => false,
             Expr::RustMacro(_, _) => false,
@@ -222,8 +226,11 @@ fn expr_try(i: &str) -> IResult<&str, Suffix<'_>> {
     map(preceded(take_till(not_ws), char('?')), |_| Suffix::Try)(i)
 }
 
+// The name of a filter of the local `filters` module may be written as `filters::name`, to
+// call it instead of a built-in filter with the same name.
 fn filter(i: &str) -> IResult<&str, (&str, Option<Vec<Expr<'_>>>)> {
-    let (i, (_, fname, args)) = tuple((char('|'), ws(identifier), opt(arguments)))(i)?;
+    let fname = recognize(pair(opt(tag("filters::")), identifier));
+    let (i, (_, fname, args)) = tuple((char('|'), ws(fname), opt(arguments)))(i)?;
     Ok((i, (fname, args)))
 }
 
@@ -340,7 +347,12 @@ fn expr_any(i: &str) -> IResult<&str, Expr<'_>> {
 fn arguments(i: &str) -> IResult<&str, Vec<Expr<'_>>> {
     delimited(
         ws(char('(')),
-        separated_list0(char(','), ws(expr_any)),
+        separated_list0(char(','), ws(alt((named_argument, expr_any)))),
         ws(char(')')),
     )(i)
 }
+
+fn named_argument(i: &str) -> IResult<&str, Expr<'_>> {
+    let (i, (name, _, _, value)) = tuple((identifier, ws(char('=')), not(char('=')), expr_any))(i)?;
+    Ok((i, Expr::NamedArgument(name, Box::new(value))))
+}
@@ -0,0 +1,501 @@
+//! Serialization of the syntax tree as JSON
+//!
+//! This is the output of the `print = "json"` option of Askama's `template()`
+//! attribute. The schema is documented in the book's debugging chapter and must
+//! only be extended in a backwards compatible way, unless the version is
+//! incremented.
+
+use std::fmt::Write;
+
+use super::{Expr, Node, Span, Target, Whitespace, WithSpan, Ws};
+
+/// The version of the JSON schema
+pub const VERSION: u32 = 1;
+
+/// Returns `nodes` as a JSON array; offsets are computed relative to `source`,
+/// which the nodes were parsed from.
+pub fn nodes_to_json(source: &str, nodes: &[WithSpan<Node<'_>>]) -> String {
+    let mut json = Json {
+        source,
+        out: String::new(),
+    };
+    json.nodes(nodes);
+    json.out
+}
+
+struct Json<'s> {
+    source: &'s str,
+    out: String,
+}
+
+impl Json<'_> {
+    fn nodes(&mut self, nodes: &[WithSpan<Node<'_>>]) {
+        self.list(nodes, |json, node| json.node(node, node.span()));
+    }
+
+    fn node(&mut self, node: &Node<'_>, span: Span) {
+        match node {
+            Node::Lit(lws, val, rws) => {
+                self.begin("lit");
+                self.offset(lws);
+                self.str("lws", lws);
+                self.str("val", val);
+                self.str("rws", rws);
+            }
+            Node::Comment(ws, text) => {
+                self.begin("comment");
+                self.ws("ws", *ws);
+                self.offset(text);
+                self.str("text", text);
+            }
+            Node::Expr(ws, expr) => {
+                self.begin("expr");
+                self.ws("ws", *ws);
+                self.key("expr");
+                self.expr(expr);
+            }
+            Node::Call(ws, scope, name, args) => {
+                self.begin("call");
+                self.ws("ws", *ws);
+                self.key("scope");
+                match scope {
+                    Some(scope) => self.string(scope),
+                    None => self.out.push_str("null"),
+                }
+                self.offset(name);
+                self.str("name", name);
+                self.exprs("args", args);
+            }
+            Node::LetDecl(ws, target) => {
+                self.begin("let_decl");
+                self.ws("ws", *ws);
+                self.key("target");
+                self.target(target);
+            }
+            Node::Let(ws, target, expr) => {
+                self.begin("let");
+                self.ws("ws", *ws);
+                self.key("target");
+                self.target(target);
+                self.key("expr");
+                self.expr(expr);
+            }
+            Node::Cond(conds, ws) => {
+                self.begin("if");
+                self.key("branches");
+                self.list(conds, |json, (ws, test, nodes)| {
+                    json.out.push('{');
+                    json.ws("ws", *ws);
+                    json.key("target");
+                    match test.as_ref().and_then(|test| test.target.as_ref()) {
+                        Some(target) => json.target(target),
+                        None => json.out.push_str("null"),
+                    }
+                    json.key("cond");
+                    match test {
+                        Some(test) => json.expr(&test.expr),
+                        None => json.out.push_str("null"),
+                    }
+                    json.key("nodes");
+                    json.nodes(nodes);
+                    json.out.push('}');
+                });
+                self.ws("ws", *ws);
+            }
+            Node::Match(ws1, expr, prefix, arms, ws2) => {
+                self.begin("match");
+                self.ws("ws1", *ws1);
+                self.key("expr");
+                self.expr(expr);
+                self.key("prefix");
+                self.nodes(prefix);
+                self.key("arms");
+                self.list(arms, |json, (ws, target, nodes)| {
+                    json.out.push('{');
+                    json.ws("ws", *ws);
+                    json.key("target");
+                    json.target(target);
+                    json.key("nodes");
+                    json.nodes(nodes);
+                    json.out.push('}');
+                });
+                self.ws("ws2", *ws2);
+            }
+            Node::Loop(l) => {
+                self.begin("for");
+                self.ws("ws1", l.ws1);
+                self.key("var");
+                self.target(&l.var);
+                self.key("iter");
+                self.expr(&l.iter);
+                self.key("cond");
+                match &l.cond {
+                    Some(cond) => self.expr(cond),
+                    None => self.out.push_str("null"),
+                }
+                self.key("body");
+                self.nodes(&l.body);
+                self.ws("ws2", l.ws2);
+                self.key("else");
+                self.nodes(&l.else_block);
+                self.ws("ws3", l.ws3);
+            }
+            Node::Extends(path) => {
+                self.begin("extends");
+                self.offset(path);
+                self.str("path", path);
+            }
+            Node::BlockDef(def) => {
+                self.begin("block");
+                self.ws("ws1", def.ws1);
+                self.offset(def.name);
+                self.str("name", def.name);
+                self.bool("required", def.required);
+                self.key("nodes");
+                self.nodes(&def.nodes);
+                self.ws("ws2", def.ws2);
+            }
+            Node::Include(ws, path, ignore_missing) => {
+                self.begin("include");
+                self.ws("ws", *ws);
+                self.key("path");
+                self.expr(path);
+                self.bool("ignore_missing", *ignore_missing);
+            }
+            Node::Embed(ws1, path, nodes, ws2) => {
+                self.begin("embed");
+                self.ws("ws1", *ws1);
+                self.offset(path);
+                self.str("path", path);
+                self.key("nodes");
+                self.nodes(nodes);
+                self.ws("ws2", *ws2);
+            }
+            Node::Import(ws, path, scope) => {
+                self.begin("import");
+                self.ws("ws", *ws);
+                self.offset(path);
+                self.str("path", path);
+                self.str("scope", scope);
+            }
+            Node::FromImport(ws, path, names) => {
+                self.begin("from_import");
+                self.ws("ws", *ws);
+                self.offset(path);
+                self.str("path", path);
+                self.key("names");
+                self.list(names, |json, (name, alias)| {
+                    json.out.push('{');
+                    json.str("name", name);
+                    json.str("alias", alias);
+                    json.out.push('}');
+                });
+            }
+            Node::Macro(name, m) => {
+                self.begin("macro");
+                self.ws("ws1", m.ws1);
+                self.offset(name);
+                self.str("name", name);
+                self.key("args");
+                self.list(&m.args, |json, arg| json.string(arg));
+                self.key("nodes");
+                self.nodes(&m.nodes);
+                self.ws("ws2", m.ws2);
+            }
+            Node::Trans(trans) => {
+                self.begin("trans");
+                self.ws("ws1", trans.ws1);
+                self.key("vars");
+                self.list(&trans.vars, |json, (name, expr)| {
+                    json.out.push('{');
+                    json.str("name", name);
+                    json.key("expr");
+                    json.expr(expr);
+                    json.out.push('}');
+                });
+                self.key("singular");
+                self.nodes(&trans.singular);
+                self.key("plural");
+                match &trans.plural {
+                    Some((ws, nodes)) => {
+                        self.out.push('{');
+                        self.ws("ws", *ws);
+                        self.key("nodes");
+                        self.nodes(nodes);
+                        self.out.push('}');
+                    }
+                    None => self.out.push_str("null"),
+                }
+                self.ws("ws2", trans.ws2);
+            }
+            Node::Raw(ws1, lws, val, rws, ws2) => {
+                self.begin("raw");
+                self.ws("ws1", *ws1);
+                self.offset(lws);
+                self.str("lws", lws);
+                self.str("val", val);
+                self.str("rws", rws);
+                self.ws("ws2", *ws2);
+            }
+            Node::Break(ws) => {
+                self.begin("break");
+                self.ws("ws", *ws);
+            }
+            Node::Continue(ws) => {
+                self.begin("continue");
+                self.ws("ws", *ws);
+            }
+        }
+        self.key("span");
+        let _ = write!(self.out, "[{},{}]", span.start, span.end);
+        self.out.push('}');
+    }
+
+    fn expr(&mut self, expr: &Expr<'_>) {
+        match expr {
+            Expr::BoolLit(s) | Expr::NumLit(s) | Expr::StrLit(s) | Expr::CharLit(s) => {
+                self.begin(match expr {
+                    Expr::BoolLit(_) => "bool",
+                    Expr::NumLit(_) => "num",
+                    Expr::StrLit(_) => "str",
+                    _ => "char",
+                });
+                self.offset(s);
+                self.str("value", s);
+            }
+            Expr::Var(name) => {
+                self.begin("var");
+                self.offset(name);
+                self.str("name", name);
+            }
+            Expr::Path(path) => {
+                self.begin("path");
+                if let Some(first) = path.first() {
+                    self.offset(first);
+                }
+                self.key("path");
+                self.list(path, |json, s| json.string(s));
+            }
+            Expr::Array(items) => {
+                self.begin("array");
+                self.exprs("items", items);
+            }
+            Expr::Attr(obj, attr) => {
+                self.begin("attr");
+                self.key("expr");
+                self.expr(obj);
+                self.offset(attr);
+                self.str("attr", attr);
+            }
+            Expr::Index(obj, key) => {
+                self.begin("index");
+                self.key("expr");
+                self.expr(obj);
+                self.key("index");
+                self.expr(key);
+            }
+            Expr::Filter(name, args) => {
+                self.begin("filter");
+                self.offset(name);
+                self.str("name", name);
+                self.exprs("args", args);
+            }
+            Expr::Unary(op, expr) => {
+                self.begin("unary");
+                self.offset(op);
+                self.str("op", op);
+                self.key("expr");
+                self.expr(expr);
+            }
+            Expr::BinOp(op, lhs, rhs) => {
+                self.begin("binop");
+                self.offset(op);
+                self.str("op", op);
+                self.key("lhs");
+                self.expr(lhs);
+                self.key("rhs");
+                self.expr(rhs);
+            }
+            Expr::Range(op, lhs, rhs) => {
+                self.begin("range");
+                self.offset(op);
+                self.str("op", op);
+                for (key, expr) in [("lhs", lhs), ("rhs", rhs)] {
+                    self.key(key);
+                    match expr {
+                        Some(expr) => self.expr(expr),
+                        None => self.out.push_str("null"),
+                    }
+                }
+            }
+            Expr::Group(expr) => {
+                self.begin("group");
+                self.key("expr");
+                self.expr(expr);
+            }
+            Expr::Tuple(items) => {
+                self.begin("tuple");
+                self.exprs("items", items);
+            }
+            Expr::Call(obj, args) => {
+                self.begin("call");
+                self.key("expr");
+                self.expr(obj);
+                self.exprs("args", args);
+            }
+            Expr::RustMacro(name, args) => {
+                self.begin("macro");
+                self.offset(name);
+                self.str("name", name);
+                self.str("args", args);
+            }
+            Expr::Try(expr) => {
+                self.begin("try");
+                self.key("expr");
+                self.expr(expr);
+            }
+            Expr::NamedArgument(name, expr) => {
+                self.begin("named_argument");
+                self.offset(name);
+                self.str("name", name);
+                self.key("expr");
+                self.expr(expr);
+            }
+        }
+        self.out.push('}');
+    }
+
+    fn target(&mut self, target: &Target<'_>) {
+        match target {
+            Target::Name(name) => {
+                self.begin("name");
+                self.offset(name);
+                self.str("name", name);
+            }
+            Target::Tuple(path, targets) => {
+                self.begin("tuple");
+                self.key("path");
+                self.list(path, |json, s| json.string(s));
+                self.key("items");
+                self.list(targets, Self::target);
+            }
+            Target::Struct(path, fields) => {
+                self.begin("struct");
+                self.key("path");
+                self.list(path, |json, s| json.string(s));
+                self.key("fields");
+                self.list(fields, |json, (name, target)| {
+                    json.out.push('{');
+                    json.str("name", name);
+                    json.key("target");
+                    json.target(target);
+                    json.out.push('}');
+                });
+            }
+            Target::NumLit(s) | Target::StrLit(s) | Target::CharLit(s) | Target::BoolLit(s) => {
+                self.begin(match target {
+                    Target::NumLit(_) => "num",
+                    Target::StrLit(_) => "str",
+                    Target::CharLit(_) => "char",
+                    _ => "bool",
+                });
+                self.offset(s);
+                self.str("value", s);
+            }
+            Target::Path(path) => {
+                self.begin("path");
+                if let Some(first) = path.first() {
+                    self.offset(first);
+                }
+                self.key("path");
+                self.list(path, |json, s| json.string(s));
+            }
+        }
+        self.out.push('}');
+    }
+
+    fn exprs(&mut self, key: &str, exprs: &[Expr<'_>]) {
+        self.key(key);
+        self.list(exprs, Self::expr);
+    }
+
+    fn list<T>(&mut self, items: &[T], mut f: impl FnMut(&mut Self, &T)) {
+        self.out.push('[');
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            f(self, item);
+        }
+        self.out.push(']');
+    }
+
+    // Starts an object with its `type`, which is closed by the caller.
+    fn begin(&mut self, ty: &str) {
+        self.out.push('{');
+        self.str("type", ty);
+    }
+
+    fn key(&mut self, key: &str) {
+        if !self.out.ends_with('{') {
+            self.out.push(',');
+        }
+        self.string(key);
+        self.out.push(':');
+    }
+
+    fn str(&mut self, key: &str, value: &str) {
+        self.key(key);
+        self.string(value);
+    }
+
+    fn bool(&mut self, key: &str, value: bool) {
+        self.key(key);
+        self.out.push_str(if value { "true" } else { "false" });
+    }
+
+    fn ws(&mut self, key: &str, ws: Ws) {
+        self.key(key);
+        self.out.push('[');
+        for (i, ws) in [ws.0, ws.1].into_iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.out.push_str(match ws {
+                Some(Whitespace::Preserve) => "\"+\"",
+                Some(Whitespace::Suppress) => "\"-\"",
+                Some(Whitespace::Minimize) => "\"~\"",
+                None => "null",
+            });
+        }
+        self.out.push(']');
+    }
+
+    // The byte offset of `s` in the source, if it is a slice of the source.
+    fn offset(&mut self, s: &str) {
+        let start = self.source.as_ptr() as usize;
+        let ptr = s.as_ptr() as usize;
+        if ptr >= start && ptr + s.len() <= start + self.source.len() {
+            self.key("offset");
+            let _ = write!(self.out, "{}", ptr - start);
+        }
+    }
+
+    fn string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                c if (c as u32) < 0x20 => {
+                    let _ = write!(self.out, "\\u{:04x}", c as u32);
+                }
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+}
@@ -1,5 +1,24 @@
+//! The template parser of the [Askama](https://github.com/djc/askama)
+//! templating engine
+//!
+//! [`parse()`] turns the source of a template into a list of [`Node`]s. The
+//! nodes borrow names, literals and text from the source, and each of them
+//! knows its location in the source as a [`Span`].
+//!
+//! ```
+//! use askama_parser::{parse, Expr, Node, Span, Syntax, Ws};
+//!
+//! let nodes = parse("Hello, {{ name }}!", &Syntax::default()).unwrap();
+//! assert_eq!(nodes[1], Node::Expr(Ws(None, None), Expr::Var("name")));
+//! assert_eq!(nodes[1].span(), Span { start: 7, end: 17 });
+//! ```
+
+#![deny(elided_lifetimes_in_paths)]
+#![deny(unreachable_pub)]
+
 use std::cell::Cell;
-use std::str;
+use std::ops::Deref;
+use std::{fmt, str};
 
 use nom::branch::alt;
 use nom::bytes::complete::{escaped, is_not, tag, take_till};
@@ -11,26 +30,208 @@ use nom::multi::separated_list1;
 use nom::sequence::{delimited, pair, tuple};
 use nom::{error_position, AsChar, IResult, InputTakeAtPosition};
 
-pub(crate) use self::expr::Expr;
-pub(crate) use self::node::{Cond, CondTest, Loop, Macro, Node, Target, When, Whitespace, Ws};
-use crate::config::Syntax;
-use crate::CompileError;
+pub use self::expr::Expr;
+pub use self::node::{
+    message_id, BlockDef, Cond, CondTest, Loop, Macro, Node, Target, Trans, When, Whitespace, Ws,
+};
 
 mod expr;
+pub mod json;
 mod node;
 #[cfg(test)]
 mod tests;
 
+/// The delimiters of the tags of a template
+#[derive(Debug)]
+pub struct Syntax<'a> {
+    pub block_start: &'a str,
+    pub block_end: &'a str,
+    pub expr_start: &'a str,
+    pub expr_end: &'a str,
+    pub comment_start: &'a str,
+    pub comment_end: &'a str,
+}
+
+impl Syntax<'_> {
+    /// Checks that the delimiters can be told apart by the parser
+    pub fn validate(&self) -> Result<(), ParseError> {
+        for s in [
+            self.block_start,
+            self.block_end,
+            self.expr_start,
+            self.expr_end,
+            self.comment_start,
+            self.comment_end,
+        ] {
+            if s.len() < 2 {
+                return Err(
+                    format!("delimiters must be at least two characters long: {s:?}").into(),
+                );
+            } else if s.chars().any(|c| c.is_whitespace()) {
+                return Err(format!("delimiters may not contain white spaces: {s:?}").into());
+            }
+        }
+
+        for (s1, s2) in [
+            (self.block_start, self.expr_start),
+            (self.block_start, self.comment_start),
+            (self.expr_start, self.comment_start),
+        ] {
+            if s1.starts_with(s2) || s2.starts_with(s1) {
+                return Err(format!(
+                    "a delimiter may not be the prefix of another delimiter: {s1:?} vs {s2:?}",
+                )
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Syntax<'static> {
+    fn default() -> Self {
+        Self {
+            block_start: "{%",
+            block_end: "%}",
+            expr_start: "{{",
+            expr_end: "}}",
+            comment_start: "{#",
+            comment_end: "#}",
+        }
+    }
+}
+
+/// A range of bytes in the source of a template
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A node of the syntax tree with its location in the source
+///
+/// It dereferences to the node. Comparisons ignore the span, so that nodes can
+/// be compared with nodes parsed from a different source.
+#[derive(Clone, Debug)]
+pub struct WithSpan<T> {
+    inner: T,
+    span: Span,
+}
+
+impl<T> WithSpan<T> {
+    pub fn new(inner: T, span: Span) -> Self {
+        Self { inner, span }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T> Deref for WithSpan<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+/// Wraps a node that wasn't parsed, with an empty span
+impl<T> From<T> for WithSpan<T> {
+    fn from(inner: T) -> Self {
+        Self::new(inner, Span::default())
+    }
+}
+
+impl<T: PartialEq> PartialEq for WithSpan<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: PartialEq> PartialEq<T> for WithSpan<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.inner == *other
+    }
+}
+
+/// An error in the source of a template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    offset: Option<usize>,
+}
+
+impl ParseError {
+    /// The description of the error
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The byte offset in the source where the error was found, if known
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<&'static str> for ParseError {
+    fn from(s: &'static str) -> Self {
+        s.to_owned().into()
+    }
+}
+
+impl From<String> for ParseError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            offset: None,
+        }
+    }
+}
+
 struct State<'a> {
     syntax: &'a Syntax<'a>,
     loop_depth: Cell<usize>,
+    /// The length of the source, as the offset of the remaining input is the
+    /// difference to its length
+    source_len: usize,
 }
 
 impl<'a> State<'a> {
-    fn new(syntax: &'a Syntax<'a>) -> State<'a> {
+    fn new(syntax: &'a Syntax<'a>, source_len: usize) -> State<'a> {
         State {
             syntax,
             loop_depth: Cell::new(0),
+            source_len,
+        }
+    }
+
+    /// Adds the span of the input consumed by `inner` to its result
+    fn spanned<'i, O>(
+        &self,
+        mut inner: impl FnMut(&'i str) -> IResult<&'i str, O>,
+    ) -> impl FnMut(&'i str) -> IResult<&'i str, WithSpan<O>> {
+        let source_len = self.source_len;
+        move |i| {
+            let (j, o) = inner(i)?;
+            let span = Span {
+                start: source_len - i.len(),
+                end: source_len - j.len(),
+            };
+            Ok((j, WithSpan::new(o, span)))
         }
     }
 
@@ -58,11 +259,15 @@ impl From<char> for Whitespace {
     }
 }
 
-pub(crate) fn parse<'a>(src: &'a str, syntax: &Syntax<'_>) -> Result<Vec<Node<'a>>, CompileError> {
-    match Node::parse(src, &State::new(syntax)) {
+/// Parses the template `src`, whose tags are delimited as in `syntax`
+pub fn parse<'a>(src: &'a str, syntax: &Syntax<'_>) -> Result<Vec<WithSpan<Node<'a>>>, ParseError> {
+    match Node::parse(src, &State::new(syntax, src.len())) {
         Ok((left, res)) => {
             if !left.is_empty() {
-                Err(format!("unable to parse template:\n\n{left:?}").into())
+                Err(ParseError {
+                    message: format!("unable to parse template:\n\n{left:?}"),
+                    offset: Some(src.len() - left.len()),
+                })
             } else {
                 Ok(res)
             }
@@ -81,13 +286,16 @@ pub(crate) fn parse<'a>(src: &'a str, syntax: &Syntax<'_>) -> Result<Vec<Node<'a
             let (row, last_line) = source_before.lines().enumerate().last().unwrap();
             let column = last_line.chars().count();
 
-            let msg = format!(
+            let message = format!(
                 "problems parsing template source at row {}, column {} near:\n{}",
                 row + 1,
                 column,
                 source_after,
             );
-            Err(msg.into())
+            Err(ParseError {
+                message,
+                offset: Some(offset),
+            })
         }
 
         Err(nom::Err::Incomplete(_)) => Err("parsing incomplete".into()),
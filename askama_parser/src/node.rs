@@ -1,44 +1,49 @@
 use std::str;
 
 use nom::branch::alt;
-use nom::bytes::complete::{tag, take_until};
+use nom::bytes::complete::{tag, take_until, take_while1};
 use nom::character::complete::char;
-use nom::combinator::{complete, consumed, cut, map, opt, peek, value};
+use nom::combinator::{complete, consumed, cut, map, opt, peek};
 use nom::error::{Error, ErrorKind};
 use nom::multi::{fold_many0, many0, many1, separated_list0, separated_list1};
 use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use nom::{error_position, IResult};
 
 use super::{
-    bool_lit, char_lit, identifier, keyword, num_lit, path, skip_till, split_ws_parts, str_lit,
-    tag_block_end, tag_block_start, tag_comment_end, tag_comment_start, tag_expr_end,
-    tag_expr_start, take_content, ws, Expr, State,
+    bool_lit, char_lit, identifier, is_ws, keyword, num_lit, path, skip_till, split_ws_parts,
+    str_lit, tag_block_end, tag_block_start, tag_comment_end, tag_comment_start, tag_expr_end,
+    tag_expr_start, take_content, ws, Expr, ParseError, State, WithSpan,
 };
-use crate::config::WhitespaceHandling;
 
+/// A part of a template: text, a comment, an expression or a tag
 #[derive(Debug, PartialEq)]
-pub(crate) enum Node<'a> {
+#[non_exhaustive]
+pub enum Node<'a> {
     Lit(&'a str, &'a str, &'a str),
-    Comment(Ws),
+    Comment(Ws, &'a str),
     Expr(Ws, Expr<'a>),
     Call(Ws, Option<&'a str>, &'a str, Vec<Expr<'a>>),
     LetDecl(Ws, Target<'a>),
     Let(Ws, Target<'a>, Expr<'a>),
     Cond(Vec<Cond<'a>>, Ws),
-    Match(Ws, Expr<'a>, Vec<When<'a>>, Ws),
+    Match(Ws, Expr<'a>, Vec<WithSpan<Node<'a>>>, Vec<When<'a>>, Ws),
     Loop(Loop<'a>),
     Extends(&'a str),
-    BlockDef(Ws, &'a str, Vec<Node<'a>>, Ws),
-    Include(Ws, &'a str),
+    BlockDef(BlockDef<'a>),
+    Include(Ws, Expr<'a>, bool),
+    Embed(Ws, &'a str, Vec<WithSpan<Node<'a>>>, Ws),
     Import(Ws, &'a str, &'a str),
+    FromImport(Ws, &'a str, Vec<(&'a str, &'a str)>),
     Macro(&'a str, Macro<'a>),
+    Trans(Trans<'a>),
     Raw(Ws, &'a str, &'a str, &'a str, Ws),
     Break(Ws),
     Continue(Ws),
 }
 
+/// A pattern, as in `let` and `for` tags and `when` blocks
 #[derive(Debug, PartialEq)]
-pub(crate) enum Target<'a> {
+pub enum Target<'a> {
     Name(&'a str),
     Tuple(Vec<&'a str>, Vec<Target<'a>>),
     Struct(Vec<&'a str>, Vec<(&'a str, Target<'a>)>),
@@ -49,61 +54,76 @@ pub(crate) enum Target<'a> {
     Path(Vec<&'a str>),
 }
 
+/// The whitespace handling requested by a `+`, `-` or `~` character in a tag
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub(crate) enum Whitespace {
+pub enum Whitespace {
     Preserve,
     Suppress,
     Minimize,
 }
 
-impl From<WhitespaceHandling> for Whitespace {
-    fn from(ws: WhitespaceHandling) -> Self {
-        match ws {
-            WhitespaceHandling::Suppress => Whitespace::Suppress,
-            WhitespaceHandling::Preserve => Whitespace::Preserve,
-            WhitespaceHandling::Minimize => Whitespace::Minimize,
-        }
-    }
+#[derive(Debug, PartialEq)]
+pub struct Loop<'a> {
+    pub ws1: Ws,
+    pub var: Target<'a>,
+    pub iter: Expr<'a>,
+    pub cond: Option<Expr<'a>>,
+    pub body: Vec<WithSpan<Node<'a>>>,
+    pub ws2: Ws,
+    pub else_block: Vec<WithSpan<Node<'a>>>,
+    pub ws3: Ws,
 }
 
 #[derive(Debug, PartialEq)]
-pub(crate) struct Loop<'a> {
-    pub(crate) ws1: Ws,
-    pub(crate) var: Target<'a>,
-    pub(crate) iter: Expr<'a>,
-    pub(crate) cond: Option<Expr<'a>>,
-    pub(crate) body: Vec<Node<'a>>,
-    pub(crate) ws2: Ws,
-    pub(crate) else_block: Vec<Node<'a>>,
-    pub(crate) ws3: Ws,
+pub struct BlockDef<'a> {
+    pub ws1: Ws,
+    pub name: &'a str,
+    /// The block must be overridden by a descendant template
+    pub required: bool,
+    pub nodes: Vec<WithSpan<Node<'a>>>,
+    pub ws2: Ws,
 }
 
-pub(crate) type When<'a> = (Ws, Target<'a>, Vec<Node<'a>>);
+pub type When<'a> = (Ws, Target<'a>, Vec<WithSpan<Node<'a>>>);
 
 #[derive(Debug, PartialEq)]
-pub(crate) struct Macro<'a> {
-    pub(crate) ws1: Ws,
-    pub(crate) args: Vec<&'a str>,
-    pub(crate) nodes: Vec<Node<'a>>,
-    pub(crate) ws2: Ws,
+pub struct Trans<'a> {
+    pub ws1: Ws,
+    /// Variables bound for the message, as in `{% trans name = user.name %}`
+    pub vars: Vec<(&'a str, Expr<'a>)>,
+    pub singular: Vec<WithSpan<Node<'a>>>,
+    /// The whitespace handling of the `pluralize` tag and the plural form
+    pub plural: Option<(Ws, Vec<WithSpan<Node<'a>>>)>,
+    pub ws2: Ws,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Macro<'a> {
+    pub ws1: Ws,
+    pub args: Vec<&'a str>,
+    pub nodes: Vec<WithSpan<Node<'a>>>,
+    pub ws2: Ws,
 }
 
 /// First field is "minus/plus sign was used on the left part of the item".
 ///
 /// Second field is "minus/plus sign was used on the right part of the item".
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub(crate) struct Ws(pub(crate) Option<Whitespace>, pub(crate) Option<Whitespace>);
+pub struct Ws(pub Option<Whitespace>, pub Option<Whitespace>);
 
-pub(crate) type Cond<'a> = (Ws, Option<CondTest<'a>>, Vec<Node<'a>>);
+pub type Cond<'a> = (Ws, Option<CondTest<'a>>, Vec<WithSpan<Node<'a>>>);
 
 #[derive(Debug, PartialEq)]
-pub(crate) struct CondTest<'a> {
-    pub(crate) target: Option<Target<'a>>,
-    pub(crate) expr: Expr<'a>,
+pub struct CondTest<'a> {
+    pub target: Option<Target<'a>>,
+    pub expr: Expr<'a>,
 }
 
 impl Node<'_> {
-    pub(super) fn parse<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Vec<Node<'a>>> {
+    pub(super) fn parse<'a>(
+        i: &'a str,
+        s: &State<'_>,
+    ) -> IResult<&'a str, Vec<WithSpan<Node<'a>>>> {
         parse_template(i, s)
     }
 }
@@ -241,7 +261,11 @@ fn block_match<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
             opt(expr_handle_ws),
             |i| tag_block_end(i, s),
             cut(tuple((
-                ws(many0(ws(value((), |i| block_comment(i, s))))),
+                many0(
+                    s.spanned(alt((map(take_while1(is_ws), split_ws_parts), |i| {
+                        block_comment(i, s)
+                    }))),
+                ),
                 many1(|i| when_block(i, s)),
                 cut(tuple((
                     opt(|i| match_else_block(i, s)),
@@ -255,14 +279,17 @@ fn block_match<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
             ))),
         ))),
     ));
-    let (i, (pws1, _, (expr, nws1, _, (_, arms, (else_arm, (_, pws2, _, nws2)))))) = p(i)?;
+    let (i, (pws1, _, (expr, nws1, _, (prefix, arms, (else_arm, (_, pws2, _, nws2)))))) = p(i)?;
 
     let mut arms = arms;
     if let Some(arm) = else_arm {
         arms.push(arm);
     }
 
-    Ok((i, Node::Match(Ws(pws1, nws1), expr, arms, Ws(pws2, nws2))))
+    Ok((
+        i,
+        Node::Match(Ws(pws1, nws1), expr, prefix, arms, Ws(pws2, nws2)),
+    ))
 }
 
 fn block_let(i: &str) -> IResult<&str, Node<'_>> {
@@ -287,7 +314,7 @@ fn block_let(i: &str) -> IResult<&str, Node<'_>> {
     ))
 }
 
-fn parse_loop_content<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Vec<Node<'a>>> {
+fn parse_loop_content<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Vec<WithSpan<Node<'a>>>> {
     s.enter_loop();
     let result = parse_template(i, s);
     s.leave_loop();
@@ -363,11 +390,14 @@ fn block_block<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
     let mut start = tuple((
         opt(expr_handle_ws),
         ws(keyword("block")),
-        cut(tuple((ws(identifier), opt(expr_handle_ws), |i| {
-            tag_block_end(i, s)
-        }))),
+        cut(tuple((
+            ws(identifier),
+            opt(ws(keyword("required"))),
+            opt(expr_handle_ws),
+            |i| tag_block_end(i, s),
+        ))),
     ));
-    let (i, (pws1, _, (name, nws1, _))) = start(i)?;
+    let (i, (pws1, _, (name, required, nws1, _))) = start(i)?;
 
     let mut end = cut(tuple((
         |i| parse_template(i, s),
@@ -382,7 +412,13 @@ fn block_block<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
 
     Ok((
         i,
-        Node::BlockDef(Ws(pws1, nws1), name, contents, Ws(pws2, nws2)),
+        Node::BlockDef(BlockDef {
+            ws1: Ws(pws1, nws1),
+            name,
+            required: required.is_some(),
+            nodes: contents,
+            ws2: Ws(pws2, nws2),
+        }),
     ))
 }
 
@@ -390,10 +426,158 @@ fn block_include(i: &str) -> IResult<&str, Node<'_>> {
     let mut p = tuple((
         opt(expr_handle_ws),
         ws(keyword("include")),
-        cut(pair(ws(str_lit), opt(expr_handle_ws))),
+        cut(tuple((
+            ws(Expr::parse),
+            opt(pair(ws(keyword("ignore")), ws(keyword("missing")))),
+            opt(expr_handle_ws),
+        ))),
+    ));
+    let (i, (pws, _, (path, ignore_missing, nws))) = p(i)?;
+    Ok((
+        i,
+        Node::Include(Ws(pws, nws), path, ignore_missing.is_some()),
+    ))
+}
+
+fn block_embed<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
+    let mut start = tuple((
+        opt(expr_handle_ws),
+        ws(keyword("embed")),
+        cut(tuple((ws(str_lit), opt(expr_handle_ws), |i| {
+            tag_block_end(i, s)
+        }))),
     ));
-    let (i, (pws, _, (name, nws))) = p(i)?;
-    Ok((i, Node::Include(Ws(pws, nws), name)))
+    let (i, (pws1, _, (path, nws1, _))) = start(i)?;
+
+    let mut end = cut(tuple((
+        |i| parse_template(i, s),
+        cut(tuple((
+            |i| tag_block_start(i, s),
+            opt(expr_handle_ws),
+            ws(keyword("endembed")),
+            opt(expr_handle_ws),
+        ))),
+    )));
+    let (i, (contents, (_, pws2, _, nws2))) = end(i)?;
+
+    Ok((
+        i,
+        Node::Embed(Ws(pws1, nws1), path, contents, Ws(pws2, nws2)),
+    ))
+}
+
+impl<'a> Trans<'a> {
+    /// Returns the text of the message and of its plural form, in which variables are written
+    /// as `{name}`, and the names of the variables.
+    pub fn message(&self) -> Result<(String, Option<String>, Vec<&'a str>), ParseError> {
+        let mut names = Vec::new();
+        let singular = message_text(&self.singular, &mut names)?;
+        let plural = match self.plural {
+            Some((_, ref nodes)) => Some(message_text(nodes, &mut names)?),
+            None => None,
+        };
+        Ok((singular, plural, names))
+    }
+}
+
+/// Derives a Fluent message id from the text of a `trans` block
+///
+/// The id is made of the lowercase ASCII letters and digits of the text, where other characters
+/// become dashes, so `Hello, {name}!` becomes `hello-name`. Ids start with a letter, as
+/// required by Fluent.
+pub fn message_id(text: &str) -> String {
+    let mut id = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            id.push(c.to_ascii_lowercase());
+        } else if !id.is_empty() && !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    if id.ends_with('-') {
+        id.pop();
+    }
+    if id.is_empty() {
+        id.push_str("msg");
+    } else if !id.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        id.insert_str(0, "msg-");
+    }
+    id
+}
+
+fn message_text<'a>(
+    nodes: &[WithSpan<Node<'a>>],
+    names: &mut Vec<&'a str>,
+) -> Result<String, ParseError> {
+    let mut text = String::new();
+    for node in nodes {
+        match **node {
+            Node::Lit(lws, val, rws) => {
+                text.push_str(lws);
+                text.push_str(val);
+                text.push_str(rws);
+            }
+            Node::Expr(_, Expr::Var(name)) => {
+                text.push('{');
+                text.push_str(name);
+                text.push('}');
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            Node::Comment(..) => {}
+            _ => return Err("only text and variables are allowed in `trans` blocks".into()),
+        }
+    }
+    Ok(text.trim().to_string())
+}
+
+fn block_trans<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
+    let var = pair(ws(identifier), preceded(ws(char('=')), ws(Expr::parse)));
+    let mut start = tuple((
+        opt(expr_handle_ws),
+        ws(keyword("trans")),
+        cut(tuple((
+            separated_list0(ws(char(',')), var),
+            opt(expr_handle_ws),
+            |i| tag_block_end(i, s),
+        ))),
+    ));
+    let (i, (pws1, _, (vars, nws1, _))) = start(i)?;
+
+    let plural = tuple((
+        |i| tag_block_start(i, s),
+        opt(expr_handle_ws),
+        ws(keyword("pluralize")),
+        cut(tuple((
+            opt(expr_handle_ws),
+            |i| tag_block_end(i, s),
+            |i| parse_template(i, s),
+        ))),
+    ));
+    let mut end = cut(tuple((
+        |i| parse_template(i, s),
+        opt(plural),
+        cut(tuple((
+            |i| tag_block_start(i, s),
+            opt(expr_handle_ws),
+            ws(keyword("endtrans")),
+            opt(expr_handle_ws),
+        ))),
+    )));
+    let (i, (singular, plural, (_, pws2, _, nws2))) = end(i)?;
+
+    let plural = plural.map(|(_, pws, _, (nws, _, nodes))| (Ws(pws, nws), nodes));
+    Ok((
+        i,
+        Node::Trans(Trans {
+            ws1: Ws(pws1, nws1),
+            vars,
+            singular,
+            plural,
+            ws2: Ws(pws2, nws2),
+        }),
+    ))
 }
 
 fn block_import(i: &str) -> IResult<&str, Node<'_>> {
@@ -410,6 +594,29 @@ fn block_import(i: &str) -> IResult<&str, Node<'_>> {
     Ok((i, Node::Import(Ws(pws, nws), name, scope)))
 }
 
+fn block_from_import(i: &str) -> IResult<&str, Node<'_>> {
+    let name = pair(
+        ws(identifier),
+        opt(preceded(ws(keyword("as")), cut(ws(identifier)))),
+    );
+    let mut p = tuple((
+        opt(expr_handle_ws),
+        ws(keyword("from")),
+        cut(tuple((
+            ws(str_lit),
+            ws(keyword("import")),
+            separated_list1(ws(char(',')), name),
+            opt(expr_handle_ws),
+        ))),
+    ));
+    let (i, (pws, _, (path, _, names, nws))) = p(i)?;
+    let names = names
+        .into_iter()
+        .map(|(name, alias)| (name, alias.unwrap_or(name)))
+        .collect();
+    Ok((i, Node::FromImport(Ws(pws, nws), path, names)))
+}
+
 fn block_macro<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
     let mut start = tuple((
         opt(expr_handle_ws),
@@ -518,7 +725,10 @@ fn block_node<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
             |i| block_match(i, s),
             block_extends,
             block_include,
+            |i| block_embed(i, s),
+            |i| block_trans(i, s),
             block_import,
+            block_from_import,
             |i| block_block(i, s),
             |i| block_macro(i, s),
             |i| block_raw(i, s),
@@ -554,11 +764,11 @@ fn block_comment<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
         |i| tag_comment_start(i, s),
         cut(tuple((
             opt(expr_handle_ws),
-            |i| block_comment_body(i, s),
+            consumed(|i| block_comment_body(i, s)),
             |i| tag_comment_end(i, s),
         ))),
     ));
-    let (i, (_, (pws, tail, _))) = p(i)?;
+    let (i, (_, (pws, (body, tail), _))) = p(i)?;
     let nws = if tail.ends_with('-') {
         Some(Whitespace::Suppress)
     } else if tail.ends_with('+') {
@@ -568,7 +778,11 @@ fn block_comment<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
     } else {
         None
     };
-    Ok((i, Node::Comment(Ws(pws, nws))))
+    let text = match nws {
+        Some(_) => &body[..body.len() - 1],
+        None => body,
+    };
+    Ok((i, Node::Comment(Ws(pws, nws), text.trim_matches(is_ws))))
 }
 
 fn expr_node<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
@@ -585,13 +799,13 @@ fn expr_node<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Node<'a>> {
     Ok((i, Node::Expr(Ws(pws, nws), expr)))
 }
 
-fn parse_template<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Vec<Node<'a>>> {
-    many0(alt((
+fn parse_template<'a>(i: &'a str, s: &State<'_>) -> IResult<&'a str, Vec<WithSpan<Node<'a>>>> {
+    many0(s.spanned(alt((
         complete(|i| take_content(i, s)),
         complete(|i| block_comment(i, s)),
         complete(|i| expr_node(i, s)),
         complete(|i| block_node(i, s)),
-    )))(i)
+    ))))(i)
 }
 
 fn variant_lit(i: &str) -> IResult<&str, Target<'_>> {
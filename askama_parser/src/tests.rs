@@ -1,5 +1,4 @@
-use crate::config::Syntax;
-use crate::parser::{Expr, Node, Whitespace, Ws};
+use crate::{BlockDef, Expr, Node, Syntax, Target, Trans, Whitespace, Ws};
 
 fn check_ws_split(s: &str, res: &(&str, &str, &str)) {
     match super::split_ws_parts(s) {
@@ -41,6 +40,13 @@ fn test_parse_filter() {
         super::parse("{{ 2|abs }}", &syntax).unwrap(),
         vec![Node::Expr(Ws(None, None), Filter("abs", vec![NumLit("2")]),)],
     );
+    assert_eq!(
+        super::parse("{{ name|filters::title }}", &syntax).unwrap(),
+        vec![Node::Expr(
+            Ws(None, None),
+            Filter("filters::title", vec![Var("name")]),
+        )],
+    );
     assert_eq!(
         super::parse("{{ -2|abs }}", &syntax).unwrap(),
         vec![Node::Expr(
@@ -436,105 +442,127 @@ fn test_odd_calls() {
     );
 }
 
+#[test]
+fn test_parse_match_prefix() {
+    let s = &Syntax::default();
+    assert_eq!(
+        super::parse("{% match x %} {# y #}\n{% when _ %}{% endmatch %}", s).unwrap(),
+        vec![Node::Match(
+            Ws(None, None),
+            Expr::Var("x"),
+            vec![
+                Node::Lit(" ", "", "").into(),
+                Node::Comment(Ws(None, None), "y").into(),
+                Node::Lit("\n", "", "").into(),
+            ],
+            vec![(Ws(None, None), Target::Name("_"), vec![])],
+            Ws(None, None),
+        )],
+    );
+}
+
 #[test]
 fn test_parse_comments() {
     let s = &Syntax::default();
 
     assert_eq!(
         super::parse("{##}", s).unwrap(),
-        vec![Node::Comment(Ws(None, None))],
+        vec![Node::Comment(Ws(None, None), "")],
     );
     assert_eq!(
         super::parse("{#- #}", s).unwrap(),
-        vec![Node::Comment(Ws(Some(Whitespace::Suppress), None))],
+        vec![Node::Comment(Ws(Some(Whitespace::Suppress), None), "")],
     );
     assert_eq!(
         super::parse("{# -#}", s).unwrap(),
-        vec![Node::Comment(Ws(None, Some(Whitespace::Suppress)))],
+        vec![Node::Comment(Ws(None, Some(Whitespace::Suppress)), "")],
     );
     assert_eq!(
         super::parse("{#--#}", s).unwrap(),
-        vec![Node::Comment(Ws(
-            Some(Whitespace::Suppress),
-            Some(Whitespace::Suppress)
-        ))],
+        vec![Node::Comment(
+            Ws(Some(Whitespace::Suppress), Some(Whitespace::Suppress)),
+            ""
+        )],
     );
     assert_eq!(
         super::parse("{#- foo\n bar -#}", s).unwrap(),
-        vec![Node::Comment(Ws(
-            Some(Whitespace::Suppress),
-            Some(Whitespace::Suppress)
-        ))],
+        vec![Node::Comment(
+            Ws(Some(Whitespace::Suppress), Some(Whitespace::Suppress)),
+            "foo\n bar"
+        )],
     );
     assert_eq!(
         super::parse("{#- foo\n {#- bar\n -#} baz -#}", s).unwrap(),
-        vec![Node::Comment(Ws(
-            Some(Whitespace::Suppress),
-            Some(Whitespace::Suppress)
-        ))],
+        vec![Node::Comment(
+            Ws(Some(Whitespace::Suppress), Some(Whitespace::Suppress)),
+            "foo\n {#- bar\n -#} baz"
+        )],
     );
     assert_eq!(
         super::parse("{#+ #}", s).unwrap(),
-        vec![Node::Comment(Ws(Some(Whitespace::Preserve), None))],
+        vec![Node::Comment(Ws(Some(Whitespace::Preserve), None), "")],
     );
     assert_eq!(
         super::parse("{# +#}", s).unwrap(),
-        vec![Node::Comment(Ws(None, Some(Whitespace::Preserve)))],
+        vec![Node::Comment(Ws(None, Some(Whitespace::Preserve)), "")],
     );
     assert_eq!(
         super::parse("{#++#}", s).unwrap(),
-        vec![Node::Comment(Ws(
-            Some(Whitespace::Preserve),
-            Some(Whitespace::Preserve)
-        ))],
+        vec![Node::Comment(
+            Ws(Some(Whitespace::Preserve), Some(Whitespace::Preserve)),
+            ""
+        )],
     );
     assert_eq!(
         super::parse("{#+ foo\n bar +#}", s).unwrap(),
-        vec![Node::Comment(Ws(
-            Some(Whitespace::Preserve),
-            Some(Whitespace::Preserve)
-        ))],
+        vec![Node::Comment(
+            Ws(Some(Whitespace::Preserve), Some(Whitespace::Preserve)),
+            "foo\n bar"
+        )],
     );
     assert_eq!(
         super::parse("{#+ foo\n {#+ bar\n +#} baz -+#}", s).unwrap(),
-        vec![Node::Comment(Ws(
-            Some(Whitespace::Preserve),
-            Some(Whitespace::Preserve)
-        ))],
+        vec![Node::Comment(
+            Ws(Some(Whitespace::Preserve), Some(Whitespace::Preserve)),
+            "foo\n {#+ bar\n +#} baz -"
+        )],
     );
     assert_eq!(
         super::parse("{#~ #}", s).unwrap(),
-        vec![Node::Comment(Ws(Some(Whitespace::Minimize), None))],
+        vec![Node::Comment(Ws(Some(Whitespace::Minimize), None), "")],
     );
     assert_eq!(
         super::parse("{# ~#}", s).unwrap(),
-        vec![Node::Comment(Ws(None, Some(Whitespace::Minimize)))],
+        vec![Node::Comment(Ws(None, Some(Whitespace::Minimize)), "")],
     );
     assert_eq!(
         super::parse("{#~~#}", s).unwrap(),
-        vec![Node::Comment(Ws(
-            Some(Whitespace::Minimize),
-            Some(Whitespace::Minimize)
-        ))],
+        vec![Node::Comment(
+            Ws(Some(Whitespace::Minimize), Some(Whitespace::Minimize)),
+            ""
+        )],
     );
     assert_eq!(
         super::parse("{#~ foo\n bar ~#}", s).unwrap(),
-        vec![Node::Comment(Ws(
-            Some(Whitespace::Minimize),
-            Some(Whitespace::Minimize)
-        ))],
+        vec![Node::Comment(
+            Ws(Some(Whitespace::Minimize), Some(Whitespace::Minimize)),
+            "foo\n bar"
+        )],
     );
     assert_eq!(
         super::parse("{#~ foo\n {#~ bar\n ~#} baz -~#}", s).unwrap(),
-        vec![Node::Comment(Ws(
-            Some(Whitespace::Minimize),
-            Some(Whitespace::Minimize)
-        ))],
+        vec![Node::Comment(
+            Ws(Some(Whitespace::Minimize), Some(Whitespace::Minimize)),
+            "foo\n {#~ bar\n ~#} baz -"
+        )],
     );
 
     assert_eq!(
         super::parse("{# foo {# bar #} {# {# baz #} qux #} #}", s).unwrap(),
-        vec![Node::Comment(Ws(None, None))],
+        vec![Node::Comment(
+            Ws(None, None),
+            "foo {# bar #} {# {# baz #} qux #}"
+        )],
     );
 }
 
@@ -662,7 +690,197 @@ fn test_missing_space_after_kw() {
     let syntax = Syntax::default();
     let err = super::parse("{%leta=b%}", &syntax).unwrap_err();
     assert!(matches!(
-        &*err.msg,
+        err.message(),
         "unable to parse template:\n\n\"{%leta=b%}\""
     ));
 }
+
+#[test]
+fn test_parse_include() {
+    use Expr::*;
+    let syntax = Syntax::default();
+    assert_eq!(
+        super::parse("{% include \"a.html\" %}", &syntax).unwrap(),
+        vec![Node::Include(Ws(None, None), StrLit("a.html"), false)],
+    );
+    assert_eq!(
+        super::parse("{%- include \"a.html\" ignore missing -%}", &syntax).unwrap(),
+        vec![Node::Include(
+            Ws(Some(Whitespace::Suppress), Some(Whitespace::Suppress)),
+            StrLit("a.html"),
+            true
+        )],
+    );
+    assert_eq!(
+        super::parse("{% include [\"a.html\", \"b.html\"][idx] %}", &syntax).unwrap(),
+        vec![Node::Include(
+            Ws(None, None),
+            Index(
+                Box::new(Array(vec![StrLit("a.html"), StrLit("b.html")])),
+                Box::new(Var("idx"))
+            ),
+            false
+        )],
+    );
+}
+
+#[test]
+fn test_parse_embed() {
+    let syntax = Syntax::default();
+    assert_eq!(
+        super::parse(
+            "{% embed \"a.html\" %}{% block b %}{% endblock %}{%- endembed %}",
+            &syntax
+        )
+        .unwrap(),
+        vec![Node::Embed(
+            Ws(None, None),
+            "a.html",
+            vec![Node::BlockDef(BlockDef {
+                ws1: Ws(None, None),
+                name: "b",
+                required: false,
+                nodes: vec![],
+                ws2: Ws(None, None),
+            })
+            .into()],
+            Ws(Some(Whitespace::Suppress), None),
+        )],
+    );
+}
+
+#[test]
+fn test_parse_block_modifiers() {
+    let syntax = Syntax::default();
+    assert_eq!(
+        super::parse("{% block title required %}{% endblock %}", &syntax).unwrap(),
+        vec![Node::BlockDef(BlockDef {
+            ws1: Ws(None, None),
+            name: "title",
+            required: true,
+            nodes: vec![],
+            ws2: Ws(None, None),
+        })],
+    );
+    // Overriding blocks always see the locals of the base template, so Jinja's `scoped`
+    // marker isn't supported.
+    assert!(super::parse("{% block item scoped %}{% endblock %}", &syntax).is_err());
+}
+
+#[test]
+fn test_parse_from_import() {
+    let syntax = Syntax::default();
+    assert_eq!(
+        super::parse(
+            "{% from \"macros.html\" import button, card as panel -%}",
+            &syntax
+        )
+        .unwrap(),
+        vec![Node::FromImport(
+            Ws(None, Some(Whitespace::Suppress)),
+            "macros.html",
+            vec![("button", "button"), ("card", "panel")],
+        )],
+    );
+    assert!(super::parse("{% from \"macros.html\" import %}", &syntax).is_err());
+}
+
+#[test]
+fn test_parse_trans() {
+    let syntax = Syntax::default();
+    assert_eq!(
+        super::parse(
+            "{% trans count = n %}One{% pluralize -%} {{ count }}{% endtrans %}",
+            &syntax
+        )
+        .unwrap(),
+        vec![Node::Trans(Trans {
+            ws1: Ws(None, None),
+            vars: vec![("count", Expr::Var("n"))],
+            singular: vec![Node::Lit("", "One", "").into()],
+            plural: Some((
+                Ws(None, Some(Whitespace::Suppress)),
+                vec![
+                    Node::Lit(" ", "", "").into(),
+                    Node::Expr(Ws(None, None), Expr::Var("count")).into()
+                ],
+            )),
+            ws2: Ws(None, None),
+        })],
+    );
+}
+
+#[test]
+fn test_message_id() {
+    assert_eq!(super::message_id("Hello, {name}!"), "hello-name");
+    assert_eq!(super::message_id("One item"), "one-item");
+    assert_eq!(super::message_id("{count} items"), "count-items");
+    assert_eq!(super::message_id("3 Äpfel"), "msg-3-pfel");
+    assert_eq!(super::message_id("!"), "msg");
+}
+
+#[test]
+fn test_parse_named_argument() {
+    let syntax = Syntax::default();
+    assert_eq!(
+        super::parse("{{ _(\"key\", count = n, a == b) }}", &syntax).unwrap(),
+        vec![Node::Expr(
+            Ws(None, None),
+            Expr::Call(
+                Box::new(Expr::Var("_")),
+                vec![
+                    Expr::StrLit("key"),
+                    Expr::NamedArgument("count", Box::new(Expr::Var("n"))),
+                    Expr::BinOp("==", Box::new(Expr::Var("a")), Box::new(Expr::Var("b"))),
+                ],
+            ),
+        )],
+    );
+}
+
+#[test]
+fn test_nodes_to_json() {
+    let syntax = Syntax::default();
+    let src = r#"a {%- if x.y == "b\"" %}{{ z|e }}{% endif +%}"#;
+    let nodes = super::parse(src, &syntax).unwrap();
+    assert_eq!(
+        super::json::nodes_to_json(src, &nodes),
+        concat!(
+            r#"[{"type":"lit","offset":0,"lws":"","val":"a","rws":" ","span":[0,2]},"#,
+            r#"{"type":"if","branches":[{"ws":["-",null],"target":null,"#,
+            r#""cond":{"type":"binop","offset":13,"op":"==","#,
+            r#""lhs":{"type":"attr","expr":{"type":"var","offset":9,"name":"x"},"offset":11,"attr":"y"},"#,
+            r#""rhs":{"type":"str","offset":17,"value":"b\\\""}},"#,
+            r#""nodes":[{"type":"expr","ws":[null,null],"expr":{"type":"filter","offset":29,"name":"e","#,
+            r#""args":[{"type":"var","offset":27,"name":"z"}]},"span":[24,33]}]}],"#,
+            r#""ws":[null,"+"],"span":[2,45]}]"#,
+        ),
+    );
+}
+
+#[test]
+fn test_parse_spans() {
+    let syntax = Syntax::default();
+    let src = "a {% for x in y %} {{ x }}{% endfor %}{# c #}";
+    let nodes = super::parse(src, &syntax).unwrap();
+    let spans: Vec<_> = nodes
+        .iter()
+        .map(|node| &src[node.span().start..node.span().end])
+        .collect();
+    assert_eq!(
+        spans,
+        ["a ", "{% for x in y %} {{ x }}{% endfor %}", "{# c #}"]
+    );
+    let body = match &*nodes[1] {
+        Node::Loop(l) => &l.body,
+        _ => panic!("expected a loop"),
+    };
+    let spans: Vec<_> = body
+        .iter()
+        .map(|node| &src[node.span().start..node.span().end])
+        .collect();
+    assert_eq!(spans, [" ", "{{ x }}"]);
+
+    let err = super::parse("a {{ x }}\n{% if %}", &syntax).unwrap_err();
+    assert_eq!(err.offset(), Some(16));
+}
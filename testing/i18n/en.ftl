@@ -0,0 +1,7 @@
+welcome = Welcome, { $name }!
+cart-items =
+    { $count ->
+        [one] One item in your cart
+       *[other] { $count } items in your cart
+    }
+goodbye-name = See you, { $name }!
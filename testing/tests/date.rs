@@ -0,0 +1,59 @@
+#![cfg(any(feature = "chrono", feature = "time"))]
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use askama::filters::{FormatDateTime, Timestamp};
+use askama::Template;
+
+#[derive(Template)]
+#[template(
+    source = "{{ at|date(\"%d.%m.%Y %H:%M %z\") }}|{{ at|datetime }}|{{ at|isoformat }}|\
+              {{ since|timesince }}|{{ since|timeuntil }}",
+    ext = "txt"
+)]
+struct DateTemplate<T: FormatDateTime + Timestamp> {
+    at: T,
+    since: T,
+}
+
+// Two hours and a few seconds ago
+fn two_hours_ago() -> i64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    now.as_secs() as i64 - 2 * 60 * 60 - 30
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_chrono() {
+    use dep_chrono::{FixedOffset, TimeZone};
+
+    let offset = FixedOffset::west_opt(5 * 3600).unwrap();
+    let t = DateTemplate {
+        at: offset.with_ymd_and_hms(2023, 1, 2, 3, 4, 5).unwrap(),
+        since: offset.timestamp_opt(two_hours_ago(), 0).unwrap(),
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "02.01.2023 03:04 -0500|2023-01-02 03:04:05|2023-01-02T03:04:05-05:00|2 hours|0 minutes"
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn test_time() {
+    use dep_time::{Date, Month, OffsetDateTime, UtcOffset};
+
+    let offset = UtcOffset::from_hms(-5, 0, 0).unwrap();
+    let t = DateTemplate {
+        at: Date::from_calendar_date(2023, Month::January, 2)
+            .unwrap()
+            .with_hms(3, 4, 5)
+            .unwrap()
+            .assume_offset(offset),
+        since: OffsetDateTime::from_unix_timestamp(two_hours_ago()).unwrap(),
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "02.01.2023 03:04 -0500|2023-01-02 03:04:05|2023-01-02T03:04:05-05:00|2 hours|0 minutes"
+    );
+}
@@ -0,0 +1,76 @@
+use askama::Template;
+
+#[derive(Template)]
+#[template(
+    source = r#"{% embed "embed-panel.html" %}{% block body %}{{ s }}{% endblock %}{% endembed %}"#,
+    ext = "html"
+)]
+struct EmbedTemplate<'a> {
+    s: &'a str,
+}
+
+#[test]
+fn test_embed() {
+    let t = EmbedTemplate { s: "foo" };
+    assert_eq!(t.render().unwrap(), "<div>Panel: foo</div>");
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"
+{%- for s in strs -%}
+{% embed "embed-panel.html" %}
+  {%- block title %}{{ loop.index }}. {% call super() %}{% endblock -%}
+  {%- block body %}{{ s }}{% endblock -%}
+{% endembed %}
+{%- endfor %}
+{% embed "embed-panel.html" %}{% endembed %}"#,
+    ext = "html"
+)]
+struct EmbedLoopTemplate<'a> {
+    strs: &'a [&'a str],
+}
+
+#[test]
+fn test_embed_loop() {
+    let t = EmbedLoopTemplate {
+        strs: &["foo", "bar"],
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "<div>1. Panel: foo</div><div>2. Panel: bar</div>\n<div>Panel: empty</div>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"{% embed "embed-card.html" %}{% block body %}{{ s }}{% endblock %}{% endembed %}"#,
+    ext = "html"
+)]
+struct EmbedExtendingTemplate<'a> {
+    s: &'a str,
+}
+
+#[test]
+fn test_embed_extending_template() {
+    let t = EmbedExtendingTemplate { s: "foo" };
+    assert_eq!(t.render().unwrap(), "<div>Card: foo</div>");
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"{% extends "embed-panel.html" %}
+{% block body -%}
+{% embed "embed-panel.html" %}{% block title %}Inner{% endblock %}{% endembed %}
+{%- endblock %}"#,
+    ext = "html"
+)]
+struct EmbedInChildTemplate;
+
+#[test]
+fn test_embed_in_child() {
+    assert_eq!(
+        EmbedInChildTemplate.render().unwrap(),
+        "<div>Panel: <div>Inner: empty</div></div>"
+    );
+}
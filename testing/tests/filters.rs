@@ -126,6 +126,10 @@ mod filters {
     pub fn mytrim(s: &dyn (::std::fmt::Display)) -> ::askama::Result<String> {
         Ok(s.to_string().trim().to_owned())
     }
+    // for test_local_filter_with_typo_name
+    pub fn dates(s: &str) -> ::askama::Result<String> {
+        Ok(format!("{s} to {s}"))
+    }
 }
 
 #[test]
@@ -134,6 +138,40 @@ fn test_my_filter() {
     assert_eq!(t.render().unwrap(), "faa");
 }
 
+mod local_title {
+    use askama::Template;
+
+    mod filters {
+        pub fn title(s: &str) -> ::askama::Result<String> {
+            Ok(format!("Dr. {s}"))
+        }
+    }
+
+    #[derive(Template)]
+    #[template(source = "{{ s|filters::title }}", ext = "txt")]
+    struct LocalTitleTemplate<'a> {
+        s: &'a str,
+    }
+
+    #[test]
+    fn test_local_filter_with_builtin_name() {
+        let t = LocalTitleTemplate { s: "who" };
+        assert_eq!(t.render().unwrap(), "Dr. who");
+    }
+}
+
+#[derive(Template)]
+#[template(source = "{{ s|dates }}", ext = "txt")]
+struct LocalDatesTemplate<'a> {
+    s: &'a str,
+}
+
+#[test]
+fn test_local_filter_with_typo_name() {
+    let t = LocalDatesTemplate { s: "May" };
+    assert_eq!(t.render().unwrap(), "May to May");
+}
+
 #[derive(Template)]
 #[template(path = "filters_join.html")]
 struct JoinTemplate<'a> {
@@ -309,3 +347,325 @@ fn test_json_script() {
         r#"<script>var user = "\u003c/script\u003e\u003cbutton\u003eHacked!\u003c/button\u003e"</script>"#
     );
 }
+
+#[derive(Template)]
+#[template(
+    source = "{{ total|numberformat }} {{ total|numberformat(2) }} {{ ratio|percent }} \
+              {{ ratio|percent(1) }} {{ total|currency(\"EUR\") }} {{ total|currency(code) }}",
+    ext = "txt"
+)]
+struct NumberFormatTemplate<'a> {
+    total: f64,
+    ratio: f32,
+    code: &'a str,
+}
+
+#[test]
+fn test_number_format() {
+    let t = NumberFormatTemplate {
+        total: 1234567.891,
+        ratio: 0.256,
+        code: "USD",
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "1,234,568 1,234,567.89 26% 25.6% €1,234,567.89 $1,234,567.89"
+    );
+}
+
+#[cfg(feature = "icu")]
+#[derive(Template)]
+#[template(
+    source = "{{ total|numberformat(2, \"de\") }} {{ ratio|percent(0, lang) }} \
+              {{ total|currency(\"EUR\", lang) }}",
+    ext = "txt"
+)]
+struct NumberFormatLocaleTemplate {
+    total: i64,
+    ratio: f64,
+    lang: String,
+}
+
+#[cfg(feature = "icu")]
+#[test]
+fn test_number_format_locale() {
+    let t = NumberFormatLocaleTemplate {
+        total: -1234567,
+        ratio: 0.5,
+        lang: "fr".to_string(),
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "-1.234.567,00 50\u{a0}% -1\u{202f}234\u{202f}567,00\u{a0}€"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    source = "{{ names|sort|join(\", \") }} / {{ names|reverse|join(\", \") }} / \
+              {{ names|unique|join(\", \") }} / {{ names|length }} / \
+              {% if let Some(name) = names|first %}{{ name }}{% endif %} \
+              {% if let Some(name) = names|sort|last %}{{ name }}{% endif %} / \
+              {{ scores|sum }} {% if let Some(min) = scores|min %}{{ min }}{% endif %} \
+              {% if let Some(max) = scores|max %}{{ max }}{% endif %} / \
+              {% for user in users|sort_by(\"info.age\")|reverse %}{{ user.name }} {% endfor %}",
+    ext = "txt"
+)]
+struct CollectionTemplate {
+    names: Vec<&'static str>,
+    scores: Vec<u32>,
+    users: Vec<User>,
+}
+
+struct User {
+    name: &'static str,
+    info: UserInfo,
+}
+
+struct UserInfo {
+    age: u32,
+}
+
+#[test]
+fn test_collection_filters() {
+    let user = |name, age| User {
+        name,
+        info: UserInfo { age },
+    };
+    let t = CollectionTemplate {
+        names: vec!["carol", "alice", "bob", "alice"],
+        scores: vec![7, 3, 12],
+        users: vec![user("alice", 31), user("bob", 25), user("carol", 42)],
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "alice, alice, bob, carol / alice, bob, alice, carol / carol, alice, bob / 4 / \
+         carol carol / 22 3 12 / carol alice bob "
+    );
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% for item in items|sort_by(\"price\") %}{{ item.price }} {% endfor %}",
+    ext = "txt"
+)]
+struct SortByTemplate {
+    items: Vec<Item>,
+}
+
+struct Item {
+    price: f64,
+}
+
+#[test]
+fn test_sort_by_nan() {
+    let items = (0..25)
+        .map(|i| Item {
+            price: if i % 5 == 0 {
+                f64::NAN
+            } else {
+                f64::from(25 - i)
+            },
+        })
+        .collect();
+    let numbers: String = (1..25)
+        .filter(|n| n % 5 != 0)
+        .map(|n| format!("{n} "))
+        .collect();
+    assert_eq!(
+        SortByTemplate { items }.render().unwrap(),
+        numbers + &"NaN ".repeat(5)
+    );
+}
+
+#[derive(Template)]
+#[template(
+    source = "{{ members|select(\"active\")|map(\"email\")|join(\", \") }} / \
+              {{ members|reject(\"active\")|map(\"email\")|join(\", \") }} / \
+              {{ members|select(\"team\", \"ops\")|map(\"email\")|join(\", \") }} / \
+              {{ members|select(self::is_admin)|length }} / \
+              {% for (team, members) in members|sort_by(\"team\")|groupby(\"team\") -%}
+              {{ team }}: {{ members|map(\"email\")|join(\", \") }}; {% endfor %}",
+    ext = "txt"
+)]
+struct SelectTemplate {
+    members: Vec<Member>,
+}
+
+struct Member {
+    email: &'static str,
+    active: bool,
+    team: String,
+}
+
+fn is_admin(member: &&Member) -> bool {
+    member.email.starts_with("admin@")
+}
+
+#[test]
+fn test_select_filters() {
+    let member = |email, active, team: &str| Member {
+        email,
+        active,
+        team: team.to_string(),
+    };
+    let t = SelectTemplate {
+        members: vec![
+            member("admin@example.com", true, "ops"),
+            member("bob@example.com", false, "dev"),
+            member("carol@example.com", true, "dev"),
+        ],
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "admin@example.com, carol@example.com / bob@example.com / admin@example.com / 1 / \
+         dev: bob@example.com, carol@example.com; ops: admin@example.com; "
+    );
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% for row in cells|batch(3, empty) %}[{{ row|join(\" \") }}]{% endfor %} \
+              {% for row in cells|select(self::is_short)|batch(cols) %}[{{ row|join(\" \") }}]{% endfor %} \
+              {% for column in cells|slice(2) -%}
+              {% if loop.first %}<{% endif %}{{ column|join(\" \") }}{% if loop.last %}>{% else %}|{% endif %}
+              {%- endfor %}",
+    ext = "txt"
+)]
+struct BatchTemplate {
+    cells: Vec<&'static str>,
+    empty: &'static str,
+    cols: usize,
+}
+
+fn is_short(cell: &&&str) -> bool {
+    cell.len() < 3
+}
+
+#[test]
+fn test_batch_slice() {
+    let t = BatchTemplate {
+        cells: vec!["a", "b", "c", "d", "eee"],
+        empty: "-",
+        cols: 2,
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "[a b c][d eee -] [a b][c d] <a b c|d eee>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    source = "{{ nickname|default(\"—\") }} {{ bio|default(\"<none>\") }} \
+              {{ name|default(\"anonymous\", true) }} [{{ age|unwrap_or_empty }}] \
+              {{ bio|default(\"<i>none</i>\"|safe) }} {{ html|safe|default(\"<none>\", true) }} \
+              {{ nickname|default(bio|unwrap_or_empty) }} {{ names|first|default(\"?\") }}",
+    ext = "html"
+)]
+struct DefaultTemplate {
+    nickname: Option<String>,
+    bio: Option<&'static str>,
+    name: &'static str,
+    age: Option<u32>,
+    html: &'static str,
+    names: Vec<&'static str>,
+}
+
+#[test]
+fn test_default() {
+    let t = DefaultTemplate {
+        nickname: None,
+        bio: None,
+        name: "",
+        age: None,
+        html: "",
+        names: vec![],
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "— &lt;none&gt; anonymous [] <i>none</i> &lt;none&gt;  ?"
+    );
+
+    let t = DefaultTemplate {
+        nickname: Some("<bob>".into()),
+        bio: Some("<b>hi</b>"),
+        name: "Bob",
+        age: Some(42),
+        html: "<b>hi</b>",
+        names: vec!["alice"],
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "&lt;bob&gt; &lt;b&gt;hi&lt;/b&gt; Bob [42] &lt;b&gt;hi&lt;/b&gt; <b>hi</b> \
+         &lt;bob&gt; alice"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    source = "{{ html|striptags|wordwrap(12) }}\n{{ html|striptags|truncatewords(2) }}\n\
+              {{ html|truncatechars_html(8)|safe }}\n{{ heading|title }}",
+    ext = "html"
+)]
+struct TextFiltersTemplate {
+    html: &'static str,
+    heading: &'static str,
+}
+
+#[test]
+fn test_text_filters() {
+    let t = TextFiltersTemplate {
+        html: "<p>Fish &amp; <b>chips</b></p><p>for everyone</p>",
+        heading: "fish and CHIPS",
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "Fish &amp; chips\nfor everyone\nFish &amp;...\n\
+         <p>Fish &amp; <b>c...</b></p>\nFish And Chips"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    source = "{{ title|replace(\"è\", \"e\") }} \
+              {% for tag in tags|split(\",\") %}[{{ tag|trim }}]{% endfor %}\n\
+              {{ comment|urlize }}",
+    ext = "html"
+)]
+struct StringFiltersTemplate {
+    title: &'static str,
+    tags: String,
+    comment: &'static str,
+}
+
+#[test]
+fn test_string_filters() {
+    let t = StringFiltersTemplate {
+        title: "Crème brûlée",
+        tags: "dessert, <french>".into(),
+        comment: "<3 https://example.com/?a&b, or mail chef@example.com",
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "Creme brûlée [dessert][&lt;french&gt;]\n\
+         &lt;3 <a href=\"https://example.com/?a&amp;b\" rel=\"noopener\">https://example.com/?a&amp;b</a>, \
+         or mail <a href=\"mailto:chef@example.com\" rel=\"noopener\">chef@example.com</a>"
+    );
+}
+
+#[cfg(feature = "slugify")]
+#[derive(Template)]
+#[template(source = "/posts/{{ title|slugify }}", ext = "html")]
+struct SlugifyTemplate {
+    title: &'static str,
+}
+
+#[cfg(feature = "slugify")]
+#[test]
+fn test_slugify() {
+    let t = SlugifyTemplate {
+        title: "Crème brûlée, à la française!",
+    };
+    assert_eq!(t.render().unwrap(), "/posts/creme-brulee-a-la-francaise");
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "i18n")]
+
+use askama::i18n::{FluentCatalog, GettextCatalog, Localizer, Message};
+use askama::Template;
+
+// A tiny German catalog, standing in for a gettext or Fluent backend.
+struct German;
+
+impl Localizer for German {
+    fn translate(&self, message: &Message<'_>) -> String {
+        let text = match (message.text, message.count) {
+            ("Hello {name}!", _) => "Hallo {name}!",
+            ("One item", Some(1)) => "Ein Element",
+            ("One item", _) => "{count} Elemente",
+            ("welcome", _) => "Willkommen, {name}!",
+            _ => return message.fallback(),
+        };
+        message.format(text)
+    }
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% trans %}Hello {{ name }}!{% endtrans %}",
+    ext = "html",
+    config = "test_i18n.toml"
+)]
+struct TransTemplate<'a> {
+    name: &'a str,
+}
+
+impl Localizer for TransTemplate<'_> {
+    fn translate(&self, message: &Message<'_>) -> String {
+        German.translate(message)
+    }
+}
+
+#[test]
+fn test_trans() {
+    let t = TransTemplate { name: "<Welt>" };
+    assert_eq!(t.render().unwrap(), "Hallo &lt;Welt&gt;!");
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% for n in counts -%}
+        {% trans count = n %}One item{% pluralize %}{{ count }} items{% endtrans %};
+    {%- endfor %}",
+    ext = "txt",
+    config = "test_i18n.toml"
+)]
+struct PluralTemplate {
+    counts: Vec<usize>,
+}
+
+impl Localizer for PluralTemplate {
+    fn translate(&self, message: &Message<'_>) -> String {
+        message.fallback()
+    }
+}
+
+#[test]
+fn test_trans_pluralize() {
+    let t = PluralTemplate { counts: vec![1, 3] };
+    assert_eq!(t.render().unwrap(), "One item;3 items;");
+}
+
+#[derive(Default)]
+struct AppCtx;
+
+impl Localizer for AppCtx {
+    fn translate(&self, message: &Message<'_>) -> String {
+        German.translate(message)
+    }
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"{{ _("welcome", name = user) }} {{ _("cart-items", count = items) }}"#,
+    ext = "html",
+    config = "test_i18n.toml",
+    context = "AppCtx"
+)]
+struct CallTemplate<'a> {
+    user: &'a str,
+    items: u32,
+}
+
+#[test]
+fn test_localize_call() {
+    let t = CallTemplate {
+        user: "Anna",
+        items: 2,
+    };
+    assert_eq!(
+        t.render_with(&AppCtx).unwrap(),
+        "Willkommen, Anna! cart-items"
+    );
+}
+
+const EN_FTL: &str = include_str!("../i18n/en.ftl");
+
+struct FluentCtx {
+    catalog: FluentCatalog,
+}
+
+impl Localizer for FluentCtx {
+    fn translate(&self, message: &Message<'_>) -> String {
+        self.catalog.translate(message)
+    }
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"{% trans %}Goodbye, {{ name }}!{% endtrans %} {{ _("cart-items", count = items) }}"#,
+    ext = "html",
+    config = "test_i18n.toml",
+    context = "FluentCtx"
+)]
+struct FluentTemplate<'a> {
+    name: &'a str,
+    items: u32,
+}
+
+#[test]
+fn test_fluent_catalog() {
+    let ctx = FluentCtx {
+        catalog: FluentCatalog::new("en", &[EN_FTL]).unwrap(),
+    };
+    let t = FluentTemplate {
+        name: "<Anna>",
+        items: 1,
+    };
+    assert_eq!(
+        t.render_with(&ctx).unwrap(),
+        "See you, &lt;Anna&gt;! One item in your cart"
+    );
+    let t = FluentTemplate {
+        name: "Bob",
+        items: 3,
+    };
+    assert_eq!(
+        t.render_with(&ctx).unwrap(),
+        "See you, Bob! 3 items in your cart"
+    );
+}
+
+const DE_PO: &str = include_str!("../i18n/de.po");
+
+struct GettextCtx {
+    catalog: GettextCatalog,
+}
+
+impl Localizer for GettextCtx {
+    fn translate(&self, message: &Message<'_>) -> String {
+        self.catalog.translate(message)
+    }
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% trans %}Hello {{ name }}!{% endtrans %} \
+              {% trans count = items %}One item{% pluralize %}{{ count }} items{% endtrans %}",
+    ext = "html",
+    config = "test_i18n.toml",
+    context = "GettextCtx"
+)]
+struct GettextTemplate<'a> {
+    name: &'a str,
+    items: u32,
+}
+
+#[test]
+fn test_gettext_catalog() {
+    let ctx = GettextCtx {
+        catalog: GettextCatalog::new(&[DE_PO]).unwrap(),
+    };
+    let t = GettextTemplate {
+        name: "<Anna>",
+        items: 1,
+    };
+    assert_eq!(
+        t.render_with(&ctx).unwrap(),
+        "Hallo &lt;Anna&gt;! Ein Element"
+    );
+    let t = GettextTemplate {
+        name: "Bob",
+        items: 3,
+    };
+    assert_eq!(t.render_with(&ctx).unwrap(), "Hallo Bob! 3 Elemente");
+}
+
+// The key is checked against the catalogs with its escape sequences resolved.
+#[derive(Template)]
+#[template(
+    source = r#"{{ _("Say \"hi\"") }}"#,
+    ext = "html",
+    config = "test_i18n.toml"
+)]
+struct EscapedKeyTemplate;
+
+impl Localizer for EscapedKeyTemplate {
+    fn translate(&self, message: &Message<'_>) -> String {
+        message.fallback()
+    }
+}
+
+#[test]
+fn test_escaped_key() {
+    assert_eq!(EscapedKeyTemplate.render().unwrap(), "Say &quot;hi&quot;");
+}
@@ -12,3 +12,133 @@ fn test_include() {
     let s = IncludeTemplate { strs: &strs };
     assert_eq!(s.render().unwrap(), "\n  INCLUDED: foo\n  INCLUDED: bar")
 }
+
+#[derive(Template)]
+#[template(
+    source = r#"{% include ["include-compact.html", "include-full.html"][idx] %}"#,
+    ext = "html"
+)]
+struct IncludeIndexTemplate<'a> {
+    idx: usize,
+    s: &'a str,
+}
+
+#[test]
+fn test_include_index() {
+    let t = IncludeIndexTemplate { idx: 0, s: "foo" };
+    assert_eq!(t.render().unwrap(), "compact: foo");
+    let t = IncludeIndexTemplate { idx: 1, s: "bar" };
+    assert_eq!(t.render().unwrap(), "full: bar");
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"{% include ["include-compact.html", "include-full.html"][1] %}|{% include ["include-compact.html", "include-full.html"][idx] %}"#,
+    ext = "html"
+)]
+struct IncludeIntegerIndexTemplate<'a> {
+    idx: i32,
+    s: &'a str,
+}
+
+#[test]
+fn test_include_integer_index() {
+    let t = IncludeIntegerIndexTemplate { idx: 0, s: "foo" };
+    assert_eq!(t.render().unwrap(), "full: foo|compact: foo");
+    let t = IncludeIntegerIndexTemplate { idx: -1, s: "foo" };
+    assert!(t.render().is_err());
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"{% include ["include-compact.html", "include-compact.html", "include-full.html"][idx] %}"#,
+    ext = "html"
+)]
+struct IncludeIndexRepeatedTemplate<'a> {
+    idx: usize,
+    s: &'a str,
+}
+
+#[test]
+fn test_include_index_repeated() {
+    let t = IncludeIndexRepeatedTemplate { idx: 1, s: "foo" };
+    assert_eq!(t.render().unwrap(), "compact: foo");
+    let t = IncludeIndexRepeatedTemplate { idx: 2, s: "bar" };
+    assert_eq!(t.render().unwrap(), "full: bar");
+    let t = IncludeIndexRepeatedTemplate { idx: 3, s: "baz" };
+    assert!(t.render().is_err());
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"{% include ["include-compact.html", "include-full.html"][name] %}"#,
+    ext = "html"
+)]
+struct IncludeNameTemplate<'a> {
+    name: &'a str,
+    s: &'a str,
+}
+
+#[test]
+fn test_include_name() {
+    let t = IncludeNameTemplate {
+        name: "include-full.html",
+        s: "foo",
+    };
+    assert_eq!(t.render().unwrap(), "full: foo");
+    let t = IncludeNameTemplate {
+        name: "include-compact.html",
+        s: "bar",
+    };
+    assert_eq!(t.render().unwrap(), "compact: bar");
+    let t = IncludeNameTemplate {
+        name: "include.html",
+        s: "baz",
+    };
+    assert!(t.render().is_err());
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"{% include ["include-missing.html", "include-full.html"] %}"#,
+    ext = "html"
+)]
+struct IncludeFirstFoundTemplate<'a> {
+    s: &'a str,
+}
+
+#[test]
+fn test_include_first_found() {
+    let t = IncludeFirstFoundTemplate { s: "foo" };
+    assert_eq!(t.render().unwrap(), "full: foo");
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"a{% include "include-missing.html" ignore missing %}b"#,
+    ext = "html"
+)]
+struct IncludeIgnoreMissingTemplate;
+
+#[test]
+fn test_include_ignore_missing() {
+    assert_eq!(IncludeIgnoreMissingTemplate.render().unwrap(), "ab");
+}
+
+#[derive(Template)]
+#[template(
+    source = r#"[{% include ["include-missing.html", "include-full.html"][idx] ignore missing %}]"#,
+    ext = "html"
+)]
+struct IncludeIndexIgnoreMissingTemplate<'a> {
+    idx: usize,
+    s: &'a str,
+}
+
+#[test]
+fn test_include_index_ignore_missing() {
+    let t = IncludeIndexIgnoreMissingTemplate { idx: 0, s: "foo" };
+    assert_eq!(t.render().unwrap(), "[]");
+    let t = IncludeIndexIgnoreMissingTemplate { idx: 1, s: "foo" };
+    assert_eq!(t.render().unwrap(), "[full: foo]");
+}
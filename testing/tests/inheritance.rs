@@ -340,3 +340,27 @@ fn test_let_block() {
     let t = LetChild {};
     assert_eq!(t.render().unwrap(), "1");
 }
+
+// Overriding blocks see the locals of the base template.
+#[derive(Template)]
+#[template(path = "loop-block-child.html")]
+struct LoopBlockChild<'a> {
+    items: &'a [&'a str],
+}
+
+#[test]
+fn test_loop_block() {
+    let t = LoopBlockChild {
+        items: &["a", "b", "c"],
+    };
+    assert_eq!(t.render().unwrap(), "[a][b][c]");
+}
+
+#[derive(Template)]
+#[template(path = "required-child.html")]
+struct RequiredChild;
+
+#[test]
+fn test_required_block() {
+    assert_eq!(RequiredChild.render().unwrap(), "<title>Index</title>");
+}
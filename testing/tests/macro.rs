@@ -83,3 +83,43 @@ fn str_cmp() {
     let t = StrCmpTemplate;
     assert_eq!(t.render().unwrap(), "AfooBotherCneitherD");
 }
+
+#[derive(Template)]
+#[template(path = "macro-child.html")]
+struct InheritedMacroTemplate<'a> {
+    s: &'a str,
+}
+
+#[test]
+fn test_inherited_macro() {
+    let t = InheritedMacroTemplate { s: "foo" };
+    assert_eq!(t.render().unwrap(), "<h1>foo</h1>foo foo foo");
+}
+
+#[derive(Template)]
+#[template(path = "from-import.html")]
+struct FromImportTemplate<'a> {
+    s: &'a str,
+}
+
+#[test]
+fn test_from_import() {
+    let t = FromImportTemplate { s: "foo" };
+    assert_eq!(t.render().unwrap(), "foo foo foo|foo foo foo");
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% call lib::thrice(s) %}|{% call parent() %}",
+    ext = "txt",
+    config = "test_prelude.toml"
+)]
+struct PreludeTemplate<'a> {
+    s: &'a str,
+}
+
+#[test]
+fn test_prelude() {
+    let t = PreludeTemplate { s: "foo" };
+    assert_eq!(t.render().unwrap(), "foo foo foo|foo");
+}
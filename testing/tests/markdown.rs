@@ -1,6 +1,10 @@
 #![cfg(feature = "markdown")]
 
+use std::collections::HashMap;
+use std::io::{self, Write};
+
 use askama::Template;
+use comrak::adapters::SyntaxHighlighterAdapter;
 use comrak::{ComrakOptions, ComrakRenderOptions};
 
 #[derive(Template)]
@@ -73,3 +77,119 @@ before\
 after",
     );
 }
+
+#[cfg(feature = "syntect")]
+#[derive(Template)]
+#[template(source = "{{content|markdown(\"base16-ocean.dark\")}}", ext = "html")]
+struct MarkdownHighlightTemplate<'a> {
+    content: &'a str,
+}
+
+#[cfg(feature = "syntect")]
+#[test]
+fn test_markdown_highlight() {
+    let s = MarkdownHighlightTemplate {
+        content: "```rust\nfn main() {}\n```",
+    };
+    let html = s.render().unwrap();
+    assert!(html.starts_with("<pre"));
+    assert!(html.contains("background-color:#2b303b;"));
+    assert!(html.contains("<span"));
+    assert!(!html.contains("```"));
+}
+
+#[cfg(feature = "syntect")]
+#[derive(Template)]
+#[template(
+    source = "{{content|markdown}}",
+    ext = "html",
+    config = "test_highlight.toml"
+)]
+struct MarkdownConfiguredHighlightTemplate<'a> {
+    content: &'a str,
+}
+
+#[cfg(feature = "syntect")]
+#[test]
+fn test_markdown_configured_highlight() {
+    let s = MarkdownConfiguredHighlightTemplate {
+        content: "```rust\nfn main() {}\n```",
+    };
+    let html = s.render().unwrap();
+    assert!(html.starts_with("<pre"));
+    assert!(html.contains("background-color:#ffffff;"));
+    assert!(html.contains("<span"));
+}
+
+// A highlighter that upper-cases the code, to check that custom highlighters are used.
+struct UpperHighlighter;
+
+impl SyntaxHighlighterAdapter for UpperHighlighter {
+    fn write_highlighted(
+        &self,
+        output: &mut dyn Write,
+        _lang: Option<&str>,
+        code: &str,
+    ) -> io::Result<()> {
+        output.write_all(code.to_uppercase().as_bytes())
+    }
+
+    fn write_pre_tag(
+        &self,
+        output: &mut dyn Write,
+        _attributes: HashMap<String, String>,
+    ) -> io::Result<()> {
+        output.write_all(b"<pre class=\"upper\">")
+    }
+
+    fn write_code_tag(
+        &self,
+        output: &mut dyn Write,
+        _attributes: HashMap<String, String>,
+    ) -> io::Result<()> {
+        output.write_all(b"<code>")
+    }
+}
+
+#[derive(Template)]
+#[template(source = "{{content|markdown(options, highlighter)}}", ext = "html")]
+struct MarkdownCustomHighlightTemplate<'a> {
+    content: &'a str,
+    options: &'a ComrakOptions,
+    highlighter: &'a dyn SyntaxHighlighterAdapter,
+}
+
+#[test]
+fn test_markdown_custom_highlight() {
+    let s = MarkdownCustomHighlightTemplate {
+        content: "```rust\nfn main() {}\n```",
+        options: &ComrakOptions::default(),
+        highlighter: &UpperHighlighter,
+    };
+    assert_eq!(
+        s.render().unwrap(),
+        "<pre class=\"upper\"><code>FN MAIN() {}\n</code></pre>\n"
+    );
+}
+
+#[cfg(all(feature = "syntect", feature = "ammonia"))]
+#[derive(Template)]
+#[template(
+    source = "{{content|markdown}}",
+    ext = "html",
+    config = "test_sanitize_highlight.toml"
+)]
+struct MarkdownSanitizedHighlightTemplate<'a> {
+    content: &'a str,
+}
+
+#[cfg(all(feature = "syntect", feature = "ammonia"))]
+#[test]
+fn test_markdown_sanitized_highlight() {
+    let s = MarkdownSanitizedHighlightTemplate {
+        content: "```rust\nfn main() {}\n```",
+    };
+    let html = s.render().unwrap();
+    assert!(html.starts_with("<pre style=\"background-color:#2b303b;\">"));
+    assert!(html.contains("<span style="));
+}
@@ -0,0 +1,86 @@
+use askama::Template;
+
+#[derive(Default)]
+struct AppCtx {
+    user: &'static str,
+    links: Vec<&'static str>,
+}
+
+#[derive(Template)]
+#[template(
+    source = "{{ title }} by {{ user }}:{% for link in links %} {{ link }}{% endfor %}",
+    ext = "txt",
+    context = "AppCtx"
+)]
+struct PageTemplate<'a> {
+    title: &'a str,
+}
+
+#[test]
+fn test_render_with() {
+    let ctx = AppCtx {
+        user: "alice",
+        links: vec!["home", "about"],
+    };
+    let t = PageTemplate { title: "Index" };
+    assert_eq!(t.render_with(&ctx).unwrap(), "Index by alice: home about");
+}
+
+#[derive(Template)]
+#[template(
+    source = "Hello, {{ user }}!",
+    ext = "txt",
+    context = "AppCtx",
+    default_context
+)]
+struct DefaultContextTemplate;
+
+#[test]
+fn test_default_context() {
+    let ctx = AppCtx {
+        user: "alice",
+        ..AppCtx::default()
+    };
+    assert_eq!(
+        DefaultContextTemplate.render_with(&ctx).unwrap(),
+        "Hello, alice!"
+    );
+    assert_eq!(DefaultContextTemplate.render().unwrap(), "Hello, !");
+    assert_eq!(DefaultContextTemplate.to_string(), "Hello, !");
+}
+
+#[derive(Template)]
+#[template(source = "{{ user }}", ext = "txt", context = "AppCtx")]
+struct ShadowTemplate {
+    user: &'static str,
+}
+
+#[test]
+fn test_field_shadows_context() {
+    let ctx = AppCtx {
+        user: "alice",
+        ..AppCtx::default()
+    };
+    let t = ShadowTemplate { user: "bob" };
+    assert_eq!(t.render_with(&ctx).unwrap(), "bob");
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% let _ctx = \"local\" %}{{ _ctx }} {{ user }}",
+    ext = "txt",
+    context = "AppCtx"
+)]
+struct LocalNamedCtxTemplate;
+
+#[test]
+fn test_local_named_ctx() {
+    let ctx = AppCtx {
+        user: "alice",
+        ..AppCtx::default()
+    };
+    assert_eq!(
+        LocalNamedCtxTemplate.render_with(&ctx).unwrap(),
+        "local alice"
+    );
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "ammonia")]
+
+use askama::Template;
+
+#[derive(Template)]
+#[template(source = "<div>{{ comment|sanitize }}</div>", ext = "html")]
+struct SanitizeTemplate<'a> {
+    comment: &'a str,
+}
+
+#[test]
+fn test_sanitize() {
+    let t = SanitizeTemplate {
+        comment: "<b onclick=\"evil()\">Hi</b><script>evil()</script> \
+                  <a href=\"https://example.com\">&lt;3</a>",
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "<div><b>Hi</b> \
+         <a href=\"https://example.com\" rel=\"noopener noreferrer\">&lt;3</a></div>"
+    );
+}
+
+#[derive(Template)]
+#[template(
+    source = "{{ comment|sanitize }}",
+    ext = "html",
+    config = "test_sanitize.toml"
+)]
+struct AllowlistTemplate<'a> {
+    comment: &'a str,
+}
+
+#[test]
+fn test_sanitize_allowlist() {
+    let t = AllowlistTemplate {
+        comment: "<p><b>Hi</b> <em>there</em>, <a href=\"http://example.com\" title=\"x\">\
+                  http</a> <a href=\"https://example.com\">https</a></p>",
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "<p>Hi <em>there</em>, <a rel=\"nofollow\">http</a> \
+         <a href=\"https://example.com\" rel=\"nofollow\">https</a></p>"
+    );
+}
+
+#[cfg(feature = "markdown")]
+#[derive(Template)]
+#[template(
+    source = "{{ content|markdown(options) }}",
+    ext = "html",
+    config = "test_sanitize.toml"
+)]
+struct MarkdownTemplate<'a> {
+    content: &'a str,
+    options: &'a comrak::ComrakOptions,
+}
+
+#[cfg(feature = "markdown")]
+#[test]
+fn test_sanitize_markdown() {
+    let mut options = comrak::ComrakOptions::default();
+    options.render.unsafe_ = true;
+    let t = MarkdownTemplate {
+        content: "**Hi** <em onclick=\"evil()\">there</em><script>evil()</script>",
+        options: &options,
+    };
+    assert_eq!(
+        t.render().unwrap(),
+        "<p><strong>Hi</strong> <em>there</em></p>\n"
+    );
+}
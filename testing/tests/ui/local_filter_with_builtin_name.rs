@@ -0,0 +1,16 @@
+use askama::Template;
+
+mod filters {
+    pub fn title(s: &str) -> ::askama::Result<String> {
+        Ok(format!("Dr. {s}"))
+    }
+}
+
+#[derive(Template)]
+#[template(source = "{{ name|title }}", ext = "txt")]
+struct LocalTitle<'a> {
+    name: &'a str,
+}
+
+fn main() {
+}
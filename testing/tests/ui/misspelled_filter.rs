@@ -0,0 +1,10 @@
+use askama::Template;
+
+#[derive(Template)]
+#[template(source = "{{ name|uper }}", ext = "txt")]
+struct MisspelledFilter<'a> {
+    name: &'a str,
+}
+
+fn main() {
+}
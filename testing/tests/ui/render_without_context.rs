@@ -0,0 +1,14 @@
+use askama::Template;
+
+#[derive(Default)]
+struct AppCtx {
+    user: String,
+}
+
+#[derive(Template)]
+#[template(source = "Hello, {{ user }}!", ext = "txt", context = "AppCtx")]
+struct HelloTemplate;
+
+fn main() {
+    let _ = HelloTemplate.render();
+}
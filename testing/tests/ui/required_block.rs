@@ -0,0 +1,8 @@
+use askama::Template;
+
+#[derive(Template)]
+#[template(source = "{% block title required %}{% endblock %}", ext = "txt")]
+struct RequiredBlock;
+
+fn main() {
+}
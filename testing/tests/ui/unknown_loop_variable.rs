@@ -0,0 +1,13 @@
+use askama::Template;
+
+#[derive(Template)]
+#[template(
+    source = "{% for v in values %}{{ loop.idnex }}{{ v }}{% endfor %}",
+    ext = "txt"
+)]
+struct UnknownLoopVariable<'a> {
+    values: &'a [&'a str],
+}
+
+fn main() {
+}
@@ -0,0 +1,11 @@
+use askama::Template;
+
+#[derive(Template)]
+#[template(
+    source = "{% macro button(label) %}[{{ label }}]{% endmacro %}{% call buton(\"ok\") %}",
+    ext = "txt"
+)]
+struct UnknownMacro;
+
+fn main() {
+}
@@ -0,0 +1,13 @@
+#![deny(deprecated)]
+
+use askama::Template;
+
+#[derive(Template)]
+#[template(source = "{{ name }}", ext = "txt", warn_unused_fields)]
+struct UnusedFields<'a> {
+    name: &'a str,
+    age: u32,
+}
+
+fn main() {
+}
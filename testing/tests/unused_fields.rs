@@ -0,0 +1,49 @@
+#![deny(deprecated)]
+
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "unused-fields.html", warn_unused_fields)]
+struct AllFieldsUsed<'a> {
+    title: &'a str,
+    body: &'a str,
+    count: usize,
+    suffix: &'a str,
+    note: &'a str,
+    footer: &'a str,
+}
+
+#[test]
+fn test_all_fields_used() {
+    let t = AllFieldsUsed {
+        title: "Title",
+        body: "Body",
+        count: 2,
+        suffix: "x",
+        note: "Note",
+        footer: "Footer",
+    };
+    assert_eq!(t.render().unwrap(), "Title Body 2x Note\nFooter");
+}
+
+#[derive(Template)]
+#[template(
+    source = "{% for item in items %}{{ item }}{% endfor %}{{ self.len() }}",
+    ext = "txt",
+    warn_unused_fields
+)]
+struct MethodCall {
+    items: Vec<usize>,
+}
+
+impl MethodCall {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+#[test]
+fn test_method_call() {
+    let t = MethodCall { items: vec![1, 2] };
+    assert_eq!(t.render().unwrap(), "122");
+}